@@ -0,0 +1,416 @@
+use crate::csv::parse_timestamp;
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{Builder, Library};
+use chrono::{Local, NaiveDate, TimeZone};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// External ids are AniList media ids, which anime and manga share.
+pub const SOURCE: &str = "anilist";
+/// `MediaListStatus` values in the order the data export numbers them.
+const STATUSES: [&str; 6] = [
+    "CURRENT",
+    "PLANNING",
+    "COMPLETED",
+    "DROPPED",
+    "PAUSED",
+    "REPEATING",
+];
+/// AniList media formats and the type they become, others keep their own name.
+const TYPES: [(&str, &str); 10] = [
+    ("TV", "TV"),
+    ("TV_SHORT", "TV Short"),
+    ("MOVIE", "Movie"),
+    ("SPECIAL", "Special"),
+    ("OVA", "OVA"),
+    ("ONA", "ONA"),
+    ("MUSIC", "Music"),
+    ("MANGA", "Manga"),
+    ("NOVEL", "Light Novel"),
+    ("ONE_SHOT", "One Shot"),
+];
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Responses(Vec<Response>),
+    Response(Response),
+    Gdpr(Gdpr),
+}
+
+/// The response to a `MediaListCollection` query.
+#[derive(serde::Deserialize)]
+struct Response {
+    data: Data,
+}
+
+#[derive(serde::Deserialize)]
+struct Data {
+    #[serde(rename = "MediaListCollection")]
+    collection: Collection,
+}
+
+#[derive(serde::Deserialize)]
+struct Collection {
+    lists: Vec<List>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct List {
+    #[serde(default)]
+    is_custom_list: bool,
+    entries: Vec<Entry>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    status: Option<String>,
+    progress: Option<i64>,
+    started_at: Option<FuzzyDate>,
+    completed_at: Option<FuzzyDate>,
+    /// Seconds since the epoch.
+    updated_at: Option<i64>,
+    created_at: Option<i64>,
+    media: Media,
+}
+
+#[derive(serde::Deserialize)]
+struct Media {
+    id: i64,
+    r#type: Option<String>,
+    format: Option<String>,
+    title: Title,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Title {
+    user_preferred: Option<String>,
+    romaji: Option<String>,
+    english: Option<String>,
+    native: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct FuzzyDate {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl FuzzyDate {
+    /// Midnight of the day, the first month or day standing in for a missing one.
+    fn timestamp(&self) -> Option<i64> {
+        let (month, day) = (self.month.unwrap_or(1), self.day.unwrap_or(1));
+        let date = NaiveDate::from_ymd_opt(self.year?, month, day)?;
+        let datetime = Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?;
+        Some(datetime.timestamp_millis())
+    }
+}
+
+/// The data export, which has no titles and numbers statuses and media types.
+#[derive(serde::Deserialize)]
+struct Gdpr {
+    lists: Vec<GdprEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct GdprEntry {
+    series_id: i64,
+    series_type: i64,
+    status: Option<usize>,
+    progress: Option<i64>,
+    created_at: Option<serde_json::Value>,
+    updated_at: Option<serde_json::Value>,
+}
+
+/// A time the data export wrote as seconds or as text.
+fn time(value: &Option<serde_json::Value>) -> Option<i64> {
+    match value.as_ref()? {
+        serde_json::Value::Number(seconds) => seconds.as_i64().map(|seconds| seconds * 1000),
+        serde_json::Value::String(text) => parse_timestamp(text).or_else(|| {
+            let datetime = chrono::DateTime::parse_from_rfc3339(text).ok()?;
+            Some(datetime.timestamp_millis())
+        }),
+        _ => None,
+    }
+}
+
+/// What a work is named when the export has no title for it.
+fn placeholder(kind: &str, id: impl std::fmt::Display) -> String {
+    format!("AniList {kind} {id}")
+}
+
+/// Whether `name` is what a work linked to the AniList id `external_id` is named without a
+/// title, so that an import with the title renames it.
+pub fn is_placeholder(external_id: &str, name: &str) -> bool {
+    ["Anime", "Manga"]
+        .iter()
+        .any(|kind| name == placeholder(kind, external_id))
+}
+
+/// One list entry however the export wrote it.
+struct Item {
+    id: i64,
+    manga: bool,
+    name: String,
+    format: Option<String>,
+    status: String,
+    progress: i64,
+    added: Option<i64>,
+    updated: Option<i64>,
+}
+
+fn items(export: Export) -> Vec<Item> {
+    let entries = |response: Response| {
+        let lists = response.data.collection.lists.into_iter();
+        lists
+            .filter(|list| !list.is_custom_list)
+            .flat_map(|list| list.entries)
+    };
+    let entries: Vec<Entry> = match export {
+        Export::Responses(responses) => responses.into_iter().flat_map(entries).collect(),
+        Export::Response(response) => entries(response).collect(),
+        Export::Gdpr(gdpr) => {
+            return gdpr
+                .lists
+                .into_iter()
+                .map(|entry| {
+                    let manga = entry.series_type == 1;
+                    let kind = if manga { "Manga" } else { "Anime" };
+                    Item {
+                        id: entry.series_id,
+                        manga,
+                        name: placeholder(kind, entry.series_id),
+                        format: None,
+                        status: STATUSES
+                            .get(entry.status.unwrap_or(1))
+                            .unwrap_or(&STATUSES[1])
+                            .to_string(),
+                        progress: entry.progress.unwrap_or(0),
+                        added: time(&entry.created_at),
+                        updated: time(&entry.updated_at),
+                    }
+                })
+                .collect();
+        }
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let title = entry.media.title;
+            let manga = entry.media.r#type.as_deref() == Some("MANGA");
+            let kind = if manga { "Manga" } else { "Anime" };
+            let name = [
+                title.user_preferred,
+                title.romaji,
+                title.english,
+                title.native,
+            ]
+            .into_iter()
+            .flatten()
+            .find(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| placeholder(kind, entry.media.id));
+            let seconds = |time: Option<i64>| Some(time.filter(|time| *time > 0)? * 1000);
+            Item {
+                id: entry.media.id,
+                manga,
+                name,
+                format: entry.media.format,
+                status: entry.status.unwrap_or_else(|| STATUSES[1].to_string()),
+                progress: entry.progress.unwrap_or(0),
+                added: entry
+                    .started_at
+                    .and_then(|date| date.timestamp())
+                    .or(seconds(entry.created_at)),
+                updated: entry
+                    .completed_at
+                    .and_then(|date| date.timestamp())
+                    .or(seconds(entry.updated_at)),
+            }
+        })
+        .collect()
+}
+
+/// The status an AniList status becomes, named like MyAnimeList names them, and whether works
+/// in it are updated.
+fn status(status: &str, manga: bool) -> (&str, bool) {
+    match (status, manga) {
+        ("CURRENT", false) => ("Watching", true),
+        ("CURRENT", true) => ("Reading", true),
+        ("PLANNING", false) => ("Plan to Watch", false),
+        ("PLANNING", true) => ("Plan to Read", false),
+        ("COMPLETED", _) => ("Completed", false),
+        ("DROPPED", _) => ("Dropped", false),
+        ("PAUSED", _) => ("On-Hold", false),
+        ("REPEATING", false) => ("Rewatching", true),
+        ("REPEATING", true) => ("Rereading", true),
+        (status, _) => (status, false),
+    }
+}
+
+/// Reads an AniList list export as a library, either the data export or the JSON response to
+/// a `MediaListCollection` query, or a list of them for anime and manga. Each entry becomes a
+/// work in its list status, named like MyAnimeList names them, with the episodes or chapters
+/// so far as progress. Works get their media format as type and the `Anime` or `Manga` format.
+/// The data export has no titles, its works are named after their AniList id until a synced
+/// import with titles renames them. Every work is linked to its AniList id so importing again
+/// updates it.
+pub fn parse(json: &str, now: i64) -> DatabaseResult<Library> {
+    let export = serde_json::from_str(json)
+        .map_err(|err| Error::Invalid(format!("Not an AniList export: {err}")))?;
+
+    let mut builder = Builder::new(now);
+    let mut seen = HashSet::new();
+    for item in items(export) {
+        if !seen.insert(item.id) {
+            continue;
+        }
+
+        let (status, is_update) = status(&item.status, item.manga);
+        let status = builder.status(status, is_update);
+        let kind = if item.manga { "Manga" } else { "Anime" };
+        let r#type = match item.format.as_deref() {
+            Some(format) => {
+                let name = TYPES.iter().find(|(id, _)| *id == format);
+                name.map_or(format, |(_, name)| name)
+            }
+            None => kind,
+        };
+        let r#type = builder.r#type(r#type);
+        let format = builder.format(kind);
+        let added = item.added.unwrap_or(now);
+
+        let work_id = builder.work(Work {
+            id: 0,
+            name: item.name,
+            progress: item.progress.to_string(),
+            status,
+            r#type,
+            format,
+            updated: item.updated.unwrap_or(added),
+            added,
+            creators: vec![],
+            tags: vec![],
+        });
+        builder.link(work_id, SOURCE, item.id.to_string());
+    }
+    Ok(builder.build())
+}
+
+pub fn read(path: &Path, now: i64) -> DatabaseResult<Library> {
+    parse(&std::fs::read_to_string(path)?, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{"data": {"MediaListCollection": {"lists": [
+        {"name": "Watching", "isCustomList": false, "entries": [{
+            "status": "CURRENT", "progress": 12, "updatedAt": 1700000000, "createdAt": 1600000000,
+            "startedAt": {"year": 2023, "month": 1, "day": 2},
+            "completedAt": {"year": null, "month": null, "day": null},
+            "media": {"id": 1, "type": "ANIME", "format": "TV",
+                "title": {"userPreferred": "Cowboy Bebop", "romaji": "Cowboy Bebop"}}
+        }]},
+        {"name": "Favourites", "isCustomList": true, "entries": [{
+            "status": "CURRENT", "progress": 12,
+            "media": {"id": 1, "type": "ANIME", "format": "TV", "title": {"romaji": "Bebop"}}
+        }]},
+        {"name": "Planning", "isCustomList": false, "entries": [{
+            "status": "PLANNING", "progress": 0,
+            "media": {"id": 2, "type": "MANGA", "format": "NOVEL",
+                "title": {"userPreferred": null, "english": "Spice and Wolf"}}
+        }]}
+    ]}}}"#;
+
+    const GDPR: &str = r#"{"user": {"id": 4}, "lists": [
+        {"series_id": 30, "series_type": 1, "status": 2, "progress": 95,
+            "created_at": 1600000000, "updated_at": "2021-05-06 07:08:09"}
+    ]}"#;
+
+    #[test]
+    fn reads_query_response() -> Result<(), Box<dyn std::error::Error>> {
+        let library = parse(RESPONSE, 7)?;
+
+        assert_eq!(Library::names(&library.types), vec!["TV", "Light Novel"]);
+        assert_eq!(Library::names(&library.formats), vec!["Anime", "Manga"]);
+        assert_eq!(library.status_names(), vec!["Watching", "Plan to Read"]);
+
+        let [bebop, spice] = library.expect_works();
+        assert_eq!(
+            (bebop.name.as_str(), bebop.progress.as_str()),
+            ("Cowboy Bebop", "12")
+        );
+        assert_eq!(bebop.added, parse_timestamp("2023-01-02").unwrap());
+        assert_eq!(bebop.updated, 1_700_000_000_000);
+        assert_eq!(spice.name, "Spice and Wolf");
+        assert_eq!((spice.added, spice.updated), (7, 7));
+        assert_eq!(library.external_ids[1].external_id, "2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_data_export() -> Result<(), Box<dyn std::error::Error>> {
+        let library = parse(GDPR, 7)?;
+
+        let work = &library.works[0];
+        assert_eq!(
+            (work.name.as_str(), work.progress.as_str()),
+            ("AniList Manga 30", "95")
+        );
+        assert_eq!(work.added, 1_600_000_000_000);
+        assert_eq!(
+            work.updated,
+            parse_timestamp("2021-05-06 07:08:09").unwrap()
+        );
+        assert_eq!(library.statuses[0].name, "Completed");
+
+        assert!(matches!(
+            parse(r#"{"works": []}"#, 0),
+            Err(Error::Invalid(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn titles_rename_data_export_works() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::library::{import, ImportMode};
+        use crate::store::LibraryStore;
+
+        let mut store = crate::database::Database::default();
+        store.open_in_memory()?;
+        let titled = RESPONSE.replace(r#""id": 2,"#, r#""id": 30,"#);
+        let name = || -> Result<String, Box<dyn std::error::Error>> {
+            let ids = store.get_external_ids()?;
+            let work_id = ids
+                .iter()
+                .find(|row| row.external_id == "30")
+                .map(|row| row.work_id);
+            let works = store.get_works()?;
+            let work = works.into_iter().find(|work| Some(work.id) == work_id);
+            Ok(work.map(|work| work.name).unwrap_or_default())
+        };
+
+        import(&store, &parse(GDPR, 7)?, ImportMode::Sync, false)?;
+        assert_eq!(name()?, "AniList Manga 30");
+
+        import(&store, &parse(&titled, 7)?, ImportMode::Sync, false)?;
+        assert_eq!(name()?, "Spice and Wolf");
+
+        import(&store, &parse(GDPR, 7)?, ImportMode::Sync, false)?;
+        assert_eq!(name()?, "Spice and Wolf");
+        assert_eq!(store.get_works()?.len(), 2);
+
+        Ok(())
+    }
+}
@@ -1,763 +1,1510 @@
-use tauri::Emitter;
 use crate::{
+    backup::{Backup, BackupSettings},
+    calibre,
+    commands::{self, Changed},
     config::Config,
-    database::{Creator, Database, Format, Status, Type, Work},
-    menu::set_menu_state
+    csv::{ColumnMapping, CsvImportReport, CsvPreview, Duplicates, Sheet},
+    database::{
+        self, BulkEdit, BulkResult, Changes, Creator, Database, ExportMapping, Format, StaleWork,
+        Status, Tag, Type, Work,
+    },
+    entity::{
+        CreatorColumn, FormatColumn, NewFormat, NewStatus, NewTag, NewType, Sorted, StatusColumn,
+        Table, TagColumn, TypeColumn, WorkColumn,
+    },
+    error::Error,
+    integrity::IntegrityReport,
+    library::{ImportMode, ImportReport, Source},
+    pool::Pool,
+    restore::{self, RestorePreview},
+    schedule::{Release, ReleaseSchedule, ScheduleState},
+    service::{ExportReport, MappingOptions, Service},
+    store::LibraryStore,
+    window::{self, set_menus_state, View, MAIN_WINDOW},
 };
 use std::{path::PathBuf, sync::Mutex};
+use tauri::{Emitter, Manager};
 
+/// Runs `f` on the writer and emits the changes it made once they are committed. The edit is
+/// done by then, so a change that can't be emitted is only logged.
+async fn write_changes<T: Send + 'static>(
+    app: tauri::AppHandle,
+    pool: &Pool,
+    f: impl FnOnce(&Database) -> Result<Changed<T>, Error> + Send + 'static,
+) -> Result<T, Error> {
+    let (value, changes) = pool.write(f).await?;
+    for change in changes {
+        if let Err(err) = change.emit(&app) {
+            log::error!("Failed to emit change: {err}.");
+        }
+    }
+    Ok(value)
+}
 
+/// Runs `f` on a blocking thread so that it doesn't hold up the window.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    tauri::async_runtime::spawn_blocking(f).await?
+}
 
 #[tauri::command]
-pub fn open_database(
+pub async fn open_database(
     app: tauri::AppHandle,
-    window: tauri::WebviewWindow,
-    config: tauri::State<Mutex<Config>>,
-    database: tauri::State<Mutex<Database>>,
+    config: tauri::State<'_, Mutex<Config>>,
+    pool: tauri::State<'_, Pool>,
     path: PathBuf,
-) -> Result<(), String> {
+    password: Option<String>,
+) -> Result<(), Error> {
     log::info!("Opening database: PATH - {path:?}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let mut db_guard = database.lock().unwrap();
-        db_guard.open(&path)?;
+    let inner = async {
+        let now = chrono::Local::now();
+        let backup = config.lock().unwrap().backups.path(&path, now);
+        let (opened, pool) = (path.clone(), pool.inner().clone());
+        blocking(move || pool.open(&opened, Some(&backup), password.as_deref())).await?;
 
         let mut config_guard = config.lock().unwrap();
+        if let Err(err) = config_guard.backups.prune(&path, now) {
+            log::error!("Failed to prune backups: {err}.");
+        }
+
         config_guard.last_database = Some(path.to_path_buf());
+        config_guard.add_recent_database(path.to_path_buf());
+        window::set_recent_menus(&app, &config_guard.recent_databases)?;
 
-        set_menu_state(&window.menu().unwrap(), true)?;
+        set_menus_state(&app, true)?;
 
-        window.set_title(&format!("zero - {}", path.display()))?;
+        if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+            window.set_title(&format!("zero - {}", path.display()))?;
+        }
 
-        Ok(app.emit(crate::event::OPENED_DATABASE_EVENT, path)?)
+        tauri::async_runtime::spawn(crate::menu::report_integrity(app.clone()));
+        Ok(app.emit(crate::event::OPENED_DATABASE_EVENT, &path)?)
     };
 
-    match inner() {
+    match inner.await {
         Ok(()) => Ok(()),
+        Err(Error::WrongKey) => {
+            log::warn!("Failed to open database: {}.", Error::WrongKey);
+            if let Err(err) = app.emit(crate::event::PASSWORD_REQUIRED_EVENT, &path) {
+                log::error!("Failed to ask for password: {err}.");
+            }
+            Err(Error::WrongKey)
+        }
         Err(err) => {
-            let message = format!("Failed to open database: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to open database: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn close_database(
+pub async fn close_database(
     app: tauri::AppHandle,
-    window: tauri::WebviewWindow,
-    config: tauri::State<Mutex<Config>>,
-    database: tauri::State<Mutex<Database>>,
-) -> Result<(), String> {
+    config: tauri::State<'_, Mutex<Config>>,
+    pool: tauri::State<'_, Pool>,
+) -> Result<(), Error> {
     log::info!("Closing database.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let mut db_guard = database.lock().unwrap();
-        db_guard.close()?;
+    let inner = async {
+        let pool = pool.inner().clone();
+        blocking(move || pool.close()).await?;
 
         let mut config_guard = config.lock().unwrap();
         config_guard.last_database = None;
 
-        set_menu_state(&window.menu().unwrap(), false)?;
+        set_menus_state(&app, false)?;
 
-        window.set_title("zero")?;
+        if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+            window.set_title("zero")?;
+        }
 
         Ok(app.emit(crate::event::CLOSED_DATABASE_EVENT, ())?)
     };
 
-    match inner() {
+    match inner.await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to close database: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to close database: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn database_path(database: tauri::State<Mutex<Database>>) -> Option<std::path::PathBuf> {
+pub fn database_path(pool: tauri::State<Pool>) -> Option<std::path::PathBuf> {
     log::info!("Getting current database path.");
-    let guard = database.lock().unwrap();
-    guard.path()
+    pool.path()
 }
 
+/// The last database when it is still waiting for its password after startup.
 #[tauri::command]
-pub fn get_works(database: tauri::State<Mutex<Database>>) -> Result<Vec<Work>, String> {
-    log::info!("Getting works.");
+pub fn locked_database(
+    config: tauri::State<Mutex<Config>>,
+    pool: tauri::State<Pool>,
+) -> Option<PathBuf> {
+    log::info!("Getting locked database path.");
+    match pool.path() {
+        Some(_) => None,
+        None => config.lock().unwrap().last_database.clone(),
+    }
+}
+
+#[tauri::command]
+pub async fn set_password(
+    config: tauri::State<'_, Mutex<Config>>,
+    pool: tauri::State<'_, Pool>,
+    password: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Setting database password.");
+
+    let inner = async {
+        let path = pool.path().ok_or(Error::NoDatabaseOpen)?;
+        let backups = config.lock().unwrap().backups.list(&path)?;
+        let pool = pool.inner().clone();
+
+        blocking(move || {
+            let old = pool.key();
+            let new = password.as_deref().filter(|password| !password.is_empty());
+            pool.set_key(new)?;
+
+            // Backups would otherwise stay readable with the old password, or without one.
+            for backup in backups {
+                if let Err(err) = database::rekey(&backup.path, old.as_deref(), new) {
+                    log::error!("Failed to set password of backup {:?}: {err}.", backup.path);
+                }
+            }
+            Ok(())
+        })
+        .await
+    };
+
+    match inner.await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to set database password: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// Backups of the open database, newest first, with their rows when the current password opens
+/// them.
+#[tauri::command]
+pub async fn list_backups(
+    config: tauri::State<'_, Mutex<Config>>,
+    pool: tauri::State<'_, Pool>,
+) -> Result<Vec<Backup>, Error> {
+    log::info!("Listing backups.");
+
+    let inner = async {
+        let path = pool.path().ok_or(Error::NoDatabaseOpen)?;
+        let mut backups = config.lock().unwrap().backups.list(&path)?;
+        let key = pool.key();
+
+        blocking(move || {
+            for backup in &mut backups {
+                let rows = restore::backup_key(&backup.path, key.as_deref(), None)
+                    .and_then(|key| restore::rows(&backup.path, key));
+                match rows {
+                    Ok(rows) => backup.rows = Some(rows),
+                    Err(Error::WrongKey) => {}
+                    Err(err) => log::warn!("Failed to read backup {:?}: {err}.", backup.path),
+                }
+            }
+            Ok(backups)
+        })
+        .await
+    };
+
+    match inner.await {
+        Ok(backups) => Ok(backups),
+        Err(err) => {
+            log::error!("Failed to list backups: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_backup_settings(config: tauri::State<Mutex<Config>>) -> BackupSettings {
+    log::info!("Getting backup settings.");
+    config.lock().unwrap().backups.clone()
+}
 
-    let inner = || -> Result<Vec<Work>, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.get_works()
+#[tauri::command]
+pub fn set_backup_settings(config: tauri::State<Mutex<Config>>, settings: BackupSettings) {
+    log::info!("Setting backup settings: SETTINGS - {settings:?}.");
+    config.lock().unwrap().backups = settings;
+}
+
+/// What restoring the backup at `path` would change. `password` is the one the backup was taken
+/// under, when it isn't the current one.
+#[tauri::command]
+pub async fn preview_restore(
+    pool: tauri::State<'_, Pool>,
+    path: PathBuf,
+    password: Option<String>,
+) -> Result<RestorePreview, Error> {
+    log::info!("Previewing restore: PATH - {path:?}.");
+
+    let inner = async {
+        let current = pool.path().ok_or(Error::NoDatabaseOpen)?;
+        let key = pool.key();
+
+        blocking(move || {
+            let backup_key = restore::backup_key(&path, key.as_deref(), password.as_deref())?;
+            restore::preview(&current, &path, key.as_deref(), backup_key)
+        })
+        .await
     };
 
-    match inner() {
+    match inner.await {
+        Ok(preview) => Ok(preview),
+        Err(err) => {
+            log::error!("Failed to preview restore: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// Replaces the open database with the backup at `path`, which keeps the current password.
+/// `password` is the one the backup was taken under, when it isn't the current one.
+#[tauri::command]
+pub async fn restore_backup(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<Config>>,
+    pool: tauri::State<'_, Pool>,
+    path: PathBuf,
+    password: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Restoring backup: PATH - {path:?}.");
+
+    let inner = async {
+        let current = pool.path().ok_or(Error::NoDatabaseOpen)?;
+        let previous = config
+            .lock()
+            .unwrap()
+            .backups
+            .path(&current, chrono::Local::now());
+        if previous == path {
+            return Err(Error::Invalid(
+                "Backup would be replaced by itself".to_string(),
+            ));
+        }
+        let restoring = pool.inner().clone();
+        blocking(move || {
+            let key = restoring.key();
+            let backup_key = restore::backup_key(&path, key.as_deref(), password.as_deref())?;
+            restoring.restore(&path, &previous, backup_key)
+        })
+        .await?;
+        tauri::async_runtime::spawn(crate::menu::report_integrity(app.clone()));
+
+        // Every window reloads its data when the database is closed and opened again.
+        app.emit(crate::event::CLOSED_DATABASE_EVENT, ())?;
+        Ok(app.emit(crate::event::OPENED_DATABASE_EVENT, current)?)
+    };
+
+    match inner.await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to restore backup: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn check_integrity(pool: tauri::State<'_, Pool>) -> Result<IntegrityReport, Error> {
+    log::info!("Checking database integrity.");
+
+    let inner = move |db: &Database| -> Result<IntegrityReport, Error> { db.check_integrity() };
+
+    match pool.read(inner).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            log::error!("Failed to check database integrity: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn repair_database(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+) -> Result<IntegrityReport, Error> {
+    log::info!("Repairing database.");
+
+    match write_changes(app, &pool, commands::repair).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            log::error!("Failed to repair database: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn export_library(pool: tauri::State<'_, Pool>, path: PathBuf) -> Result<(), Error> {
+    log::info!("Exporting library: PATH - {path:?}.");
+
+    let inner = move |db: &Database| {
+        commands::export_library(db, &path, chrono::Local::now().timestamp_millis())
+    };
+
+    match pool.read(inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to export library: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// Merges the library in the `source` file at `path` into the open database, or into a new one
+/// at `target` when given, `mode` deciding what happens to works it already has. A dry run only
+/// reports what the import would do.
+#[tauri::command]
+pub async fn import_library(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<Config>>,
+    pool: tauri::State<'_, Pool>,
+    path: PathBuf,
+    source: Source,
+    mode: ImportMode,
+    target: Option<PathBuf>,
+    dry_run: bool,
+) -> Result<ImportReport, Error> {
+    log::info!(
+        "Importing library: PATH - {path:?}, SOURCE - {source:?}, MODE - {mode:?}, \
+         TARGET - {target:?}, DRY RUN - {dry_run}."
+    );
+
+    let inner = async {
+        let library = source.read(&path, chrono::Local::now().timestamp_millis())?;
+        match target {
+            Some(target) if target.try_exists()? => Err(Error::Invalid(format!(
+                "'{}' already exists",
+                target.display()
+            ))),
+            Some(_) if dry_run => {
+                let mut db = Database::default();
+                db.open_in_memory()?;
+                let report = commands::import_library(&db, &library, mode, false);
+                db.close()?;
+                Ok(report?.0)
+            }
+            target => {
+                if let Some(target) = target {
+                    open_database(app.clone(), config, app.state::<Pool>(), target, None).await?;
+                }
+                let inner =
+                    move |db: &Database| commands::import_library(db, &library, mode, dry_run);
+                write_changes(app, &pool, inner).await
+            }
+        }
+    };
+
+    match inner.await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            log::error!("Failed to import library: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// Custom columns of the Calibre library in `path` that can hold read statuses.
+#[tauri::command]
+pub async fn calibre_columns(path: PathBuf) -> Result<Vec<calibre::Column>, Error> {
+    log::info!("Reading Calibre columns: PATH - {path:?}.");
+
+    match blocking(move || calibre::columns(&path)).await {
+        Ok(columns) => Ok(columns),
+        Err(err) => {
+            log::error!("Failed to read Calibre columns: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn export_csv(pool: tauri::State<'_, Pool>, path: PathBuf) -> Result<(), Error> {
+    log::info!("Exporting CSV: PATH - {path:?}.");
+
+    let inner = move |db: &Database| commands::export_csv(db, &path);
+
+    match pool.read(inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to export CSV: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// The values `service` has for statuses and types along with what each of ours maps to.
+#[tauri::command]
+pub async fn get_export_mapping(
+    pool: tauri::State<'_, Pool>,
+    service: Service,
+) -> Result<MappingOptions, Error> {
+    log::info!("Getting export mapping: SERVICE - {service:?}.");
+
+    let inner = move |db: &Database| commands::get_export_mapping(db, service);
+
+    match pool.read(inner).await {
+        Ok(options) => Ok(options),
+        Err(err) => {
+            log::error!("Failed to get {service:?} export mapping: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_export_mapping(
+    pool: tauri::State<'_, Pool>,
+    service: Service,
+    mapping: ExportMapping,
+) -> Result<(), Error> {
+    log::info!("Setting export mapping: SERVICE - {service:?}, MAPPING - {mapping:?}.");
+
+    let inner = move |db: &Database| commands::set_export_mapping(db, service, mapping);
+
+    match pool.write(inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to set {service:?} export mapping: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// Writes the anime list, or manga list when `manga` is set, for MyAnimeList to import.
+#[tauri::command]
+pub async fn export_mal(
+    pool: tauri::State<'_, Pool>,
+    path: PathBuf,
+    manga: bool,
+) -> Result<ExportReport, Error> {
+    log::info!("Exporting MyAnimeList: PATH - {path:?}, MANGA - {manga}.");
+
+    let inner = move |db: &Database| commands::export_mal(db, &path, manga);
+
+    match pool.read(inner).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            log::error!("Failed to export MyAnimeList: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn export_goodreads(
+    pool: tauri::State<'_, Pool>,
+    path: PathBuf,
+) -> Result<ExportReport, Error> {
+    log::info!("Exporting Goodreads: PATH - {path:?}.");
+
+    let inner = move |db: &Database| commands::export_goodreads(db, &path);
+
+    match pool.read(inner).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            log::error!("Failed to export Goodreads: {err}.");
+            Err(err)
+        }
+    }
+}
+
+/// The headers and first rows of the CSV file at `path`, with a mapping of its columns when
+/// they are named like the exported ones.
+#[tauri::command]
+pub async fn preview_csv(path: PathBuf) -> Result<CsvPreview, Error> {
+    log::info!("Previewing CSV: PATH - {path:?}.");
+
+    match blocking(move || Sheet::read(&path)).await {
+        Ok(sheet) => Ok(sheet.into()),
+        Err(err) => {
+            log::error!("Failed to preview CSV: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn import_csv(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    path: PathBuf,
+    mapping: ColumnMapping,
+    duplicates: Duplicates,
+    dry_run: bool,
+) -> Result<CsvImportReport, Error> {
+    log::info!("Importing CSV: PATH - {path:?}, DUPLICATES - {duplicates:?}, DRY RUN - {dry_run}.");
+
+    let inner = move |db: &Database| {
+        let sheet = Sheet::read(&path)?;
+        let now = chrono::Local::now().timestamp_millis();
+        commands::import_csv(db, &sheet, &mapping, duplicates, now, dry_run)
+    };
+
+    match write_changes(app, &pool, inner).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            log::error!("Failed to import CSV: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_works(pool: tauri::State<'_, Pool>) -> Result<Vec<Work>, Error> {
+    log::info!("Getting works.");
+
+    let inner = move |db: &Database| -> Result<Vec<Work>, Error> { db.get_works() };
+
+    match pool.read(inner).await {
         Ok(works) => Ok(works),
         Err(err) => {
-            let message = format!("Failed to get works: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to get works: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn get_creators(database: tauri::State<Mutex<Database>>) -> Result<Vec<Creator>, String> {
+pub async fn get_creators(pool: tauri::State<'_, Pool>) -> Result<Vec<Creator>, Error> {
     log::info!("Getting creators.");
 
-    let inner = || -> Result<Vec<Creator>, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.get_creators()
-    };
+    let inner = move |db: &Database| -> Result<Vec<Creator>, Error> { db.get_creators() };
 
-    match inner() {
+    match pool.read(inner).await {
         Ok(creators) => Ok(creators),
         Err(err) => {
-            let message = format!("Failed to get creators: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to get creators: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_work_name(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_work_name(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     name: String,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating work [{id}]: NAME - {name}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("works", &id, vec![("name", &name)])
+    let inner = move |db: &Database| {
+        let columns = [WorkColumn::Name(name)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update work [{id}] name: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update work [{id}] name: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_work_progress(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_work_progress(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     progress: String,
     timestamp: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating work [{id}]: PROGRESS - {progress}, TIMESTAMP - {timestamp}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update(
-            "works",
-            &id,
-            vec![("progress", &progress), ("updated", &timestamp)],
-        )?;
-        Ok(())
+    let inner = move |db: &Database| {
+        let columns = [
+            WorkColumn::Progress(progress),
+            WorkColumn::Updated(timestamp),
+        ];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update work [{id}] progress: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update work [{id}] progress: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_work_status(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_work_status(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     status: i64,
     timestamp: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating work [{id}]: STATUS - {status}, TIMESTAMP - {timestamp}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update(
-            "works",
-            &id,
-            vec![("status", &status), ("updated", &timestamp)],
-        )?;
-        Ok(())
+    let inner = move |db: &Database| {
+        let columns = [WorkColumn::Status(status), WorkColumn::Updated(timestamp)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update work [{id}] status: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update work [{id}] status: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_work_type(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_work_type(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     r#type: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating work [{id}]: TYPE - {}.", r#type);
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("works", &id, vec![("type", &r#type)])
+    let inner = move |db: &Database| {
+        let columns = [WorkColumn::Type(r#type)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update work [{id}] type: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update work [{id}] type: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_work_format(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_work_format(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     format: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating work [{id}]: FORMAT - {}.", format);
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("works", &id, vec![("format", &format)])
+    let inner = move |db: &Database| {
+        let columns = [WorkColumn::Format(format)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update work [{id}] format: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update work [{id}] format: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_creator_name(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_creator_name(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     name: String,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating creator [{id}]: NAME - {name}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("creators", &id, vec![("name", &name)])
+    let inner = move |db: &Database| {
+        let columns = [CreatorColumn::Name(name)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update creator [{id}] name: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update creator [{id}] name: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn add_work(database: tauri::State<Mutex<Database>>, work: Work) -> Result<i64, String> {
+pub async fn add_work(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    work: Work,
+) -> Result<i64, Error> {
     log::info!("Adding work: WORK - {work:?}.");
 
-    let inner = || -> Result<i64, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        let work_id = guard.add(
-            "works",
-            vec![
-                ("name", &work.name),
-                ("progress", &work.progress),
-                ("status", &work.status),
-                ("type", &work.r#type),
-                ("format", &work.format),
-                ("updated", &work.updated),
-                ("added", &work.added),
-            ],
-        )?;
-        work.creators
-            .iter()
-            .try_for_each(|creator_id| guard.attach(work_id, *creator_id))?;
-        Ok(work_id)
-    };
+    let inner = move |db: &Database| commands::add_work(db, &work);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(id) => {
             log::info!("Added work: ID - {id}.");
             Ok(id)
         }
         Err(err) => {
-            let message = format!("Failed to add work: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to add work: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn add_creator(
-    database: tauri::State<Mutex<Database>>,
+pub async fn add_creator(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     creator: Creator,
-) -> Result<i64, String> {
+) -> Result<i64, Error> {
     log::info!("Adding creator: CREATOR - {creator:?}.");
 
-    let inner = || -> Result<i64, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        let creator_id = guard.add("creators", vec![("name", &creator.name)])?;
-        creator
-            .works
-            .iter()
-            .try_for_each(|work_id| guard.attach(*work_id, creator_id))?;
-        Ok(creator_id)
-    };
+    let inner = move |db: &Database| commands::add_creator(db, &creator);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(id) => {
             log::info!("Added creator: ID - {id}.");
             Ok(id)
         }
         Err(err) => {
-            let message = format!("Failed to add creator: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to add creator: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn add_status(database: tauri::State<Mutex<Database>>, name: String) -> Result<i64, String> {
+pub async fn add_status(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    name: String,
+) -> Result<i64, Error> {
     log::info!("Adding status: {name}.");
 
-    let inner = || -> Result<i64, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.add("statuses", vec![("name", &name)])
+    let inner = move |db: &Database| {
+        commands::add(
+            db,
+            &NewStatus {
+                name,
+                ..Default::default()
+            },
+        )
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(id) => {
             log::info!("Added status: ID - {id}.");
             Ok(id)
         }
         Err(err) => {
-            let message = format!("Failed to add status: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to add status: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn get_statuses(database: tauri::State<Mutex<Database>>) -> Result<Vec<Status>, String> {
+pub async fn get_statuses(pool: tauri::State<'_, Pool>) -> Result<Vec<Status>, Error> {
     log::info!("Getting statuses.");
 
-    let inner = || -> Result<Vec<Status>, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.get_statuses()
-    };
+    let inner = move |db: &Database| -> Result<Vec<Status>, Error> { db.get_statuses() };
 
-    match inner() {
+    match pool.read(inner).await {
         Ok(statuses) => Ok(statuses),
         Err(err) => {
-            let message = format!("Failed to get statuses: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to get statuses: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_status_name(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_status_name(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     name: String,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating status [{id}]: NAME - {name}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("statuses", &id, vec![("name", &name)])
+    let inner = move |db: &Database| {
+        let columns = [StatusColumn::Name(name)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update status [{id}] name: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update status [{id}] name: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_status_is_update(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_status_is_update(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     is_update: bool,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating status [{id}]: IS_UPDATE - {is_update}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("statuses", &id, vec![("is_update", &is_update)])
+    let inner = move |db: &Database| {
+        let columns = [StatusColumn::IsUpdate(is_update)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update status [{id}] is_update: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update status [{id}] is_update: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn reorder_statuses(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_status_stale_after(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+    stale_after: Option<i64>,
+) -> Result<(), Error> {
+    log::info!("Updating status [{id}]: STALE_AFTER - {stale_after:?}.");
+
+    let inner = move |db: &Database| {
+        let columns = [StatusColumn::StaleAfter(stale_after)];
+        commands::update(db, id, &columns)
+    };
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to update status [{id}] stale_after: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn reorder_statuses(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     active_id: i64,
     over_id: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Reordering statuses [{active_id} -> {over_id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.reorder("statuses", &active_id, &over_id)
-    };
+    let inner = move |db: &Database| commands::reorder(db, Sorted::Statuses, active_id, over_id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to reorder statuses [{active_id} -> {over_id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to reorder statuses [{active_id} -> {over_id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn remove_work(database: tauri::State<Mutex<Database>>, id: i64) -> Result<(), String> {
+pub async fn remove_work(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+) -> Result<(), Error> {
     log::info!("Removing work [{id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.remove("works", id)
-    };
+    let inner = move |db: &Database| commands::remove(db, Table::Works, id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to remove work [{id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to remove work [{id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn remove_creator(database: tauri::State<Mutex<Database>>, id: i64) -> Result<(), String> {
+pub async fn remove_creator(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+) -> Result<(), Error> {
     log::info!("Removing creator [{id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.remove("creators", id)
-    };
+    let inner = move |db: &Database| commands::remove(db, Table::Creators, id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to remove creator [{id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to remove creator [{id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn remove_status(database: tauri::State<Mutex<Database>>, id: i64) -> Result<(), String> {
+pub async fn remove_status(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+) -> Result<(), Error> {
     log::info!("Removing status [{id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.remove("statuses", id)
-    };
+    let inner = move |db: &Database| commands::remove(db, Table::Statuses, id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to remove status [{id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to remove status [{id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn add_type(database: tauri::State<Mutex<Database>>, name: String) -> Result<i64, String> {
+pub async fn add_type(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    name: String,
+) -> Result<i64, Error> {
     log::info!("Adding type: {name}.");
 
-    let inner = || -> Result<i64, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.add("types", vec![("name", &name)])
-    };
+    let inner = move |db: &Database| commands::add(db, &NewType { name });
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(id) => {
             log::info!("Added type: ID - {id}.");
             Ok(id)
         }
         Err(err) => {
-            let message = format!("Failed to add type: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to add type: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn get_types(database: tauri::State<Mutex<Database>>) -> Result<Vec<Type>, String> {
+pub async fn get_types(pool: tauri::State<'_, Pool>) -> Result<Vec<Type>, Error> {
     log::info!("Getting types.");
 
-    let inner = || -> Result<Vec<Type>, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.get_types()
-    };
+    let inner = move |db: &Database| -> Result<Vec<Type>, Error> { db.get_types() };
 
-    match inner() {
+    match pool.read(inner).await {
         Ok(types) => Ok(types),
         Err(err) => {
-            let message = format!("Failed to get types: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to get types: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn remove_type(database: tauri::State<Mutex<Database>>, id: i64) -> Result<(), String> {
+pub async fn remove_type(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+) -> Result<(), Error> {
     log::info!("Removing type [{id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.remove("types", id)
-    };
+    let inner = move |db: &Database| commands::remove(db, Table::Types, id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to remove type [{id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to remove type [{id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_type_name(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_type_name(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     name: String,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating type [{id}]: NAME - {name}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("types", &id, vec![("name", &name)])
+    let inner = move |db: &Database| {
+        let columns = [TypeColumn::Name(name)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update type [{id}] name: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update type [{id}] name: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn reorder_types(
-    database: tauri::State<Mutex<Database>>,
+pub async fn reorder_types(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     active_id: i64,
     over_id: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Reordering types [{active_id} -> {over_id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.reorder("types", &active_id, &over_id)
-    };
+    let inner = move |db: &Database| commands::reorder(db, Sorted::Types, active_id, over_id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to reorder types [{active_id} -> {over_id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to reorder types [{active_id} -> {over_id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn add_format(database: tauri::State<Mutex<Database>>, name: String) -> Result<i64, String> {
+pub async fn add_format(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    name: String,
+) -> Result<i64, Error> {
     log::info!("Adding format: {name}.");
 
-    let inner = || -> Result<i64, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.add("formats", vec![("name", &name)])
-    };
+    let inner = move |db: &Database| commands::add(db, &NewFormat { name });
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(id) => {
             log::info!("Added format: ID - {id}.");
             Ok(id)
         }
         Err(err) => {
-            let message = format!("Failed to add format: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to add format: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn get_formats(database: tauri::State<Mutex<Database>>) -> Result<Vec<Format>, String> {
+pub async fn get_formats(pool: tauri::State<'_, Pool>) -> Result<Vec<Format>, Error> {
     log::info!("Getting formats.");
 
-    let inner = || -> Result<Vec<Format>, Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.get_formats()
-    };
+    let inner = move |db: &Database| -> Result<Vec<Format>, Error> { db.get_formats() };
 
-    match inner() {
+    match pool.read(inner).await {
         Ok(formats) => Ok(formats),
         Err(err) => {
-            let message = format!("Failed to get formats: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to get formats: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn remove_format(database: tauri::State<Mutex<Database>>, id: i64) -> Result<(), String> {
+pub async fn remove_format(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+) -> Result<(), Error> {
     log::info!("Removing format [{id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.remove("formats", id)
-    };
+    let inner = move |db: &Database| commands::remove(db, Table::Formats, id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to remove format [{id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to remove format [{id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn update_format_name(
-    database: tauri::State<Mutex<Database>>,
+pub async fn update_format_name(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     id: i64,
     name: String,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Updating format [{id}]: NAME - {name}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.update("formats", &id, vec![("name", &name)])
+    let inner = move |db: &Database| {
+        let columns = [FormatColumn::Name(name)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to update format [{id}] name: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to update format [{id}] name: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn reorder_formats(
-    database: tauri::State<Mutex<Database>>,
+pub async fn reorder_formats(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     active_id: i64,
     over_id: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Reordering formats [{active_id} -> {over_id}].");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.reorder("formats", &active_id, &over_id)
-    };
+    let inner = move |db: &Database| commands::reorder(db, Sorted::Formats, active_id, over_id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to reorder formats [{active_id} -> {over_id}]: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to reorder formats [{active_id} -> {over_id}]: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn attach(
-    database: tauri::State<Mutex<Database>>,
+pub async fn attach(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     work_id: i64,
     creator_id: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Attaching: WORK_ID - {work_id}, CREATOR_ID - {creator_id}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.attach(work_id, creator_id)
-    };
+    let inner = move |db: &Database| commands::attach(db, work_id, creator_id);
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to attach: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to attach: {err}.");
+            Err(err)
         }
     }
 }
 
 #[tauri::command]
-pub fn detach(
-    database: tauri::State<Mutex<Database>>,
+pub async fn detach(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
     work_id: i64,
     creator_id: i64,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     log::info!("Detaching: WORK_ID - {work_id}, CREATOR_ID - {creator_id}.");
 
-    let inner = || -> Result<(), Box<dyn std::error::Error>> {
-        let guard = database.lock().unwrap();
-        guard.detach(work_id, creator_id)
+    let inner = move |db: &Database| commands::detach(db, work_id, creator_id);
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to detach: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn add_tag(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    name: String,
+) -> Result<i64, Error> {
+    log::info!("Adding tag: {name}.");
+
+    let inner = move |db: &Database| commands::add(db, &NewTag { name });
+
+    match write_changes(app, &pool, inner).await {
+        Ok(id) => {
+            log::info!("Added tag: ID - {id}.");
+            Ok(id)
+        }
+        Err(err) => {
+            log::error!("Failed to add tag: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_tags(pool: tauri::State<'_, Pool>) -> Result<Vec<Tag>, Error> {
+    log::info!("Getting tags.");
+
+    let inner = move |db: &Database| -> Result<Vec<Tag>, Error> { db.get_tags() };
+
+    match pool.read(inner).await {
+        Ok(tags) => Ok(tags),
+        Err(err) => {
+            log::error!("Failed to get tags: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_changes_since(
+    pool: tauri::State<'_, Pool>,
+    version: i64,
+) -> Result<Changes, Error> {
+    log::info!("Getting changes since version {version}.");
+
+    let inner = move |db: &Database| -> Result<Changes, Error> { db.get_changes_since(version) };
+
+    match pool.read(inner).await {
+        Ok(changes) => Ok(changes),
+        Err(err) => {
+            log::error!("Failed to get changes since version {version}: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_tag(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+) -> Result<(), Error> {
+    log::info!("Removing tag [{id}].");
+
+    let inner = move |db: &Database| commands::remove(db, Table::Tags, id);
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to remove tag [{id}]: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn update_tag_name(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+    name: String,
+) -> Result<(), Error> {
+    log::info!("Updating tag [{id}]: NAME - {name}.");
+
+    let inner = move |db: &Database| {
+        let columns = [TagColumn::Name(name)];
+        commands::update(db, id, &columns)
+    };
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to update tag [{id}] name: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn tag(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    work_id: i64,
+    tag_id: i64,
+) -> Result<(), Error> {
+    log::info!("Tagging: WORK_ID - {work_id}, TAG_ID - {tag_id}.");
+
+    let inner = move |db: &Database| commands::tag(db, work_id, tag_id);
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to tag: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn untag(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    work_id: i64,
+    tag_id: i64,
+) -> Result<(), Error> {
+    log::info!("Untagging: WORK_ID - {work_id}, TAG_ID - {tag_id}.");
+
+    let inner = move |db: &Database| commands::untag(db, work_id, tag_id);
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to untag: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn bulk_edit(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    ids: Vec<i64>,
+    edit: BulkEdit,
+) -> Result<BulkResult, Error> {
+    log::info!("Bulk editing works {ids:?}: EDIT - {edit:?}.");
+
+    let inner = move |db: &Database| commands::bulk_edit(db, &ids, &edit);
+
+    match write_changes(app, &pool, inner).await {
+        Ok(result) => {
+            result.failed.iter().for_each(|failure| {
+                log::warn!(
+                    "Failed to bulk edit work [{}]: {}.",
+                    failure.id,
+                    failure.error
+                );
+            });
+            Ok(result)
+        }
+        Err(err) => {
+            log::error!("Failed to bulk edit works: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn snooze_work(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    id: i64,
+    until: Option<i64>,
+) -> Result<(), Error> {
+    log::info!("Snoozing work [{id}]: UNTIL - {until:?}.");
+
+    let inner = move |db: &Database| {
+        let columns = [WorkColumn::SnoozedUntil(until)];
+        commands::update(db, id, &columns)
     };
 
-    match inner() {
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to snooze work [{id}]: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_stale_works(
+    pool: tauri::State<'_, Pool>,
+    timestamp: i64,
+) -> Result<Vec<StaleWork>, Error> {
+    log::info!("Getting stale works: TIMESTAMP - {timestamp}.");
+
+    let inner =
+        move |db: &Database| -> Result<Vec<StaleWork>, Error> { db.get_stale_works(timestamp) };
+
+    match pool.read(inner).await {
+        Ok(works) => Ok(works),
+        Err(err) => {
+            log::error!("Failed to get stale works: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_release_schedule(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    schedule: ReleaseSchedule,
+) -> Result<(), Error> {
+    log::info!("Setting release schedule: SCHEDULE - {schedule:?}.");
+
+    let work_id = schedule.work_id;
+    let inner = move |db: &Database| commands::set_release_schedule(db, &schedule);
+
+    match write_changes(app, &pool, inner).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to set work [{work_id}] release schedule: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_release_schedule(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    work_id: i64,
+) -> Result<(), Error> {
+    log::info!("Removing release schedule of work [{work_id}].");
+
+    let inner = move |db: &Database| commands::remove_release_schedule(db, work_id);
+
+    match write_changes(app, &pool, inner).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            let message = format!("Failed to detach: {err}.");
-            log::error!("{message}");
-            Err(message)
+            log::error!("Failed to remove work [{work_id}] release schedule: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_release_schedules(
+    pool: tauri::State<'_, Pool>,
+    timestamp: i64,
+) -> Result<Vec<ScheduleState>, Error> {
+    log::info!("Getting release schedules: TIMESTAMP - {timestamp}.");
+
+    let inner = move |db: &Database| -> Result<Vec<ScheduleState>, Error> {
+        db.get_release_schedules(timestamp)
+    };
+
+    match pool.read(inner).await {
+        Ok(schedules) => Ok(schedules),
+        Err(err) => {
+            log::error!("Failed to get release schedules: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn upcoming_releases(
+    pool: tauri::State<'_, Pool>,
+    from: i64,
+    to: i64,
+) -> Result<Vec<Release>, Error> {
+    log::info!("Getting upcoming releases [{from} - {to}].");
+
+    let inner =
+        move |db: &Database| -> Result<Vec<Release>, Error> { db.upcoming_releases(from, to) };
+
+    match pool.read(inner).await {
+        Ok(releases) => Ok(releases),
+        Err(err) => {
+            log::error!("Failed to get upcoming releases [{from} - {to}]: {err}.");
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn open_window(app: tauri::AppHandle, view: View) -> Result<(), Error> {
+    log::info!("Opening window: VIEW - {view:?}.");
+
+    match window::open(&app, view) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            log::error!("Failed to open window '{}': {err}.", view.label());
+            Err(err.into())
         }
     }
 }
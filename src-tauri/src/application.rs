@@ -1,9 +1,15 @@
-use std::{path::PathBuf, sync::Mutex};
+use std::{
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 use tauri::Manager;
 
 use crate::{
     config::Config,
+    error::Error,
     menu::{self, set_menu_state},
+    pool::Pool,
 };
 
 pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
@@ -11,37 +17,92 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     if let Err(err) = config.load(PathBuf::from("config.json")) {
         log::error!("Failed to load config: {err}.");
     }
+    config.prune_recent_databases();
+    if let Some(last) = config.last_database.clone() {
+        config.add_recent_database(last);
+    }
 
-    let window =
-        tauri::WebviewWindowBuilder::new(
-                app,
-                "main",
-                tauri::WebviewUrl::App("index.html".into())
-            )
-            .title("zero")
-            .menu(crate::menu::create_main_menu(app)?)
-            .visible(false)
-            .build()?;
+    let window = tauri::WebviewWindowBuilder::new(
+        app,
+        crate::window::MAIN_WINDOW,
+        tauri::WebviewUrl::App("index.html".into()),
+    )
+    .title("zero")
+    .menu(crate::menu::create_main_menu(
+        app,
+        &config.recent_databases,
+    )?)
+    .visible(false)
+    .build()?;
 
     app.on_menu_event(menu::event_handler);
 
     let menu_handle = window.menu().unwrap();
-    let mut database = crate::database::Database::default();
-    if let Some(ref last) = config.last_database {
-        log::info!("Opening last database: {last:?}");
-        database.open(last)?;
-        window.set_title(&format!("zero - {}", last.display()))?;
-        set_menu_state(&menu_handle, true)?;
-    } else {
-        set_menu_state(&menu_handle, false)?;
-    }
+    let pool = Pool::default();
+    let opened = match config.last_database {
+        Some(ref last) => {
+            log::info!("Opening last database: {last:?}");
+            let now = chrono::Local::now();
+            match pool.open(last, Some(&config.backups.path(last, now)), None) {
+                Ok(()) => {
+                    if let Err(err) = config.backups.prune(last, now) {
+                        log::error!("Failed to prune backups: {err}.");
+                    }
+                    window.set_title(&format!("zero - {}", last.display()))?;
+                    true
+                }
+                // The frontend asks for the password once it has loaded.
+                Err(Error::WrongKey) => {
+                    log::info!("Last database is encrypted, waiting for its password.");
+                    false
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        None => false,
+    };
+    set_menu_state(&menu_handle, opened)?;
 
     app.manage(Mutex::new(config));
-    app.manage(Mutex::new(database));
+    app.manage(pool);
+    if opened {
+        tauri::async_runtime::spawn(menu::report_integrity(app.handle().clone()));
+    }
+
+    spawn_periodic_backups(app.handle().clone());
 
     Ok(())
 }
 
+/// Backs up the open database every `interval` minutes of the backup settings, if set.
+fn spawn_periodic_backups(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut last = Instant::now();
+
+        loop {
+            std::thread::sleep(Duration::from_secs(60));
+
+            let settings = app.state::<Mutex<Config>>().lock().unwrap().backups.clone();
+            let pool = app.state::<Pool>();
+            let (Some(interval), Some(path)) = (settings.interval, pool.path()) else {
+                continue;
+            };
+            if last.elapsed() < Duration::from_secs(interval * 60) {
+                continue;
+            }
+            last = Instant::now();
+
+            let now = chrono::Local::now();
+            let result = pool
+                .with_reader(|db| db.backup(&settings.path(&path, now)))
+                .and_then(|()| settings.prune(&path, now));
+            if let Err(err) = result {
+                log::error!("Failed to back up database: {err}.");
+            }
+        }
+    });
+}
+
 pub fn callback(app: &tauri::AppHandle, event: tauri::RunEvent) {
     match event {
         tauri::RunEvent::ExitRequested { .. } => {
@@ -0,0 +1,223 @@
+use crate::database::DatabaseResult;
+use crate::restore::RowCounts;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+/// Backups used to be named to the second, so two taken within one overwrote each other.
+const LEGACY_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const EXTENSION: &str = "backup.db";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BackupSettings {
+    /// Where backups are written, next to the database when not set.
+    pub folder: Option<PathBuf>,
+    /// The newest backups that are always kept.
+    pub keep_last: usize,
+    /// Days for which the newest backup of each day is kept.
+    pub keep_daily: u32,
+    /// Weeks for which the newest backup of each week is kept.
+    pub keep_weekly: u32,
+    /// Minutes between backups while a database is open, none when not set.
+    pub interval: Option<u64>,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            folder: None,
+            keep_last: 5,
+            keep_daily: 7,
+            keep_weekly: 4,
+            interval: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: i64,
+    pub size: u64,
+    /// Rows in the backup, filled in by [`crate::restore::rows`] when it can be read.
+    pub rows: Option<RowCounts>,
+}
+
+impl BackupSettings {
+    pub fn folder(&self, database: &Path) -> PathBuf {
+        self.folder
+            .clone()
+            .unwrap_or_else(|| database.parent().map(Path::to_path_buf).unwrap_or_default())
+    }
+
+    /// Path of a new backup of `database` taken at `now`.
+    pub fn path(&self, database: &Path, now: DateTime<Local>) -> PathBuf {
+        self.folder(database).join(format!(
+            "{}.{}.{EXTENSION}",
+            stem(database),
+            now.format(TIMESTAMP_FORMAT)
+        ))
+    }
+
+    /// Backups of `database`, newest first.
+    pub fn list(&self, database: &Path) -> DatabaseResult<Vec<Backup>> {
+        let folder = self.folder(database);
+        if !folder.try_exists()? {
+            return Ok(vec![]);
+        }
+
+        let prefix = format!("{}.", stem(database));
+        let mut backups = std::fs::read_dir(folder)?
+            .map(|entry| {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let created = name
+                    .strip_prefix(&prefix)
+                    .and_then(|rest| rest.strip_suffix(&format!(".{EXTENSION}")))
+                    .and_then(|timestamp| {
+                        NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+                            .or_else(|_| {
+                                NaiveDateTime::parse_from_str(timestamp, LEGACY_TIMESTAMP_FORMAT)
+                            })
+                            .ok()
+                    })
+                    .and_then(|timestamp| Local.from_local_datetime(&timestamp).earliest());
+
+                Ok(match created {
+                    Some(created) => Some(Backup {
+                        path: entry.path(),
+                        created: created.timestamp_millis(),
+                        size: entry.metadata()?.len(),
+                        rows: None,
+                    }),
+                    None => None,
+                })
+            })
+            .filter_map(Result::transpose)
+            .collect::<DatabaseResult<Vec<_>>>()?;
+
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+        Ok(backups)
+    }
+
+    /// Backups, newest first, that fall outside the retention policy at `now`.
+    pub fn expired<'a>(&self, backups: &'a [Backup], now: DateTime<Local>) -> Vec<&'a Backup> {
+        let mut kept = HashSet::new();
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+
+        for (index, backup) in backups.iter().enumerate() {
+            let Some(created) = Local.timestamp_millis_opt(backup.created).single() else {
+                continue;
+            };
+            let age = (now.date_naive() - created.date_naive()).num_days();
+
+            if index < self.keep_last {
+                kept.insert(index);
+            }
+            if age < i64::from(self.keep_daily) && days.insert(created.date_naive()) {
+                kept.insert(index);
+            }
+            if age < i64::from(self.keep_weekly) * 7 && weeks.insert(created.iso_week()) {
+                kept.insert(index);
+            }
+        }
+
+        backups
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !kept.contains(index))
+            .map(|(_, backup)| backup)
+            .collect()
+    }
+
+    /// Deletes the backups of `database` the retention policy no longer keeps.
+    pub fn prune(&self, database: &Path, now: DateTime<Local>) -> DatabaseResult<()> {
+        let backups = self.list(database)?;
+        self.expired(&backups, now)
+            .into_iter()
+            .try_for_each(|backup| {
+                log::info!("Removing expired backup: {:?}.", backup.path);
+                Ok(std::fs::remove_file(&backup.path)?)
+            })
+    }
+}
+
+fn stem(database: &Path) -> String {
+    database
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn backup(now: DateTime<Local>, age: Duration) -> Backup {
+        Backup {
+            path: PathBuf::from(format!("{}", age.num_hours())),
+            created: (now - age).timestamp_millis(),
+            size: 0,
+            rows: None,
+        }
+    }
+
+    #[test]
+    fn keeps_last_daily_and_weekly_backups() {
+        let settings = BackupSettings {
+            keep_last: 2,
+            keep_daily: 3,
+            keep_weekly: 2,
+            ..Default::default()
+        };
+        let now = Local.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap();
+        let backups =
+            [0, 1, 2, 24, 25, 48, 96, 200, 400].map(|hours| backup(now, Duration::hours(hours)));
+
+        let expired = settings
+            .expired(&backups, now)
+            .into_iter()
+            .map(|backup| backup.path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        // 0 and 1 are the last two, 24 and 48 the newest of their days and 96 the newest of the
+        // previous week. 2, 25 and 200 have newer backups in their day or week, 400 is too old.
+        assert_eq!(expired, vec!["2", "25", "200", "400"]);
+    }
+
+    #[test]
+    fn lists_backups_newest_first() -> Result<(), Box<dyn std::error::Error>> {
+        let folder = crate::database::TempFolder::new();
+        let settings = BackupSettings {
+            folder: Some(folder.path().to_path_buf()),
+            ..Default::default()
+        };
+        let database = PathBuf::from("library.db");
+        let now = Local.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap();
+
+        let legacy = folder.join("library.20240610-120000.backup.db");
+        let older = settings.path(&database, now - Duration::days(1));
+        let newer = settings.path(&database, now);
+        let newest = settings.path(&database, now + Duration::milliseconds(1));
+        std::fs::write(&legacy, "")?;
+        std::fs::write(&older, "")?;
+        std::fs::write(&newer, "")?;
+        std::fs::write(&newest, "")?;
+        std::fs::write(folder.join("other.20240612-120000.backup.db"), "")?;
+
+        let backups = settings.list(&database)?;
+
+        let paths = backups
+            .iter()
+            .map(|backup| &backup.path)
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![&newest, &newer, &older, &legacy]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{Builder, Library};
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::path::Path;
+
+const TYPE: &str = "Book";
+/// Formats picked over the others when a book has several, most wanted first.
+const FORMATS: [&str; 3] = ["EPUB", "AZW3", "PDF"];
+const NO_FORMAT: &str = "No Format";
+const READ: &str = "Read";
+const UNREAD: &str = "To Read";
+/// External ids are Calibre book ids, behind the library id when Calibre gives one.
+pub const SOURCE: &str = "calibre";
+
+/// A custom column of a Calibre library that can hold whether a book was read.
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub label: String,
+    pub name: String,
+    pub datatype: String,
+}
+
+struct Book {
+    id: i64,
+    title: String,
+    added: Option<i64>,
+    modified: Option<i64>,
+}
+
+/// Calibre writes times like `2023-01-02 03:04:05.678901+00:00`.
+fn parse_timestamp(value: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%:z")
+        .ok()
+        .map(|datetime| datetime.timestamp_millis())
+}
+
+/// Opens the `metadata.db` of the Calibre library in `folder` without ever writing to it.
+fn open(folder: &Path) -> DatabaseResult<Connection> {
+    let path = folder.join("metadata.db");
+    if !path.is_file() {
+        return Err(Error::Invalid(format!(
+            "Not a Calibre library: no '{}'",
+            path.display()
+        )));
+    }
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    Ok(Connection::open_with_flags(path, flags)?)
+}
+
+fn custom_columns(conn: &Connection) -> DatabaseResult<Vec<(i64, Column)>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT id, label, name, datatype FROM custom_columns
+        WHERE datatype IN ('bool', 'text', 'enumeration') AND NOT is_multiple
+        ORDER BY name
+    ",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let column = Column {
+            label: row.get(1)?,
+            name: row.get(2)?,
+            datatype: row.get(3)?,
+        };
+        Ok((row.get(0)?, column))
+    })?;
+
+    rows.map(|row| Ok(row?)).collect()
+}
+
+/// The custom columns of the Calibre library in `folder` that [`read`] can take read statuses
+/// from.
+pub fn columns(folder: &Path) -> DatabaseResult<Vec<Column>> {
+    let conn = open(folder)?;
+    Ok(custom_columns(&conn)?
+        .into_iter()
+        .map(|(_, column)| column)
+        .collect())
+}
+
+/// Pairs of a book id and a value, in the order `sql` returns them.
+fn by_book(conn: &Connection, sql: &str) -> DatabaseResult<HashMap<i64, Vec<String>>> {
+    let mut values = HashMap::<_, Vec<_>>::new();
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        values.entry(row.get(0)?).or_default().push(row.get(1)?);
+    }
+    Ok(values)
+}
+
+/// The status of each book by the custom column labelled `label`. Yes or no columns make books
+/// `Read` or `To Read`, text columns give their value as the status.
+fn statuses(conn: &Connection, label: &str) -> DatabaseResult<HashMap<i64, String>> {
+    let Some((id, column)) = custom_columns(conn)?
+        .into_iter()
+        .find(|(_, column)| column.label == label)
+    else {
+        return Err(Error::Invalid(format!(
+            "The Calibre library has no '{label}' column"
+        )));
+    };
+
+    let statuses = if column.datatype == "bool" {
+        let sql = format!("SELECT book, value FROM custom_column_{id} WHERE value IS NOT NULL");
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            let read: bool = row.get(1)?;
+            Ok((row.get(0)?, if read { READ } else { UNREAD }.to_string()))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    } else {
+        let sql = format!(
+            "
+            SELECT link.book, value.value FROM books_custom_column_{id}_link AS link
+            JOIN custom_column_{id} AS value ON value.id = link.value
+        "
+        );
+        by_book(conn, &sql)?
+            .into_iter()
+            .filter_map(|(book, values)| Some((book, values.into_iter().next()?)))
+            .collect()
+    };
+    Ok(statuses)
+}
+
+/// Reads the Calibre library in `folder` as a library. Each book becomes a work of the `Book`
+/// type with its authors as creators and its series and tags as tags. Its format is the first
+/// of EPUB, AZW3 and PDF it has a file in, or else any other. The custom column labelled
+/// `read_column` gives its status, books are `To Read` without one. Every work is linked to
+/// its book id so a later import can update it.
+pub fn read(folder: &Path, read_column: Option<&str>, now: i64) -> DatabaseResult<Library> {
+    let conn = open(folder)?;
+    let library_id = conn
+        .query_row("SELECT uuid FROM library_id LIMIT 1", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .unwrap_or_default();
+    let statuses = match read_column {
+        Some(label) => statuses(&conn, label)?,
+        None => HashMap::new(),
+    };
+    let authors = by_book(
+        &conn,
+        "
+        SELECT link.book, authors.name FROM books_authors_link AS link
+        JOIN authors ON authors.id = link.author
+        ORDER BY link.id
+    ",
+    )?;
+    let formats = by_book(&conn, "SELECT book, format FROM data ORDER BY format")?;
+    let series = by_book(
+        &conn,
+        "
+        SELECT link.book, series.name FROM books_series_link AS link
+        JOIN series ON series.id = link.series
+    ",
+    )?;
+    let tags = by_book(
+        &conn,
+        "
+        SELECT link.book, tags.name FROM books_tags_link AS link
+        JOIN tags ON tags.id = link.tag
+        ORDER BY tags.name
+    ",
+    )?;
+
+    let books = {
+        let mut stmt =
+            conn.prepare("SELECT id, title, timestamp, last_modified FROM books ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            let time = |index| -> rusqlite::Result<_> {
+                let value: Option<String> = row.get(index)?;
+                Ok(value.as_deref().and_then(parse_timestamp))
+            };
+            Ok(Book {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                added: time(2)?,
+                modified: time(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut builder = Builder::new(now);
+    let r#type = builder.r#type(TYPE);
+    let none = vec![];
+    for book in books {
+        let status = builder.status(statuses.get(&book.id).map_or(UNREAD, String::as_str), false);
+        let book_formats = formats.get(&book.id).unwrap_or(&none);
+        let format = FORMATS
+            .iter()
+            .find(|format| book_formats.iter().any(|name| name == *format))
+            .copied()
+            .or_else(|| book_formats.first().map(String::as_str))
+            .unwrap_or(NO_FORMAT);
+        let format = builder.format(format);
+        let creators = authors
+            .get(&book.id)
+            .unwrap_or(&none)
+            .iter()
+            .map(|name| builder.creator(name))
+            .collect();
+        let names = [&series, &tags].map(|names| names.get(&book.id).unwrap_or(&none));
+        let mut tag_ids = vec![];
+        for name in names.into_iter().flatten() {
+            let tag = builder.tag(name);
+            if !tag_ids.contains(&tag) {
+                tag_ids.push(tag);
+            }
+        }
+
+        let added = book.added.unwrap_or(now);
+        let work_id = builder.work(Work {
+            id: 0,
+            name: book.title,
+            progress: "0".to_string(),
+            status,
+            r#type,
+            format,
+            updated: book.modified.unwrap_or(added),
+            added,
+            creators,
+            tags: tag_ids,
+        });
+        let external_id = match library_id.as_str() {
+            "" => book.id.to_string(),
+            library_id => format!("{library_id}:{}", book.id),
+        };
+        builder.link(work_id, SOURCE, external_id);
+    }
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "
+        CREATE TABLE library_id (id INTEGER PRIMARY KEY, uuid TEXT NOT NULL);
+        CREATE TABLE books (
+            id INTEGER PRIMARY KEY, title TEXT, timestamp TIMESTAMP, last_modified TIMESTAMP
+        );
+        CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE books_authors_link (id INTEGER PRIMARY KEY, book INTEGER, author INTEGER);
+        CREATE TABLE data (id INTEGER PRIMARY KEY, book INTEGER, format TEXT);
+        CREATE TABLE series (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE books_series_link (id INTEGER PRIMARY KEY, book INTEGER, series INTEGER);
+        CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE books_tags_link (id INTEGER PRIMARY KEY, book INTEGER, tag INTEGER);
+        CREATE TABLE custom_columns (
+            id INTEGER PRIMARY KEY, label TEXT, name TEXT, datatype TEXT, is_multiple BOOL
+        );
+        CREATE TABLE custom_column_1 (id INTEGER PRIMARY KEY, book INTEGER, value BOOL);
+
+        INSERT INTO library_id VALUES (1, 'lib');
+        INSERT INTO books VALUES
+            (1, 'Dune', '2023-01-02 00:00:00+00:00', '2023-05-01 00:00:00.5+00:00'),
+            (2, 'Notes', NULL, NULL);
+        INSERT INTO authors VALUES (1, 'Frank Herbert'), (2, 'Brian Herbert');
+        INSERT INTO books_authors_link VALUES (1, 1, 1), (2, 1, 2);
+        INSERT INTO data VALUES (1, 1, 'PDF'), (2, 1, 'EPUB'), (3, 2, 'TXT');
+        INSERT INTO series VALUES (1, 'Dune');
+        INSERT INTO books_series_link VALUES (1, 1, 1);
+        INSERT INTO tags VALUES (1, 'Science Fiction');
+        INSERT INTO books_tags_link VALUES (1, 1, 1);
+        INSERT INTO custom_columns VALUES (1, 'read', 'Read', 'bool', 0);
+        INSERT INTO custom_column_1 VALUES (1, 1, 1);
+    ";
+
+    #[test]
+    fn reads_calibre_library() -> Result<(), Box<dyn std::error::Error>> {
+        let temp = crate::database::TempFolder::new();
+        let folder = temp.path();
+        Connection::open(folder.join("metadata.db"))?.execute_batch(SCHEMA)?;
+
+        assert_eq!(columns(folder)?[0].label, "read");
+        let library = read(folder, Some("read"), 7)?;
+
+        assert_eq!(Library::names(&library.formats), vec!["EPUB", "TXT"]);
+        assert_eq!(
+            Library::names(&library.tags),
+            vec!["Dune", "Science Fiction"]
+        );
+        assert_eq!(library.status_names(), vec![READ, UNREAD]);
+
+        let [dune, notes] = library.expect_works();
+        assert_eq!(dune.creators.len(), 2);
+        assert_eq!(dune.added, 1_672_617_600_000);
+        assert_eq!(dune.updated, 1_682_899_200_500);
+        assert_eq!((notes.added, notes.updated), (7, 7));
+        assert_eq!(library.external_ids[1].external_id, "lib:2");
+
+        assert!(matches!(
+            read(folder, Some("unknown"), 0),
+            Err(Error::Invalid(_))
+        ));
+        assert!(matches!(
+            read(&folder.join("missing"), None, 0),
+            Err(Error::Invalid(_))
+        ));
+
+        Ok(())
+    }
+}
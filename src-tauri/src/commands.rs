@@ -0,0 +1,382 @@
+//! What the commands in [`crate::api`] do, written against [`LibraryStore`] so it can be tested
+//! without a window. Edits return the [`Change`]s they made alongside their result, the commands
+//! emit them.
+
+use crate::csv::{self, ColumnMapping, CsvImportReport, Duplicates, Sheet};
+use crate::database::{
+    BulkEdit, BulkResult, Creator, Database, DatabaseResult, ExportMapping, Work,
+};
+use crate::entity::{Column, Insert, Sorted, Table};
+use crate::event::{Change, Entity, Operation};
+use crate::integrity::IntegrityReport;
+use crate::library::{self, ImportMode, ImportReport, Library};
+use crate::schedule::ReleaseSchedule;
+use crate::service::{self, ExportReport, MappingOptions, Service};
+use crate::store::LibraryStore;
+use crate::{goodreads, mal};
+use std::path::Path;
+
+/// The result of an edit and the changes it made.
+pub type Changed<T> = (T, Vec<Change>);
+
+pub fn add<R: Insert>(store: &impl LibraryStore, row: &R) -> DatabaseResult<Changed<i64>> {
+    let id = store.add(row)?;
+    Ok((id, vec![Change::new(R::TABLE, Operation::Add, vec![id])]))
+}
+
+pub fn add_work(store: &impl LibraryStore, work: &Work) -> DatabaseResult<Changed<i64>> {
+    let id = store.add_work(work)?;
+    let mut changes = vec![Change::new(Entity::Work, Operation::Add, vec![id])];
+    if !work.creators.is_empty() {
+        changes.push(
+            Change::new(Entity::Creator, Operation::Attach, work.creators.clone())
+                .with_fields(["works"]),
+        );
+    }
+    Ok((id, changes))
+}
+
+pub fn add_creator(store: &impl LibraryStore, creator: &Creator) -> DatabaseResult<Changed<i64>> {
+    let id = store.add_creator(creator)?;
+    let mut changes = vec![Change::new(Entity::Creator, Operation::Add, vec![id])];
+    if !creator.works.is_empty() {
+        changes.push(
+            Change::new(Entity::Work, Operation::Attach, creator.works.clone())
+                .with_fields(["creators"]),
+        );
+    }
+    Ok((id, changes))
+}
+
+pub fn update<C: Column>(
+    store: &impl LibraryStore,
+    id: i64,
+    columns: &[C],
+) -> DatabaseResult<Changed<()>> {
+    store.update(id, columns)?;
+    Ok(((), vec![Change::updated(id, columns)]))
+}
+
+pub fn remove(store: &impl LibraryStore, table: Table, id: i64) -> DatabaseResult<Changed<()>> {
+    store.remove(table, id)?;
+    Ok(((), vec![Change::new(table, Operation::Remove, vec![id])]))
+}
+
+pub fn reorder(
+    store: &impl LibraryStore,
+    table: Sorted,
+    active_id: i64,
+    over_id: i64,
+) -> DatabaseResult<Changed<()>> {
+    store.reorder(table, active_id, over_id)?;
+    let change = Change::new(table.table(), Operation::Reorder, vec![active_id, over_id]);
+    Ok(((), vec![change.with_fields(["sort"])]))
+}
+
+pub fn attach(
+    store: &impl LibraryStore,
+    work_id: i64,
+    creator_id: i64,
+) -> DatabaseResult<Changed<()>> {
+    store.attach(work_id, creator_id)?;
+    Ok(((), linked(Operation::Attach, work_id, creator_id)))
+}
+
+pub fn detach(
+    store: &impl LibraryStore,
+    work_id: i64,
+    creator_id: i64,
+) -> DatabaseResult<Changed<()>> {
+    store.detach(work_id, creator_id)?;
+    Ok(((), linked(Operation::Detach, work_id, creator_id)))
+}
+
+fn linked(operation: Operation, work_id: i64, creator_id: i64) -> Vec<Change> {
+    vec![
+        Change::new(Entity::Work, operation, vec![work_id]).with_fields(["creators"]),
+        Change::new(Entity::Creator, operation, vec![creator_id]).with_fields(["works"]),
+    ]
+}
+
+pub fn tag(store: &impl LibraryStore, work_id: i64, tag_id: i64) -> DatabaseResult<Changed<()>> {
+    store.tag(work_id, tag_id)?;
+    let change = Change::new(Entity::Work, Operation::Attach, vec![work_id]);
+    Ok(((), vec![change.with_fields(["tags"])]))
+}
+
+pub fn untag(store: &impl LibraryStore, work_id: i64, tag_id: i64) -> DatabaseResult<Changed<()>> {
+    store.untag(work_id, tag_id)?;
+    let change = Change::new(Entity::Work, Operation::Detach, vec![work_id]);
+    Ok(((), vec![change.with_fields(["tags"])]))
+}
+
+pub fn bulk_edit(
+    store: &impl LibraryStore,
+    ids: &[i64],
+    edit: &BulkEdit,
+) -> DatabaseResult<Changed<BulkResult>> {
+    let result = store.bulk_edit(ids, edit)?;
+    let changes = if result.succeeded.is_empty() {
+        vec![]
+    } else {
+        vec![Change::bulk(edit, &result.succeeded)]
+    };
+    Ok((result, changes))
+}
+
+pub fn set_release_schedule(
+    store: &impl LibraryStore,
+    schedule: &ReleaseSchedule,
+) -> DatabaseResult<Changed<()>> {
+    store.set_release_schedule(schedule)?;
+    let change = Change::new(
+        Entity::ReleaseSchedule,
+        Operation::Update,
+        vec![schedule.work_id],
+    );
+    Ok(((), vec![change]))
+}
+
+pub fn remove_release_schedule(
+    store: &impl LibraryStore,
+    work_id: i64,
+) -> DatabaseResult<Changed<()>> {
+    store.remove_release_schedule(work_id)?;
+    let change = Change::new(Entity::ReleaseSchedule, Operation::Remove, vec![work_id]);
+    Ok(((), vec![change]))
+}
+
+/// Repairs what the integrity check found, returning what is left of it. Only the concrete
+/// database can check itself.
+pub fn repair(db: &Database) -> DatabaseResult<Changed<IntegrityReport>> {
+    let report = db.check_integrity()?;
+    let changes = db
+        .repair(&report)?
+        .into_iter()
+        .map(|(table, ids)| Change::new(table, Operation::Reorder, ids).with_fields(["sort"]))
+        .collect();
+    Ok((db.check_integrity()?, changes))
+}
+
+pub fn export_library(store: &impl LibraryStore, path: &Path, now: i64) -> DatabaseResult<()> {
+    library::export(store, now)?.write(path)
+}
+
+/// Merges `library` into `store`, or only reports what that would do on a dry run.
+pub fn import_library(
+    store: &impl LibraryStore,
+    library: &Library,
+    mode: ImportMode,
+    dry_run: bool,
+) -> DatabaseResult<Changed<ImportReport>> {
+    let report = library::import(store, library, mode, dry_run)?;
+    let changes = if dry_run {
+        vec![]
+    } else {
+        Change::imported(&report)
+    };
+    Ok((report, changes))
+}
+
+pub fn export_csv(store: &impl LibraryStore, path: &Path) -> DatabaseResult<()> {
+    csv::export(store)?.write(path)
+}
+
+pub fn import_csv(
+    store: &impl LibraryStore,
+    sheet: &Sheet,
+    mapping: &ColumnMapping,
+    duplicates: Duplicates,
+    now: i64,
+    dry_run: bool,
+) -> DatabaseResult<Changed<CsvImportReport>> {
+    let report = csv::import(store, sheet, mapping, duplicates, now, dry_run)?;
+    let changes = if dry_run {
+        vec![]
+    } else {
+        Change::imported_csv(&report)
+    };
+    Ok((report, changes))
+}
+
+pub fn get_export_mapping(
+    store: &impl LibraryStore,
+    service: Service,
+) -> DatabaseResult<MappingOptions> {
+    Ok(MappingOptions {
+        mapping: service::mapping(store, service)?,
+        statuses: service.statuses(),
+        types: service.types(),
+    })
+}
+
+/// Stores `mapping` as that of `service`, whatever service it names itself.
+pub fn set_export_mapping(
+    store: &impl LibraryStore,
+    service: Service,
+    mut mapping: ExportMapping,
+) -> DatabaseResult<()> {
+    mapping.service = service.name().to_string();
+    store.set_export_mapping(&mapping)
+}
+
+pub fn export_mal(
+    store: &impl LibraryStore,
+    path: &Path,
+    manga: bool,
+) -> DatabaseResult<ExportReport> {
+    let (xml, report) = mal::export(store, manga)?;
+    std::fs::write(path, xml)?;
+    Ok(report)
+}
+
+pub fn export_goodreads(store: &impl LibraryStore, path: &Path) -> DatabaseResult<ExportReport> {
+    let (sheet, report) = goodreads::export(store)?;
+    sheet.write(path)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{NewCreator, NewFormat, NewStatus, NewTag, NewType, WorkColumn};
+
+    fn memory() -> Database {
+        let mut database = Database::default();
+        database.open_in_memory().unwrap();
+        database
+    }
+
+    fn work(store: &Database, creators: Vec<i64>) -> DatabaseResult<Work> {
+        Ok(Work {
+            id: 0,
+            name: "name".into(),
+            progress: "progress".into(),
+            status: store.add(&NewStatus {
+                name: "status".into(),
+                ..Default::default()
+            })?,
+            r#type: store.add(&NewType {
+                name: "type".into(),
+            })?,
+            format: store.add(&NewFormat {
+                name: "format".into(),
+            })?,
+            updated: 44,
+            added: 44,
+            creators,
+            tags: vec![],
+        })
+    }
+
+    #[test]
+    fn add_work_announces_attached_creators() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+        let creator_id = store.add(&NewCreator {
+            name: "creator".into(),
+        })?;
+
+        let (id, changes) = add_work(store, &work(store, vec![creator_id])?)?;
+
+        assert_eq!(store.get_works()?[0].creators, vec![creator_id]);
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(Entity::Work, Operation::Add, vec![id]),
+                Change::new(Entity::Creator, Operation::Attach, vec![creator_id])
+                    .with_fields(["works"]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn edits_announce_what_they_changed() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+        let (id, _) = add_work(store, &work(store, vec![])?)?;
+        let (tag_id, changes) = add(store, &NewTag { name: "tag".into() })?;
+        assert_eq!(
+            changes,
+            vec![Change::new(Entity::Tag, Operation::Add, vec![tag_id])]
+        );
+
+        let columns = [WorkColumn::Name("renamed".into())];
+        let ((), changes) = update(store, id, &columns)?;
+        assert_eq!(store.get_works()?[0].name, "renamed");
+        assert_eq!(changes, vec![Change::updated(id, &columns)]);
+
+        let ((), changes) = tag(store, id, tag_id)?;
+        assert_eq!(store.get_works()?[0].tags, vec![tag_id]);
+        assert_eq!(
+            changes,
+            vec![Change::new(Entity::Work, Operation::Attach, vec![id]).with_fields(["tags"])]
+        );
+
+        let ((), changes) = remove(store, Table::Works, id)?;
+        assert!(store.get_works()?.is_empty());
+        assert_eq!(
+            changes,
+            vec![Change::new(Entity::Work, Operation::Remove, vec![id])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn failed_edits_announce_nothing() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+
+        assert!(attach(store, 1, 2).is_err());
+
+        let edit = BulkEdit::AddTag { tag_id: 3 };
+        let (result, changes) = bulk_edit(store, &[1, 2], &edit)?;
+        assert_eq!(result.failed.len(), 2);
+        assert!(changes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn reorder_announces_both_rows() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+        let first = store.add(&NewType {
+            name: "first".into(),
+        })?;
+        let second = store.add(&NewType {
+            name: "second".into(),
+        })?;
+
+        let ((), changes) = reorder(store, Sorted::Types, first, second)?;
+
+        let names = store
+            .get_types()?
+            .into_iter()
+            .map(|row| row.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["second", "first"]);
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(Entity::Type, Operation::Reorder, vec![first, second])
+                    .with_fields(["sort"])
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run_import_announces_nothing() -> Result<(), Box<dyn std::error::Error>> {
+        let source = &memory();
+        add_work(source, &work(source, vec![])?)?;
+        let library = library::export(source, 0)?;
+
+        let store = &memory();
+        let (report, changes) = import_library(store, &library, ImportMode::Add, true)?;
+        assert_eq!(report.works.added.len(), 1);
+        assert!(changes.is_empty());
+        assert!(store.get_works()?.is_empty());
+
+        let (_, changes) = import_library(store, &library, ImportMode::Add, false)?;
+        assert!(!changes.is_empty());
+        assert_eq!(store.get_works()?.len(), 1);
+        Ok(())
+    }
+}
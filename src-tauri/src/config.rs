@@ -1,12 +1,36 @@
+use crate::backup::BackupSettings;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+const MAX_RECENT_DATABASES: usize = 10;
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Config {
-    pub last_database: Option<PathBuf>
+    pub last_database: Option<PathBuf>,
+    /// Most recently opened first.
+    #[serde(default)]
+    pub recent_databases: Vec<PathBuf>,
+    #[serde(default)]
+    pub backups: BackupSettings,
 }
 
 impl Config {
+    pub fn add_recent_database(&mut self, path: PathBuf) {
+        self.recent_databases.retain(|recent| *recent != path);
+        self.recent_databases.insert(0, path);
+        self.recent_databases.truncate(MAX_RECENT_DATABASES);
+    }
+
+    /// Forgets databases that have since been moved or deleted.
+    pub fn prune_recent_databases(&mut self) {
+        self.recent_databases
+            .retain(|recent| recent.try_exists().unwrap_or(false));
+    }
+
+    pub fn clear_recent_databases(&mut self) {
+        self.recent_databases.clear();
+    }
+
     pub fn load(&mut self, path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
         if path.try_exists()? {
             let data = std::fs::read_to_string(path)?;
@@ -23,12 +47,13 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
+    use crate::database::TempFolder;
 
     #[test]
     fn can_save_and_load_config() -> Result<(), Box<dyn std::error::Error>> {
         let mut config = Config::default();
-        let path = PathBuf::from(format!("{}.json", Uuid::new_v4()));
+        let folder = TempFolder::new();
+        let path = folder.join("config.json");
         let last_database = PathBuf::from("path");
 
         config.last_database = Some(last_database.clone());
@@ -37,6 +62,35 @@ mod tests {
 
         assert_eq!(config.last_database, Some(last_database));
 
-        Ok(std::fs::remove_file(path)?)
+        Ok(())
+    }
+
+    #[test]
+    fn recent_databases_are_bounded_and_deduplicated() {
+        let mut config = Config::default();
+
+        (0..12).for_each(|index| config.add_recent_database(PathBuf::from(format!("{index}.db"))));
+        config.add_recent_database(PathBuf::from("5.db"));
+
+        assert_eq!(config.recent_databases.len(), MAX_RECENT_DATABASES);
+        assert_eq!(config.recent_databases[0], PathBuf::from("5.db"));
+        assert_eq!(config.recent_databases[1], PathBuf::from("11.db"));
+        assert!(!config.recent_databases[1..].contains(&PathBuf::from("5.db")));
+    }
+
+    #[test]
+    fn prunes_missing_recent_databases() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Config::default();
+        let folder = TempFolder::new();
+        let existing = folder.join("existing.db");
+        std::fs::write(&existing, "")?;
+
+        config.add_recent_database(folder.join("missing.db"));
+        config.add_recent_database(existing.clone());
+        config.prune_recent_databases();
+
+        assert_eq!(config.recent_databases, vec![existing.clone()]);
+
+        Ok(())
     }
 }
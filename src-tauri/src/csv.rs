@@ -0,0 +1,631 @@
+use crate::database::{DatabaseResult, Work};
+use crate::entity::{NewCreator, NewFormat, NewStatus, NewTag, NewType, WorkColumn};
+use crate::error::Error;
+use crate::library::{ImportCount, ImportReport};
+use crate::store::LibraryStore;
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Columns of an exported sheet, also the header names [`ColumnMapping::guess`] looks for.
+pub const HEADERS: [&str; 9] = [
+    "Name", "Progress", "Status", "Type", "Format", "Creators", "Tags", "Updated", "Added",
+];
+/// Separates creators and tags within a cell.
+const LIST_SEPARATOR: char = ';';
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const PREVIEW_ROWS: usize = 5;
+
+/// The records of a CSV file, the first one being the header.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Sheet {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Sheet {
+    pub fn read(path: &Path) -> DatabaseResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses RFC 4180 CSV: fields separated by commas, optionally quoted with `"` and with
+    /// quotes inside escaped as `""`. Blank lines are skipped.
+    pub fn parse(text: &str) -> DatabaseResult<Self> {
+        let mut records = vec![];
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+        while let Some(char) = chars.next() {
+            match char {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if field.is_empty() => quoted = true,
+                ',' if !quoted => record.push(std::mem::take(&mut field)),
+                '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+                '\n' if !quoted => {
+                    record.push(std::mem::take(&mut field));
+                    if record.iter().any(|field| !field.is_empty()) {
+                        records.push(std::mem::take(&mut record));
+                    }
+                    record.clear();
+                }
+                char => field.push(char),
+            }
+        }
+        if quoted {
+            return Err(Error::Invalid("Unterminated quoted field".to_string()));
+        }
+        record.push(field);
+        if record.iter().any(|field| !field.is_empty()) {
+            records.push(record);
+        }
+
+        let mut records = records.into_iter();
+        let headers = records
+            .next()
+            .ok_or_else(|| Error::Invalid("The file has no header".to_string()))?;
+        Ok(Sheet {
+            headers,
+            rows: records.collect(),
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> DatabaseResult<()> {
+        Ok(std::fs::write(path, self.to_string())?)
+    }
+
+    /// The cell of `row` in `column`, trimmed. Short rows are padded with empty cells.
+    pub fn cell(row: &[String], column: usize) -> &str {
+        row.get(column).map_or("", |cell| cell.trim())
+    }
+
+    /// Index of the first column whose header is one of `names`, ignoring case.
+    pub fn column(&self, names: &[&str]) -> Option<usize> {
+        self.headers.iter().position(|header| {
+            names
+                .iter()
+                .any(|name| header.trim().eq_ignore_ascii_case(name))
+        })
+    }
+}
+
+impl std::fmt::Display for Sheet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for record in std::iter::once(&self.headers).chain(&self.rows) {
+            let fields = record.iter().map(|field| {
+                if field.contains([',', '"', '\r', '\n']) || field.trim() != field {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.clone()
+                }
+            });
+            write!(f, "{}\r\n", fields.collect::<Vec<_>>().join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// The start of a sheet, shown while mapping its columns.
+#[derive(serde::Serialize, Debug)]
+pub struct CsvPreview {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub mapping: Option<ColumnMapping>,
+}
+
+impl From<Sheet> for CsvPreview {
+    fn from(mut sheet: Sheet) -> Self {
+        sheet.rows.truncate(PREVIEW_ROWS);
+        let mapping = ColumnMapping::guess(&sheet);
+        CsvPreview {
+            headers: sheet.headers,
+            rows: sheet.rows,
+            mapping,
+        }
+    }
+}
+
+/// Reads milliseconds since the epoch, a local date and time as written by [`export`] or a
+/// local date as `YYYY-MM-DD` or `YYYY/MM/DD`.
+pub fn parse_timestamp(value: &str) -> Option<i64> {
+    if let Ok(timestamp) = value.parse() {
+        return Some(timestamp);
+    }
+
+    let datetime = NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%d", "%Y/%m/%d"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map(|datetime| datetime.timestamp_millis())
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    match Local.timestamp_millis_opt(timestamp).single() {
+        Some(datetime) => datetime.format(TIMESTAMP_FORMAT).to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Every work with the names of its status, type, format, creators and tags instead of ids.
+pub fn export(store: &impl LibraryStore) -> DatabaseResult<Sheet> {
+    store.transaction(|store| {
+        let statuses: HashMap<_, _> = store
+            .get_statuses()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let types: HashMap<_, _> = store
+            .get_types()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let formats: HashMap<_, _> = store
+            .get_formats()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let tags: HashMap<_, _> = store
+            .get_tags()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let creators: HashMap<_, _> = store
+            .get_creators()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+
+        let name =
+            |names: &HashMap<i64, String>, id: &i64| names.get(id).cloned().unwrap_or_default();
+        let list = |names: &HashMap<i64, String>, ids: &[i64]| {
+            let names = ids.iter().map(|id| name(names, id)).collect::<Vec<_>>();
+            names.join(&format!("{LIST_SEPARATOR} "))
+        };
+
+        let rows = store
+            .get_works()?
+            .into_iter()
+            .map(|work| {
+                vec![
+                    work.name,
+                    work.progress,
+                    name(&statuses, &work.status),
+                    name(&types, &work.r#type),
+                    name(&formats, &work.format),
+                    list(&creators, &work.creators),
+                    list(&tags, &work.tags),
+                    format_timestamp(work.updated),
+                    format_timestamp(work.added),
+                ]
+            })
+            .collect();
+
+        Ok(Sheet {
+            headers: HEADERS.map(str::to_string).to_vec(),
+            rows,
+        })
+    })
+}
+
+/// Which column of a sheet holds what, by index. Optional columns that aren't mapped leave
+/// progress and links empty and use the time of the import for the timestamps.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub name: usize,
+    pub progress: Option<usize>,
+    pub status: usize,
+    pub r#type: usize,
+    pub format: usize,
+    pub creators: Option<usize>,
+    pub tags: Option<usize>,
+    pub updated: Option<usize>,
+    pub added: Option<usize>,
+}
+
+impl ColumnMapping {
+    /// Maps the columns named like the ones [`export`] writes, if the required ones are there.
+    pub fn guess(sheet: &Sheet) -> Option<Self> {
+        let [name, progress, status, r#type, format, creators, tags, updated, added] =
+            HEADERS.map(|header| sheet.column(&[header]));
+        Some(ColumnMapping {
+            name: name?,
+            progress,
+            status: status?,
+            r#type: r#type?,
+            format: format?,
+            creators,
+            tags,
+            updated,
+            added,
+        })
+    }
+}
+
+/// What to do with a row naming a work that already exists with the same type and format.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Duplicates {
+    /// Leave the existing work as it is.
+    Skip,
+    /// Set its progress, status and update time from the row and add its creators and tags.
+    Update,
+    /// Add the row as another work.
+    Create,
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// The row number a spreadsheet would show, the header being row 1.
+    pub row: usize,
+    pub message: String,
+}
+
+/// An [`ImportReport`] where matched works were either skipped or, if listed in `updated`,
+/// updated along with their creators and tags. Rows in `errors` were left out, everything else
+/// was imported.
+#[derive(serde::Serialize, Debug, Default, PartialEq, Eq)]
+pub struct CsvImportReport {
+    #[serde(flatten)]
+    pub import: ImportReport,
+    pub errors: Vec<RowError>,
+}
+
+/// A row with its cells checked, nothing is written before all of them are.
+struct Row<'a> {
+    name: &'a str,
+    progress: &'a str,
+    status: &'a str,
+    r#type: &'a str,
+    format: &'a str,
+    creators: Vec<&'a str>,
+    tags: Vec<&'a str>,
+    updated: i64,
+    added: i64,
+}
+
+impl<'a> Row<'a> {
+    fn parse(row: &'a [String], mapping: &ColumnMapping, now: i64) -> Result<Self, String> {
+        let required = |column: usize, name: &str| match Sheet::cell(row, column) {
+            "" => Err(format!("{name} is empty")),
+            cell => Ok(cell),
+        };
+        let optional = |column: Option<usize>| column.map_or("", |column| Sheet::cell(row, column));
+        let list = |column: Option<usize>| {
+            optional(column)
+                .split(LIST_SEPARATOR)
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .collect()
+        };
+        let timestamp = |column: Option<usize>, name: &str| match optional(column) {
+            "" => Ok(now),
+            cell => parse_timestamp(cell).ok_or_else(|| format!("{name} '{cell}' is not a date")),
+        };
+
+        Ok(Row {
+            name: required(mapping.name, "Name")?,
+            progress: optional(mapping.progress),
+            status: required(mapping.status, "Status")?,
+            r#type: required(mapping.r#type, "Type")?,
+            format: required(mapping.format, "Format")?,
+            creators: list(mapping.creators),
+            tags: list(mapping.tags),
+            updated: timestamp(mapping.updated, "Updated")?,
+            added: timestamp(mapping.added, "Added")?,
+        })
+    }
+}
+
+/// Ids by name of the rows of one table, adding the missing ones on first use.
+struct Names(HashMap<String, i64>);
+
+impl Names {
+    fn new(rows: impl IntoIterator<Item = (i64, String)>) -> Self {
+        let mut names = HashMap::new();
+        for (id, name) in rows {
+            names.entry(name).or_insert(id);
+        }
+        Names(names)
+    }
+
+    fn resolve(
+        &mut self,
+        name: &str,
+        count: &mut ImportCount,
+        add: impl FnOnce(String) -> DatabaseResult<i64>,
+    ) -> DatabaseResult<i64> {
+        match self.0.get(name) {
+            Some(&id) => {
+                if !count.matched.contains(&id) && !count.added.contains(&id) {
+                    count.matched.push(id);
+                }
+                Ok(id)
+            }
+            None => {
+                let id = add(name.to_string())?;
+                self.0.insert(name.to_string(), id);
+                count.added.push(id);
+                Ok(id)
+            }
+        }
+    }
+}
+
+/// Imports the rows of `sheet` as works, adding the statuses, types, formats, creators and
+/// tags they name that don't exist yet. Rows that can't be read are reported and skipped, a
+/// failure to write one undoes the whole import. Nothing is kept when `dry_run` is set.
+pub fn import(
+    store: &impl LibraryStore,
+    sheet: &Sheet,
+    mapping: &ColumnMapping,
+    duplicates: Duplicates,
+    now: i64,
+    dry_run: bool,
+) -> DatabaseResult<CsvImportReport> {
+    let merge = |store: &_| merge(store, sheet, mapping, duplicates, now);
+    if dry_run {
+        store.dry_run(merge)
+    } else {
+        store.transaction(merge)
+    }
+}
+
+fn merge(
+    store: &impl LibraryStore,
+    sheet: &Sheet,
+    mapping: &ColumnMapping,
+    duplicates: Duplicates,
+    now: i64,
+) -> DatabaseResult<CsvImportReport> {
+    let mut report = CsvImportReport::default();
+    let mut statuses = Names::new(
+        store
+            .get_statuses()?
+            .into_iter()
+            .map(|row| (row.id, row.name)),
+    );
+    let mut types = Names::new(store.get_types()?.into_iter().map(|row| (row.id, row.name)));
+    let mut formats = Names::new(
+        store
+            .get_formats()?
+            .into_iter()
+            .map(|row| (row.id, row.name)),
+    );
+    let mut tags = Names::new(store.get_tags()?.into_iter().map(|row| (row.id, row.name)));
+    let mut creators = Names::new(
+        store
+            .get_creators()?
+            .into_iter()
+            .map(|row| (row.id, row.name)),
+    );
+
+    let mut works = HashMap::new();
+    for work in store.get_works()? {
+        works
+            .entry((work.name.clone(), work.r#type, work.format))
+            .or_insert(work);
+    }
+
+    for (index, row) in sheet.rows.iter().enumerate() {
+        let row = match Row::parse(row, mapping, now) {
+            Ok(row) => row,
+            Err(message) => {
+                report.errors.push(RowError {
+                    row: index + 2,
+                    message,
+                });
+                continue;
+            }
+        };
+
+        let import = &mut report.import;
+        let status = statuses.resolve(row.status, &mut import.statuses, |name| {
+            store.add(&NewStatus {
+                name,
+                ..Default::default()
+            })
+        })?;
+        let r#type = types.resolve(row.r#type, &mut import.types, |name| {
+            store.add(&NewType { name })
+        })?;
+        let format = formats.resolve(row.format, &mut import.formats, |name| {
+            store.add(&NewFormat { name })
+        })?;
+        let creator_ids = row
+            .creators
+            .iter()
+            .map(|name| {
+                creators.resolve(name, &mut import.creators, |name| {
+                    store.add(&NewCreator { name })
+                })
+            })
+            .collect::<DatabaseResult<Vec<_>>>()?;
+        let tag_ids = row
+            .tags
+            .iter()
+            .map(|name| tags.resolve(name, &mut import.tags, |name| store.add(&NewTag { name })))
+            .collect::<DatabaseResult<Vec<_>>>()?;
+
+        let key = (row.name.to_string(), r#type, format);
+        match works.get_mut(&key) {
+            Some(work) if duplicates != Duplicates::Create => {
+                if !import.works.matched.contains(&work.id) {
+                    import.works.matched.push(work.id);
+                }
+                if duplicates == Duplicates::Update {
+                    update(store, work, &row, status, &creator_ids, &tag_ids)?;
+                    if !import.updated.contains(&work.id) {
+                        import.updated.push(work.id);
+                    }
+                }
+            }
+            _ => {
+                let mut work = Work {
+                    id: 0,
+                    name: row.name.to_string(),
+                    progress: row.progress.to_string(),
+                    status,
+                    r#type,
+                    format,
+                    updated: row.updated,
+                    added: row.added,
+                    creators: dedup(creator_ids),
+                    tags: dedup(tag_ids),
+                };
+                work.id = store.add_work(&work)?;
+                import.works.added.push(work.id);
+                works.insert(key, work);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn update(
+    store: &impl LibraryStore,
+    work: &mut Work,
+    row: &Row,
+    status: i64,
+    creators: &[i64],
+    tags: &[i64],
+) -> DatabaseResult<()> {
+    work.progress = row.progress.to_string();
+    work.status = status;
+    work.updated = row.updated;
+    store.update(
+        work.id,
+        &[
+            WorkColumn::Progress(work.progress.clone()),
+            WorkColumn::Status(status),
+            WorkColumn::Updated(row.updated),
+        ],
+    )?;
+
+    for &creator in creators {
+        if !work.creators.contains(&creator) {
+            store.attach(work.id, creator)?;
+            work.creators.push(creator);
+        }
+    }
+    for &tag in tags {
+        if !work.tags.contains(&tag) {
+            store.tag(work.id, tag)?;
+            work.tags.push(tag);
+        }
+    }
+    Ok(())
+}
+
+fn dedup(mut ids: Vec<i64>) -> Vec<i64> {
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    fn open() -> Database {
+        let mut database = Database::default();
+        database.open_in_memory().unwrap();
+        database
+    }
+
+    #[test]
+    fn parses_quoted_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let sheet = Sheet::parse("\u{feff}a,b\r\n\"x, \"\"y\"\"\",\"line\nbreak\"\r\n\r\nlast,\n")?;
+
+        assert_eq!(sheet.headers, vec!["a", "b"]);
+        assert_eq!(
+            sheet.rows,
+            vec![vec!["x, \"y\"", "line\nbreak"], vec!["last", ""]]
+        );
+        assert_eq!(Sheet::parse(&sheet.to_string())?, sheet);
+        assert!(matches!(Sheet::parse("a,\"b"), Err(Error::Invalid(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn exports_and_imports_works() -> Result<(), Box<dyn std::error::Error>> {
+        let source = &open();
+        let text = "Name,Status,Type,Format,Creators,Progress,Added\n\
+                    Dune,Reading,Book,Paperback,Frank Herbert,12,2024-01-31\n\
+                    Dune Messiah,Reading,Book,Paperback,Frank Herbert; Brian Herbert,,\n\
+                    Missing,,Book,Paperback,,,\n\
+                    Bad date,Reading,Book,Paperback,,,yesterday\n";
+        let sheet = Sheet::parse(text)?;
+        let mapping = ColumnMapping {
+            name: 0,
+            progress: Some(5),
+            status: 1,
+            r#type: 2,
+            format: 3,
+            creators: Some(4),
+            tags: None,
+            updated: None,
+            added: Some(6),
+        };
+
+        let dry = import(source, &sheet, &mapping, Duplicates::Skip, 1, true)?;
+        assert_eq!(source.get_works()?.len(), 0);
+
+        let report = import(source, &sheet, &mapping, Duplicates::Skip, 1, false)?;
+        assert_eq!(dry.import.works.added.len(), 2);
+        assert_eq!(report.import.works.added.len(), 2);
+        assert_eq!(report.import.creators.added.len(), 2);
+        assert_eq!(report.import.statuses.added.len(), 1);
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|error| error.row)
+                .collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+
+        let works = source.get_works()?;
+        assert_eq!(works[0].progress, "12");
+        assert_eq!(works[0].added, parse_timestamp("2024-01-31").unwrap());
+        assert_eq!(works[1].creators.len(), 2);
+        assert_eq!(works[1].added, 1);
+
+        let exported = export(source)?;
+        let target = &open();
+        let mapping = ColumnMapping::guess(&exported).unwrap();
+        let report = import(target, &exported, &mapping, Duplicates::Skip, 2, false)?;
+        assert!(report.errors.is_empty());
+        assert_eq!(export(target)?, exported);
+
+        let mut edited = exported;
+        edited.rows[0][1] = "20".into();
+        let skipped = import(target, &edited, &mapping, Duplicates::Skip, 3, false)?;
+        let updated = import(target, &edited, &mapping, Duplicates::Update, 3, false)?;
+        let created = import(target, &edited, &mapping, Duplicates::Create, 3, false)?;
+
+        assert_eq!(skipped.import.works.matched.len(), 2);
+        assert!(skipped.import.updated.is_empty());
+        assert_eq!(updated.import.updated.len(), 2);
+        assert_eq!(created.import.works.added.len(), 2);
+        let works = target.get_works()?;
+        assert_eq!(works.len(), 4);
+        assert_eq!(works[0].progress, "20");
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
+use crate::entity::{Column, Insert, Sorted, Table};
+use crate::error::Error;
+use crate::integrity::{self, IntegrityReport};
+use crate::schedule::{Recurrence, Release, ReleaseSchedule, ScheduleState};
+use crate::store::LibraryStore;
 use rusqlite::named_params;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const CREATE_QUERY: &str = "
 CREATE TABLE IF NOT EXISTS works (
@@ -48,6 +53,23 @@ CREATE TABLE IF NOT EXISTS formats (
     sort INTEGER NOT NULL DEFAULT 0
 );
 
+CREATE TABLE IF NOT EXISTS release_schedules (
+    work_id  INTEGER PRIMARY KEY,
+    kind     TEXT NOT NULL CHECK (kind IN ('weekly', 'interval', 'dates')),
+    weekday  INTEGER CHECK (weekday BETWEEN 0 AND 6),
+    interval INTEGER CHECK (interval > 0),
+    start    INTEGER,
+    offset   INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (work_id) REFERENCES works (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS release_dates (
+    work_id INTEGER NOT NULL,
+    date    INTEGER NOT NULL,
+    PRIMARY KEY (work_id, date),
+    FOREIGN KEY (work_id) REFERENCES release_schedules (work_id) ON DELETE CASCADE
+);
+
 
 
 CREATE TRIGGER IF NOT EXISTS status_add
@@ -104,6 +126,139 @@ BEGIN
 END;
 ";
 
+// Bumps the version of a row in `changes`, marking it deleted if it no longer exists.
+macro_rules! track_change {
+    ($table:literal, $id:literal) => {
+        concat!("
+        INSERT INTO changes (entity, id, version, deleted)
+        VALUES ('", $table, "', ", $id, ", (SELECT coalesce(max(version), 0) + 1 FROM changes),
+                NOT EXISTS (SELECT 1 FROM ", $table, " WHERE id = ", $id, "))
+        ON CONFLICT (entity, id) DO UPDATE SET version = excluded.version, deleted = excluded.deleted;
+        ")
+    };
+}
+
+macro_rules! track_changes {
+    ($table:literal) => {
+        concat!(
+            "CREATE TRIGGER ",
+            $table,
+            "_inserted AFTER INSERT ON ",
+            $table,
+            " BEGIN",
+            track_change!($table, "NEW.id"),
+            "END;
+            CREATE TRIGGER ",
+            $table,
+            "_updated AFTER UPDATE ON ",
+            $table,
+            " BEGIN",
+            track_change!($table, "NEW.id"),
+            "END;
+            CREATE TRIGGER ",
+            $table,
+            "_deleted AFTER DELETE ON ",
+            $table,
+            " BEGIN",
+            track_change!($table, "OLD.id"),
+            "END;
+            INSERT INTO changes (entity, id, version) SELECT '",
+            $table,
+            "', id, 1 FROM ",
+            $table,
+            ";
+            "
+        )
+    };
+}
+
+// Applied in order to bring older databases up to date, `PRAGMA user_version` tracks
+// how many have already run.
+const MIGRATIONS: &[&str] = &[
+    "
+    ALTER TABLE statuses ADD COLUMN stale_after INTEGER CHECK (stale_after > 0);
+    ALTER TABLE works    ADD COLUMN snoozed_until INTEGER;
+    ",
+    "
+    CREATE TABLE tags (
+        id   INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE
+    );
+
+    CREATE TABLE work_tag (
+        work_id INTEGER NOT NULL,
+        tag_id  INTEGER NOT NULL,
+        PRIMARY KEY (work_id, tag_id),
+        FOREIGN KEY (work_id) REFERENCES works (id) ON DELETE CASCADE,
+        FOREIGN KEY (tag_id)  REFERENCES tags  (id) ON DELETE CASCADE
+    );
+    ",
+    concat!(
+        "
+        CREATE TABLE changes (
+            entity  TEXT NOT NULL,
+            id      INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            deleted INTEGER NOT NULL CHECK (deleted IN (0, 1)) DEFAULT 0,
+            PRIMARY KEY (entity, id)
+        );
+
+        CREATE INDEX changes_version ON changes (version);
+        ",
+        track_changes!("works"),
+        track_changes!("creators"),
+        track_changes!("statuses"),
+        track_changes!("types"),
+        track_changes!("formats"),
+        track_changes!("tags"),
+        "CREATE TRIGGER work_creator_inserted AFTER INSERT ON work_creator BEGIN",
+        track_change!("works", "NEW.work_id"),
+        track_change!("creators", "NEW.creator_id"),
+        "END;
+        CREATE TRIGGER work_creator_deleted AFTER DELETE ON work_creator BEGIN",
+        track_change!("works", "OLD.work_id"),
+        track_change!("creators", "OLD.creator_id"),
+        "END;
+        CREATE TRIGGER work_tag_inserted AFTER INSERT ON work_tag BEGIN",
+        track_change!("works", "NEW.work_id"),
+        "END;
+        CREATE TRIGGER work_tag_deleted AFTER DELETE ON work_tag BEGIN",
+        track_change!("works", "OLD.work_id"),
+        "END;
+        "
+    ),
+    "
+    CREATE TABLE external_ids (
+        source      TEXT NOT NULL,
+        external_id TEXT NOT NULL,
+        work_id     INTEGER NOT NULL,
+        PRIMARY KEY (source, external_id),
+        FOREIGN KEY (work_id) REFERENCES works (id) ON DELETE CASCADE
+    );
+
+    CREATE INDEX external_ids_work_id ON external_ids (work_id);
+    ",
+    "
+    CREATE TABLE status_mappings (
+        service   TEXT NOT NULL,
+        status_id INTEGER NOT NULL,
+        value     TEXT NOT NULL,
+        PRIMARY KEY (service, status_id),
+        FOREIGN KEY (status_id) REFERENCES statuses (id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE type_mappings (
+        service TEXT NOT NULL,
+        type_id INTEGER NOT NULL,
+        value   TEXT NOT NULL,
+        PRIMARY KEY (service, type_id),
+        FOREIGN KEY (type_id) REFERENCES types (id) ON DELETE CASCADE
+    );
+    ",
+];
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct Work {
     pub id: i64,
@@ -115,6 +270,32 @@ pub struct Work {
     pub updated: i64,
     pub added: i64,
     pub creators: Vec<i64>,
+    #[serde(default)]
+    pub tags: Vec<i64>,
+}
+
+/// The id a work has in another app, so that importing from it again finds the same work.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalId {
+    pub work_id: i64,
+    pub source: String,
+    pub external_id: String,
+}
+
+/// What the statuses and types of this library are called by another service, for exports to
+/// it. Statuses and types without a value have none there.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportMapping {
+    pub service: String,
+    pub statuses: Vec<Mapped>,
+    pub types: Vec<Mapped>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Mapped {
+    pub id: i64,
+    pub value: String,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -130,38 +311,346 @@ pub struct Status {
     pub id: i64,
     pub name: String,
     pub is_update: bool,
+    pub stale_after: Option<i64>,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleWork {
+    pub id: i64,
+    pub due: i64,
+    pub overdue: i64,
 }
 
 #[derive(serde::Serialize, Debug)]
 pub struct Type {
-    id: i64,
-    name: String,
+    pub id: i64,
+    pub name: String,
 }
 
 #[derive(serde::Serialize, Debug)]
 pub struct Format {
-    id: i64,
-    name: String,
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct Removed {
+    pub works: Vec<i64>,
+    pub creators: Vec<i64>,
+    pub statuses: Vec<i64>,
+    pub types: Vec<i64>,
+    pub formats: Vec<i64>,
+    pub tags: Vec<i64>,
+}
+
+/// Everything that changed after `since`. Statuses, types and formats are user sorted so they
+/// are sent whole when any of them changed and left out otherwise.
+#[derive(serde::Serialize, Debug)]
+pub struct Changes {
+    pub version: i64,
+    pub works: Vec<Work>,
+    pub creators: Vec<Creator>,
+    pub statuses: Option<Vec<Status>>,
+    pub types: Option<Vec<Type>>,
+    pub formats: Option<Vec<Format>>,
+    pub tags: Vec<Tag>,
+    pub removed: Removed,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BulkEdit {
+    #[serde(rename_all = "camelCase")]
+    SetStatus {
+        status: i64,
+        timestamp: i64,
+    },
+    SetType {
+        r#type: i64,
+    },
+    SetFormat {
+        format: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Attach {
+        creator_id: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Detach {
+        creator_id: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    AddTag {
+        tag_id: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveTag {
+        tag_id: i64,
+    },
+    Remove,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct BulkFailure {
+    pub id: i64,
+    pub error: Error,
+}
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct BulkResult {
+    pub succeeded: Vec<i64>,
+    pub failed: Vec<BulkFailure>,
+}
+
+pub type DatabaseResult<T> = Result<T, Error>;
+
+fn text(value: &dyn rusqlite::ToSql) -> Option<String> {
+    use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+
+    match value.to_sql().ok()? {
+        ToSqlOutput::Borrowed(ValueRef::Text(text)) => {
+            Some(String::from_utf8_lossy(text).into_owned())
+        }
+        ToSqlOutput::Owned(Value::Text(text)) => Some(text),
+        _ => None,
+    }
+}
+
+fn integer(value: &dyn rusqlite::ToSql) -> Option<i64> {
+    use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+
+    match value.to_sql().ok()? {
+        ToSqlOutput::Borrowed(ValueRef::Integer(integer))
+        | ToSqlOutput::Owned(Value::Integer(integer)) => Some(integer),
+        _ => None,
+    }
+}
+
+struct Savepoint<'a> {
+    conn: &'a rusqlite::Connection,
+    released: bool,
+}
+
+impl<'a> Savepoint<'a> {
+    fn new(conn: &'a rusqlite::Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch("SAVEPOINT zero")?;
+        Ok(Savepoint {
+            conn,
+            released: false,
+        })
+    }
+
+    fn release(mut self) -> rusqlite::Result<()> {
+        self.released = true;
+        self.conn.execute_batch("RELEASE zero")
+    }
 }
 
-pub type DatabaseResult<T> = Result<T, Box<dyn std::error::Error>>;
+impl Drop for Savepoint<'_> {
+    fn drop(&mut self) {
+        if !self.released {
+            if let Err(err) = self.conn.execute_batch("ROLLBACK TO zero; RELEASE zero") {
+                log::error!("Failed to roll back: {err}.");
+            }
+        }
+    }
+}
+
+/// What every unencrypted SQLite file starts with, SQLCipher encrypts it along with the rest.
+const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+pub fn is_encrypted(path: &Path) -> std::io::Result<bool> {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(PLAIN_HEADER.len());
+    match std::fs::File::open(path) {
+        Ok(file) => file
+            .take(PLAIN_HEADER.len() as u64)
+            .read_to_end(&mut header)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    Ok(header.len() == PLAIN_HEADER.len() && header != PLAIN_HEADER)
+}
+
+/// Sets the SQLCipher key of `conn` and checks that it can read the database, without a key it
+/// is opened as plain SQLite. Has to run before anything else touches the database.
+pub fn unlock(conn: &rusqlite::Connection, path: &Path, key: Option<&str>) -> DatabaseResult<()> {
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)?;
+    }
+
+    match conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    }) {
+        Ok(_) => Ok(()),
+        Err(rusqlite::Error::SqliteFailure(failure, _))
+            if failure.code == rusqlite::ErrorCode::NotADatabase
+                && (key.is_some() || is_encrypted(path)?) =>
+        {
+            Err(Error::WrongKey)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Logs every statement `conn` runs. Only installed once the connection is unlocked, so that the
+/// key is never written to the log.
+fn trace(conn: &mut rusqlite::Connection) {
+    conn.profile(Some(|val, duration| log::trace!("{val} - {:?}", duration)));
+}
+
+/// Rewrites the closed database at `path` from the `old` key to the `new` one, encrypting or
+/// decrypting it when either is missing. SQLCipher can only change the key of a database that
+/// stays encrypted, so the database is exported to a copy that then replaces it.
+pub fn rekey(path: &Path, old: Option<&str>, new: Option<&str>) -> DatabaseResult<()> {
+    let rekeyed = path.with_extension("rekeyed.db");
+    if rekeyed.try_exists()? {
+        std::fs::remove_file(&rekeyed)?;
+    }
+
+    {
+        let conn = rusqlite::Connection::open(path)?;
+        unlock(&conn, path, old)?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        conn.execute(
+            "ATTACH DATABASE ? AS rekeyed KEY ?",
+            [rekeyed.to_string_lossy().as_ref(), new.unwrap_or_default()],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(()))?;
+        // Not part of the export, the migrations would run again without it.
+        conn.execute_batch(&format!("PRAGMA rekeyed.user_version = {version}"))?;
+        conn.execute_batch("DETACH DATABASE rekeyed")?;
+    }
+
+    crate::restore::replace(path, &rekeyed)?;
+    Ok(std::fs::remove_file(rekeyed)?)
+}
 
 #[derive(Default)]
 pub struct Database {
     conn: Option<rusqlite::Connection>,
+    key: Option<String>,
 }
 
 impl Database {
     fn conn(&self) -> DatabaseResult<&rusqlite::Connection> {
-        self.conn.as_ref().ok_or("No connection to database".into())
+        self.conn.as_ref().ok_or(Error::NoDatabaseOpen)
+    }
+
+    /// The first row a new row of `table` refers to that doesn't exist, as its table and id.
+    /// SQLite doesn't say which foreign key an insert failed on.
+    fn missing_reference(
+        &self,
+        table: &str,
+        columns: &[&str],
+        values: &[&dyn rusqlite::ToSql],
+    ) -> DatabaseResult<Option<(String, i64)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT \"from\", \"table\", \"to\" FROM pragma_foreign_key_list(?)")?;
+        let keys = stmt
+            .query_map([table], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (column, referenced, key) in keys {
+            let Some(id) = columns
+                .iter()
+                .position(|&name| name == column)
+                .and_then(|index| integer(values[index]))
+            else {
+                continue;
+            };
+            let key = key.unwrap_or_else(|| "id".to_string());
+            let exists: bool = conn.query_row(
+                &format!("SELECT EXISTS (SELECT 1 FROM {referenced} WHERE {key} = ?)"),
+                [id],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                return Ok(Some((referenced, id)));
+            }
+        }
+        Ok(None)
     }
 
-    pub fn open(&mut self, path: &PathBuf) -> DatabaseResult<()> {
+    /// Opens the database at `path`, decrypting it with `key` if given and first copying it to
+    /// `backup` if given so that the copy is taken before any migration runs.
+    pub fn open(
+        &mut self,
+        path: &PathBuf,
+        backup: Option<&Path>,
+        key: Option<&str>,
+    ) -> DatabaseResult<()> {
         let mut conn = rusqlite::Connection::open(path)?;
-        conn.profile(Some(|val, duration| log::trace!("{val} - {:?}", duration)));
-        conn.backup(
-            rusqlite::DatabaseName::Main,
-            path.with_extension("backup.db"),
+        unlock(&conn, path, key)?;
+        trace(&mut conn);
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        if let Some(backup) = backup {
+            Self::backup_connection(&conn, backup, key)?;
+        }
+        conn.execute_batch(CREATE_QUERY)?;
+        Self::migrate(&conn)?;
+
+        self.conn = Some(conn);
+        self.key = key.map(str::to_owned);
+        Ok(())
+    }
+
+    /// Copies the database to `to`, encrypted with the same key as the database.
+    pub fn backup(&self, to: &Path) -> DatabaseResult<()> {
+        Self::backup_connection(self.conn()?, to, self.key())
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn check_integrity(&self) -> DatabaseResult<IntegrityReport> {
+        integrity::check(self.conn()?)
+    }
+
+    /// Makes the repairs `report` allows, returning the rows of each sorted table that were
+    /// renumbered.
+    pub fn repair(&self, report: &IntegrityReport) -> DatabaseResult<Vec<(Table, Vec<i64>)>> {
+        if !report.is_repairable() {
+            return Ok(vec![]);
+        }
+
+        self.transaction(|db| integrity::repair(db.conn()?, report))
+    }
+
+    fn backup_connection(
+        conn: &rusqlite::Connection,
+        to: &Path,
+        key: Option<&str>,
+    ) -> DatabaseResult<()> {
+        log::info!("Backing up to: {to:?}.");
+        if let Some(folder) = to.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        // SQLCipher only copies pages between databases with the same key.
+        let mut destination = rusqlite::Connection::open(to)?;
+        if let Some(key) = key {
+            destination.pragma_update(None, "key", key)?;
+        }
+        rusqlite::backup::Backup::new(conn, &mut destination)?.run_to_completion(
+            100,
+            std::time::Duration::from_millis(250),
             Some(|progress| {
                 log::info!(
                     "Backing up: {}/{}.",
@@ -170,12 +659,62 @@ impl Database {
                 );
             }),
         )?;
+        // The copy is in WAL mode like the original, opening it read only would leave journal
+        // files next to it.
+        destination.pragma_update_and_check(None, "journal_mode", "DELETE", |row| {
+            row.get::<_, String>(0)
+        })?;
+        Ok(())
+    }
+
+    /// Opens a read only connection to a database that has already been opened for writing.
+    pub fn open_reader(&mut self, path: &PathBuf, key: Option<&str>) -> DatabaseResult<()> {
+        let mut conn = rusqlite::Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        unlock(&conn, path, key)?;
+        trace(&mut conn);
+
+        self.conn = Some(conn);
+        self.key = key.map(str::to_owned);
+        Ok(())
+    }
+
+    /// Opens a database that only lives in memory, nothing is read from or backed up to disk.
+    pub fn open_in_memory(&mut self) -> DatabaseResult<()> {
+        let mut conn = rusqlite::Connection::open_in_memory()?;
+        trace(&mut conn);
         conn.execute_batch(CREATE_QUERY)?;
+        Self::migrate(&conn)?;
 
         self.conn = Some(conn);
         Ok(())
     }
 
+    fn migrate(conn: &rusqlite::Connection) -> DatabaseResult<()> {
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        MIGRATIONS
+            .iter()
+            .enumerate()
+            .skip(version)
+            .try_for_each(|(index, migration)| {
+                log::info!("Migrating database to version {}.", index + 1);
+                conn.execute_batch(&format!(
+                    "BEGIN; {migration} PRAGMA user_version = {}; COMMIT;",
+                    index + 1
+                ))
+                .map_err(|err| {
+                    let _ = conn.execute_batch("ROLLBACK");
+                    Error::Migration {
+                        version: index + 1,
+                        message: err.to_string(),
+                    }
+                })
+            })
+    }
+
     pub fn close(&mut self) -> DatabaseResult<()> {
         let conn = self.conn.take();
         if let Some(conn) = conn {
@@ -183,10 +722,11 @@ impl Database {
                 self.conn = Some(conn);
                 Err(err.into())
             } else {
+                self.key = None;
                 Ok(())
             }
         } else {
-            Err("No connection to close.".into())
+            Err(Error::NoDatabaseOpen)
         }
     }
 
@@ -197,77 +737,77 @@ impl Database {
             .map(Into::into)
     }
 
-    pub fn add(
+    fn get_release_schedules_with_progress(
         &self,
-        table: &str,
-        params: Vec<(&str, &dyn rusqlite::ToSql)>,
-    ) -> DatabaseResult<i64> {
-        let (columns, values): (Vec<&str>, Vec<&dyn rusqlite::ToSql>) = params.into_iter().unzip();
-        let mut stmt = self.conn()?.prepare_cached(&format!(
+    ) -> DatabaseResult<Vec<(ReleaseSchedule, String)>> {
+        let mut stmt = self.conn()?.prepare_cached(
             "
-            INSERT INTO {table} ({columns}) VALUES ({placeholders})
+            SELECT release_schedules.work_id, release_schedules.kind, release_schedules.weekday,
+                   release_schedules.interval, release_schedules.start, release_schedules.offset,
+                   works.progress, group_concat(release_dates.date)
+            FROM release_schedules
+            JOIN works ON works.id = release_schedules.work_id
+            LEFT JOIN release_dates ON release_schedules.work_id = release_dates.work_id
+            GROUP BY release_schedules.work_id
         ",
-            columns = columns.join(","),
-            placeholders = vec!["?"; values.len()].join(",")
-        ))?;
-
-        Ok(stmt.insert(rusqlite::params_from_iter(values))?)
-    }
-
-    pub fn remove(&self, table: &str, id: i64) -> DatabaseResult<()> {
-        let mut stmt = self
-            .conn()?
-            .prepare_cached(&format!("DELETE FROM {table} WHERE id = :id"))?;
-
-        let rows = stmt.execute(named_params! {":id": id})?;
-
-        if rows != 1 {
-            return Err(format!(
-                "Expected to remove 1 item from table '{table}' not {rows}"
-            ))?;
-        }
-
-        Ok(())
-    }
-
-    pub fn update(
-        &self,
-        table: &str,
-        id: &dyn rusqlite::ToSql,
-        params: Vec<(&str, &dyn rusqlite::ToSql)>,
-    ) -> DatabaseResult<()> {
-        let (columns, mut values): (Vec<&str>, Vec<&dyn rusqlite::ToSql>) =
-            params.into_iter().unzip();
-        values.push(id);
-        let placeholders = columns
-            .iter()
-            .map(|&column| format!("{column} = ?"))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let mut stmt = self
-            .conn()?
-            .prepare_cached(&format!("UPDATE {table} SET {placeholders} WHERE id = ?"))?;
-
-        let rows = stmt.execute(rusqlite::params_from_iter(values))?;
+        )?;
 
-        if rows != 1 {
-            return Err(format!("Expected to update 1 row not {rows}"))?;
-        }
+        let rows = stmt.query_map([], |row| {
+            let kind: String = row.get(1)?;
+            let recurrence = match kind.as_str() {
+                "weekly" => Recurrence::Weekly {
+                    weekday: row.get(2)?,
+                    start: row.get(4)?,
+                },
+                "interval" => Recurrence::Interval {
+                    days: row.get(3)?,
+                    start: row.get(4)?,
+                },
+                _ => {
+                    let dates_row: Option<String> = row.get(7)?;
+                    let dates = dates_row
+                        .map_or(Ok(vec![]), |data| {
+                            data.split(',')
+                                .map(|d| d.parse::<i64>())
+                                .collect::<Result<Vec<_>, _>>()
+                        })
+                        .map_err(|err| rusqlite::Error::UserFunctionError(Box::new(err)))?;
+                    Recurrence::Dates { dates }
+                }
+            };
+
+            Ok((
+                ReleaseSchedule {
+                    work_id: row.get(0)?,
+                    recurrence,
+                    offset: row.get(5)?,
+                },
+                row.get(6)?,
+            ))
+        })?;
 
-        Ok(())
+        rows.map(|row| Ok(row?)).collect()
     }
 
-    pub fn get_works(&self) -> DatabaseResult<Vec<Work>> {
-        let mut stmt = self.conn()?.prepare_cached("
+    fn works_since(&self, since: Option<i64>) -> DatabaseResult<Vec<Work>> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "
             SELECT works.id, works.name, works.progress, works.status, works.type, works.format,
-                   works.updated, works.added, group_concat(work_creator.creator_id ORDER BY work_creator.ROWID)
+                   works.updated, works.added,
+                   (SELECT group_concat(creator_id) FROM (
+                       SELECT creator_id FROM work_creator WHERE work_id = works.id ORDER BY ROWID
+                   )),
+                   (SELECT group_concat(tag_id) FROM (
+                       SELECT tag_id FROM work_tag WHERE work_id = works.id ORDER BY ROWID
+                   ))
             FROM works
-            LEFT JOIN work_creator ON works.id = work_creator.work_id
-            GROUP BY works.id
-        ")?;
+            WHERE :since IS NULL
+               OR works.id IN (SELECT id FROM changes WHERE entity = 'works' AND version > :since)
+            ORDER BY works.id
+        ",
+        )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(named_params! { ":since": since }, |row| {
             let id: i64 = row.get(0)?;
             let creators_row: Option<String> = row.get(8)?;
             let creators = creators_row
@@ -277,6 +817,14 @@ impl Database {
                         .collect::<Result<Vec<_>, _>>()
                 })
                 .map_err(|err| rusqlite::Error::UserFunctionError(Box::new(err)))?;
+            let tags_row: Option<String> = row.get(9)?;
+            let tags = tags_row
+                .map_or(Ok(vec![]), |data| {
+                    data.split(',')
+                        .map(|t| t.parse::<i64>())
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map_err(|err| rusqlite::Error::UserFunctionError(Box::new(err)))?;
 
             Ok(Work {
                 id,
@@ -288,21 +836,28 @@ impl Database {
                 updated: row.get(6)?,
                 added: row.get(7)?,
                 creators,
+                tags,
             })
         })?;
 
         rows.map(|row| Ok(row?)).collect()
     }
 
-    pub fn get_creators(&self) -> DatabaseResult<Vec<Creator>> {
+    fn creators_since(&self, since: Option<i64>) -> DatabaseResult<Vec<Creator>> {
         let mut stmt = self.conn()?.prepare_cached("
-            SELECT creators.id, creators.name, group_concat(work_creator.work_id ORDER BY work_creator.ROWID)
+            SELECT creators.id, creators.name,
+                   (SELECT group_concat(work_id) FROM (
+                       SELECT work_id FROM work_creator
+                       WHERE creator_id = creators.id
+                       ORDER BY ROWID
+                   ))
             FROM creators
-            LEFT JOIN work_creator ON creators.id = work_creator.creator_id
-            GROUP BY creators.id
+            WHERE :since IS NULL
+               OR creators.id IN (SELECT id FROM changes WHERE entity = 'creators' AND version > :since)
+            ORDER BY creators.id
         ")?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(named_params! { ":since": since }, |row| {
             let id: i64 = row.get(0)?;
             let works_row: Option<String> = row.get(2)?;
             let works = works_row
@@ -323,22 +878,185 @@ impl Database {
         rows.map(|row| Ok(row?)).collect()
     }
 
-    pub fn get_statuses(&self) -> DatabaseResult<Vec<Status>> {
+    fn tags_since(&self, since: Option<i64>) -> DatabaseResult<Vec<Tag>> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "
+            SELECT id, name FROM tags
+            WHERE :since IS NULL
+               OR id IN (SELECT id FROM changes WHERE entity = 'tags' AND version > :since)
+            ORDER BY name
+        ",
+        )?;
+        let rows = stmt.query_map(named_params! { ":since": since }, |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            })
+        })?;
+
+        rows.map(|row| Ok(row?)).collect()
+    }
+
+    fn changed(&self, table: Table, since: i64) -> DatabaseResult<bool> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "SELECT EXISTS (SELECT 1 FROM changes WHERE entity = ? AND version > ?)",
+        )?;
+        Ok(stmt.query_row(rusqlite::params![table.name(), since], |row| row.get(0))?)
+    }
+
+    fn removed(&self, since: i64) -> DatabaseResult<Removed> {
+        let mut stmt = self
+            .conn()?
+            .prepare_cached("SELECT entity, id FROM changes WHERE version > ? AND deleted = 1")?;
+        let mut rows = stmt.query([since])?;
+
+        let mut removed = Removed::default();
+        while let Some(row) = rows.next()? {
+            let entity: String = row.get(0)?;
+            let ids = match entity.as_str() {
+                "works" => &mut removed.works,
+                "creators" => &mut removed.creators,
+                "statuses" => &mut removed.statuses,
+                "types" => &mut removed.types,
+                "formats" => &mut removed.formats,
+                "tags" => &mut removed.tags,
+                entity => {
+                    return Err(Error::Invalid(format!(
+                        "Removed rows of unknown entity '{entity}' in changes"
+                    )))
+                }
+            };
+            ids.push(row.get(1)?);
+        }
+
+        Ok(removed)
+    }
+}
+
+impl LibraryStore for Database {
+    fn transaction<T>(&self, f: impl FnOnce(&Self) -> DatabaseResult<T>) -> DatabaseResult<T> {
+        let savepoint = Savepoint::new(self.conn()?)?;
+        let value = f(self)?;
+        savepoint.release()?;
+        Ok(value)
+    }
+
+    fn dry_run<T>(&self, f: impl FnOnce(&Self) -> DatabaseResult<T>) -> DatabaseResult<T> {
+        let _savepoint = Savepoint::new(self.conn()?)?;
+        f(self)
+    }
+
+    fn add<R: Insert>(&self, row: &R) -> DatabaseResult<i64> {
+        let table = R::TABLE.name();
+        let (columns, values): (Vec<&str>, Vec<&dyn rusqlite::ToSql>) =
+            row.columns().into_iter().unzip();
+        let mut stmt = self.conn()?.prepare_cached(&format!(
+            "
+            INSERT INTO {table} ({columns}) VALUES ({placeholders})
+        ",
+            columns = columns.join(","),
+            placeholders = vec!["?"; values.len()].join(",")
+        ))?;
+
+        stmt.insert(rusqlite::params_from_iter(&values))
+            .map_err(|err| {
+                let name = columns
+                    .iter()
+                    .position(|&column| column == "name")
+                    .and_then(|index| text(values[index]));
+                let err = Error::from(err);
+                if let Error::ConstraintViolation { ref message, .. } = err {
+                    if message.starts_with("FOREIGN KEY") {
+                        if let Ok(Some((entity, id))) =
+                            self.missing_reference(table, &columns, &values)
+                        {
+                            return err.context(&entity, Some(id));
+                        }
+                    }
+                }
+                err.context(table, None).with_name(name)
+            })
+    }
+
+    fn remove(&self, table: Table, id: i64) -> DatabaseResult<()> {
+        let table = table.name();
+        let mut stmt = self
+            .conn()?
+            .prepare_cached(&format!("DELETE FROM {table} WHERE id = :id"))?;
+
+        let rows = stmt
+            .execute(named_params! {":id": id})
+            .map_err(|err| Error::from(err).context(table, Some(id)))?;
+
+        if rows != 1 {
+            return Err(Error::NotFound {
+                entity: table.to_string(),
+                id,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn update<C: Column>(&self, id: i64, columns: &[C]) -> DatabaseResult<()> {
+        let table = C::TABLE.name();
+        let mut values = columns.iter().map(Column::value).collect::<Vec<_>>();
+        values.push(&id);
+        let placeholders = columns
+            .iter()
+            .map(|column| format!("{} = ?", column.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
         let mut stmt = self
             .conn()?
-            .prepare_cached("SELECT id, name, is_update FROM statuses ORDER BY sort")?;
+            .prepare_cached(&format!("UPDATE {table} SET {placeholders} WHERE id = ?"))?;
+
+        let rows = stmt
+            .execute(rusqlite::params_from_iter(&values))
+            .map_err(|err| {
+                let name = columns
+                    .iter()
+                    .find(|column| column.name() == "name")
+                    .and_then(|column| text(column.value()));
+                Error::from(err).context(table, Some(id)).with_name(name)
+            })?;
+
+        if rows != 1 {
+            return Err(Error::NotFound {
+                entity: table.to_string(),
+                id,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn get_works(&self) -> DatabaseResult<Vec<Work>> {
+        self.works_since(None)
+    }
+
+    fn get_creators(&self) -> DatabaseResult<Vec<Creator>> {
+        self.creators_since(None)
+    }
+
+    fn get_statuses(&self) -> DatabaseResult<Vec<Status>> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "SELECT id, name, is_update, stale_after FROM statuses ORDER BY sort",
+        )?;
         let rows = stmt.query_map([], |row| {
             Ok(Status {
                 id: row.get(0)?,
                 name: row.get(1)?,
                 is_update: row.get(2)?,
+                stale_after: row.get(3)?,
             })
         })?;
 
         rows.map(|row| Ok(row?)).collect()
     }
 
-    pub fn get_types(&self) -> DatabaseResult<Vec<Type>> {
+    fn get_types(&self) -> DatabaseResult<Vec<Type>> {
         let mut stmt = self
             .conn()?
             .prepare_cached("SELECT * FROM types ORDER BY sort")?;
@@ -352,7 +1070,7 @@ impl Database {
         rows.map(|row| Ok(row?)).collect()
     }
 
-    pub fn get_formats(&self) -> DatabaseResult<Vec<Format>> {
+    fn get_formats(&self) -> DatabaseResult<Vec<Format>> {
         let mut stmt = self
             .conn()?
             .prepare_cached("SELECT * FROM formats ORDER BY sort")?;
@@ -366,19 +1084,20 @@ impl Database {
         rows.map(|row| Ok(row?)).collect()
     }
 
-    pub fn attach(&self, work_id: i64, creator_id: i64) -> DatabaseResult<()> {
+    fn attach(&self, work_id: i64, creator_id: i64) -> DatabaseResult<()> {
         let mut stmt = self.conn()?.prepare_cached(
             "
             INSERT INTO work_creator (work_id, creator_id) VALUES (:work_id, :creator_id)
         ",
         )?;
 
-        stmt.insert(named_params! { ":work_id": work_id, ":creator_id": creator_id })?;
+        stmt.insert(named_params! { ":work_id": work_id, ":creator_id": creator_id })
+            .map_err(|err| Error::from(err).context("work_creator", None))?;
 
         Ok(())
     }
 
-    pub fn detach(&self, work_id: i64, creator_id: i64) -> DatabaseResult<()> {
+    fn detach(&self, work_id: i64, creator_id: i64) -> DatabaseResult<()> {
         let mut stmt = self.conn()?.prepare_cached(
             "DELETE FROM work_creator WHERE work_id = :work_id AND creator_id = :creator_id",
         )?;
@@ -386,90 +1105,367 @@ impl Database {
         let rows = stmt.execute(named_params! {":work_id": work_id, ":creator_id": creator_id})?;
 
         if rows != 1 {
-            return Err(format!("Expected to detach 1 pair not {rows}"))?;
+            return Err(Error::NotLinked {
+                entity: "creators".to_string(),
+                work_id,
+                id: creator_id,
+            });
         }
 
         Ok(())
     }
 
-    pub fn reorder(&self, table: &str, active_id: &i64, over_id: &i64) -> DatabaseResult<()> {
+    fn get_tags(&self) -> DatabaseResult<Vec<Tag>> {
+        self.tags_since(None)
+    }
+
+    fn tag(&self, work_id: i64, tag_id: i64) -> DatabaseResult<()> {
         let mut stmt = self
             .conn()?
-            .prepare_cached(&format!("SELECT sort FROM {table} WHERE id = :active_id"))?;
-        let rows = stmt.query_map(named_params! {":active_id": active_id}, |row| {
-            row.get::<usize, i64>(0)
-        })?;
-        let sort_active = rows
-            .map(|row| Ok(row?))
-            .collect::<DatabaseResult<Vec<i64>>>()?;
-        let sort_active = sort_active.first().ok_or("Missing active item sort")?;
+            .prepare_cached("INSERT INTO work_tag (work_id, tag_id) VALUES (:work_id, :tag_id)")?;
+
+        stmt.insert(named_params! { ":work_id": work_id, ":tag_id": tag_id })
+            .map_err(|err| Error::from(err).context("work_tag", None))?;
+
+        Ok(())
+    }
 
+    fn untag(&self, work_id: i64, tag_id: i64) -> DatabaseResult<()> {
         let mut stmt = self
             .conn()?
-            .prepare_cached(&format!("SELECT sort FROM {table} WHERE id = :over_id"))?;
-        let rows = stmt.query_map(named_params! {":over_id": over_id}, |row| {
-            row.get::<usize, i64>(0)
-        })?;
-        let sort_over = rows
-            .map(|row| Ok(row?))
-            .collect::<DatabaseResult<Vec<i64>>>()?;
-        let sort_over = sort_over.first().ok_or("Missing over item sort")?;
+            .prepare_cached("DELETE FROM work_tag WHERE work_id = :work_id AND tag_id = :tag_id")?;
+
+        let rows = stmt.execute(named_params! {":work_id": work_id, ":tag_id": tag_id})?;
 
-        let mut stmt = self.conn()?.prepare_cached(&format!(
-            "UPDATE {table} SET sort = :sort_over WHERE id = :active_id"
-        ))?;
-        let rows =
-            stmt.execute(named_params! {":sort_over": sort_over, ":active_id": active_id})?;
         if rows != 1 {
-            return Err(format!("Expected to update 1 row not {rows}"))?;
+            return Err(Error::NotLinked {
+                entity: "tags".to_string(),
+                work_id,
+                id: tag_id,
+            });
         }
 
-        // Move item up.
-        if sort_active > sort_over {
-            let mut stmt = self.conn()?.prepare_cached(&format!(
-                "UPDATE {table} SET sort = sort + 1 WHERE sort >= :sort_over AND sort < :sort_active AND id != :active_id"
-            ))?;
-            stmt.execute(named_params! {":sort_over": sort_over, ":sort_active": sort_active, ":active_id": active_id})?;
-        }
-        // Move item down.
-        else {
-            let mut stmt = self.conn()?.prepare_cached(&format!(
-                "UPDATE {table} SET sort = sort - 1 WHERE sort <= :sort_over AND sort > :sort_active AND id != :active_id"
+        Ok(())
+    }
+
+    fn reorder(&self, table: Sorted, active_id: i64, over_id: i64) -> DatabaseResult<()> {
+        let table = table.table().name();
+        self.transaction(|db| {
+            let mut stmt = db
+                .conn()?
+                .prepare_cached(&format!("SELECT sort FROM {table} WHERE id = :active_id"))?;
+            let rows = stmt.query_map(named_params! {":active_id": active_id}, |row| {
+                row.get::<usize, i64>(0)
+            })?;
+            let sort_active = rows
+                .map(|row| Ok(row?))
+                .collect::<DatabaseResult<Vec<i64>>>()?;
+            let sort_active = sort_active.first().ok_or(Error::NotFound {
+                entity: table.to_string(),
+                id: active_id,
+            })?;
+
+            let mut stmt = db
+                .conn()?
+                .prepare_cached(&format!("SELECT sort FROM {table} WHERE id = :over_id"))?;
+            let rows = stmt.query_map(named_params! {":over_id": over_id}, |row| {
+                row.get::<usize, i64>(0)
+            })?;
+            let sort_over = rows
+                .map(|row| Ok(row?))
+                .collect::<DatabaseResult<Vec<i64>>>()?;
+            let sort_over = sort_over.first().ok_or(Error::NotFound {
+                entity: table.to_string(),
+                id: over_id,
+            })?;
+
+            let mut stmt = db.conn()?.prepare_cached(&format!(
+                "UPDATE {table} SET sort = :sort_over WHERE id = :active_id"
             ))?;
-            stmt.execute(named_params! {":sort_over": sort_over, ":sort_active": sort_active, ":active_id": active_id})?;
-        }
+            let rows =
+                stmt.execute(named_params! {":sort_over": sort_over, ":active_id": active_id})?;
+            if rows != 1 {
+                return Err(Error::NotFound { entity: table.to_string(), id: active_id });
+            }
+
+            // Move item up.
+            if sort_active > sort_over {
+                let mut stmt = db.conn()?.prepare_cached(&format!(
+                    "UPDATE {table} SET sort = sort + 1 WHERE sort >= :sort_over AND sort < :sort_active AND id != :active_id"
+                ))?;
+                stmt.execute(named_params! {":sort_over": sort_over, ":sort_active": sort_active, ":active_id": active_id})?;
+            }
+            // Move item down.
+            else {
+                let mut stmt = db.conn()?.prepare_cached(&format!(
+                    "UPDATE {table} SET sort = sort - 1 WHERE sort <= :sort_over AND sort > :sort_active AND id != :active_id"
+                ))?;
+                stmt.execute(named_params! {":sort_over": sort_over, ":sort_active": sort_active, ":active_id": active_id})?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn get_changes_since(&self, version: i64) -> DatabaseResult<Changes> {
+        self.transaction(|db| {
+            let sorted = |table| db.changed(table, version);
+
+            Ok(Changes {
+                version: db.conn()?.query_row(
+                    "SELECT coalesce(max(version), 0) FROM changes",
+                    [],
+                    |row| row.get(0),
+                )?,
+                works: db.works_since(Some(version))?,
+                creators: db.creators_since(Some(version))?,
+                statuses: sorted(Table::Statuses)?
+                    .then(|| db.get_statuses())
+                    .transpose()?,
+                types: sorted(Table::Types)?.then(|| db.get_types()).transpose()?,
+                formats: sorted(Table::Formats)?
+                    .then(|| db.get_formats())
+                    .transpose()?,
+                tags: db.tags_since(Some(version))?,
+                removed: db.removed(version)?,
+            })
+        })
+    }
+
+    fn get_stale_works(&self, timestamp: i64) -> DatabaseResult<Vec<StaleWork>> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "
+            SELECT works.id, works.updated + statuses.stale_after * :day AS due
+            FROM works
+            JOIN statuses ON works.status = statuses.id
+            WHERE statuses.is_update = 1
+              AND statuses.stale_after IS NOT NULL
+              AND due < :timestamp
+              AND (works.snoozed_until IS NULL OR works.snoozed_until <= :timestamp)
+            ORDER BY due, works.id
+        ",
+        )?;
+
+        let rows = stmt.query_map(
+            named_params! {":day": DAY, ":timestamp": timestamp},
+            |row| {
+                let due: i64 = row.get(1)?;
+                Ok(StaleWork {
+                    id: row.get(0)?,
+                    due,
+                    overdue: timestamp - due,
+                })
+            },
+        )?;
+
+        rows.map(|row| Ok(row?)).collect()
+    }
+
+    fn set_release_schedule(&self, schedule: &ReleaseSchedule) -> DatabaseResult<()> {
+        schedule.recurrence.validate().map_err(Error::Invalid)?;
+
+        let (kind, weekday, interval, start, dates) = match &schedule.recurrence {
+            Recurrence::Weekly { weekday, start } => {
+                ("weekly", Some(weekday), None, Some(start), None)
+            }
+            Recurrence::Interval { days, start } => {
+                ("interval", None, Some(days), Some(start), None)
+            }
+            Recurrence::Dates { dates } => ("dates", None, None, None, Some(dates)),
+        };
+
+        self.transaction(|db| {
+            db.remove_release_schedule(schedule.work_id)?;
+            let mut stmt = db.conn()?.prepare_cached(
+                "
+                INSERT INTO release_schedules (work_id, kind, weekday, interval, start, offset)
+                VALUES (:work_id, :kind, :weekday, :interval, :start, :offset)
+            ",
+            )?;
+            stmt.execute(named_params! {
+                ":work_id": schedule.work_id,
+                ":kind": kind,
+                ":weekday": weekday,
+                ":interval": interval,
+                ":start": start,
+                ":offset": schedule.offset,
+            })?;
+
+            let mut stmt = db.conn()?.prepare_cached(
+                "INSERT OR IGNORE INTO release_dates (work_id, date) VALUES (:work_id, :date)",
+            )?;
+            dates.into_iter().flatten().try_for_each(|date| {
+                stmt.execute(named_params! {":work_id": schedule.work_id, ":date": date})?;
+                Ok(())
+            })
+        })
+    }
+
+    fn remove_release_schedule(&self, work_id: i64) -> DatabaseResult<()> {
+        let mut stmt = self
+            .conn()?
+            .prepare_cached("DELETE FROM release_schedules WHERE work_id = :work_id")?;
+        stmt.execute(named_params! {":work_id": work_id})?;
+
+        Ok(())
+    }
+
+    fn get_release_schedules(&self, timestamp: i64) -> DatabaseResult<Vec<ScheduleState>> {
+        Ok(self
+            .get_release_schedules_with_progress()?
+            .into_iter()
+            .map(|(schedule, progress)| schedule.state(&progress, timestamp))
+            .collect())
+    }
+
+    fn upcoming_releases(&self, from: i64, to: i64) -> DatabaseResult<Vec<Release>> {
+        let mut releases = self
+            .get_release_schedules_with_progress()?
+            .iter()
+            .flat_map(|(schedule, _)| schedule.releases(from, to))
+            .collect::<Vec<_>>();
+        releases.sort_by_key(|release| (release.date, release.work_id));
+
+        Ok(releases)
+    }
+
+    fn set_external_id(&self, external_id: &ExternalId) -> DatabaseResult<()> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "
+            INSERT OR REPLACE INTO external_ids (source, external_id, work_id)
+            VALUES (:source, :external_id, :work_id)
+        ",
+        )?;
+        stmt.execute(named_params! {
+            ":source": external_id.source,
+            ":external_id": external_id.external_id,
+            ":work_id": external_id.work_id,
+        })
+        .map_err(|err| Error::from(err).context("external_ids", None))?;
 
         Ok(())
     }
+
+    fn set_export_mapping(&self, mapping: &ExportMapping) -> DatabaseResult<()> {
+        self.transaction(|db| {
+            let conn = db.conn()?;
+            for (table, column, rows) in [
+                ("status_mappings", "status_id", &mapping.statuses),
+                ("type_mappings", "type_id", &mapping.types),
+            ] {
+                conn.prepare_cached(&format!("DELETE FROM {table} WHERE service = ?"))?
+                    .execute([&mapping.service])?;
+                let mut stmt = conn.prepare_cached(&format!(
+                    "INSERT INTO {table} (service, {column}, value) VALUES (?, ?, ?)"
+                ))?;
+                for row in rows {
+                    stmt.execute(rusqlite::params![mapping.service, row.id, row.value])
+                        .map_err(|err| Error::from(err).context(table, Some(row.id)))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn get_export_mapping(&self, service: &str) -> DatabaseResult<ExportMapping> {
+        let conn = self.conn()?;
+        let rows = |table: &str, column: &str| -> DatabaseResult<Vec<Mapped>> {
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {column}, value FROM {table} WHERE service = ? ORDER BY {column}"
+            ))?;
+            let rows = stmt.query_map([service], |row| {
+                Ok(Mapped {
+                    id: row.get(0)?,
+                    value: row.get(1)?,
+                })
+            })?;
+            rows.map(|row| Ok(row?)).collect()
+        };
+
+        Ok(ExportMapping {
+            service: service.to_string(),
+            statuses: rows("status_mappings", "status_id")?,
+            types: rows("type_mappings", "type_id")?,
+        })
+    }
+
+    fn get_external_ids(&self) -> DatabaseResult<Vec<ExternalId>> {
+        let mut stmt = self.conn()?.prepare_cached(
+            "
+            SELECT work_id, source, external_id FROM external_ids ORDER BY work_id, source
+        ",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ExternalId {
+                work_id: row.get(0)?,
+                source: row.get(1)?,
+                external_id: row.get(2)?,
+            })
+        })?;
+
+        rows.map(|row| Ok(row?)).collect()
+    }
+}
+
+/// A folder in the temp dir for tests that need real files, removed with everything in it when
+/// dropped so a failing test leaves nothing behind.
+#[cfg(test)]
+pub struct TempFolder(PathBuf);
+
+#[cfg(test)]
+impl TempFolder {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir(&path).unwrap();
+        TempFolder(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+#[cfg(test)]
+impl Default for TempFolder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFolder {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
+    use crate::entity::{
+        CreatorColumn, FormatColumn, NewCreator, NewFormat, NewStatus, NewTag, NewType, NewWork,
+        StatusColumn, TypeColumn, WorkColumn,
+    };
 
     struct Context {
-        id: Uuid,
         database: Database,
     }
 
     impl Context {
         pub fn new() -> Self {
-            let id = Uuid::new_v4();
             let mut database = Database::default();
-            database
-                .open(&std::path::PathBuf::from(format!("{id}.db")))
-                .unwrap();
-            Context { id, database }
+            database.open_in_memory().unwrap();
+            Context { database }
         }
     }
 
     impl Drop for Context {
         fn drop(&mut self) {
             self.database.close().unwrap();
-            std::fs::remove_file(std::path::PathBuf::from(format!("{}.db", self.id))).unwrap();
-            std::fs::remove_file(std::path::PathBuf::from(format!("{}.backup.db", self.id)))
-                .unwrap();
         }
     }
 
@@ -478,21 +1474,25 @@ mod tests {
         let database = &Context::new().database;
 
         // Add.
-        let status_id = database.add("statuses", vec![("name", &"status")])?;
-        let type_id = database.add("types", vec![("name", &"type")])?;
-        let format_id = database.add("formats", vec![("name", &"format")])?;
-        let id = database.add(
-            "works",
-            vec![
-                ("name", &"name"),
-                ("progress", &"progress"),
-                ("status", &status_id),
-                ("type", &format_id),
-                ("format", &format_id),
-                ("updated", &44i64),
-                ("added", &44i64),
-            ],
-        )?;
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: format_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
         let works = database.get_works()?;
         let added_work = works.first().unwrap();
 
@@ -508,9 +1508,11 @@ mod tests {
 
         // Update.
         database.update(
-            "works",
-            &id,
-            vec![("name", &"new_name"), ("progress", &"new_progress")],
+            id,
+            &[
+                WorkColumn::Name("new_name".into()),
+                WorkColumn::Progress("new_progress".into()),
+            ],
         )?;
         let works = database.get_works()?;
         let added_work = works.first().unwrap();
@@ -521,7 +1523,7 @@ mod tests {
         assert_eq!(added_work.progress, "new_progress");
 
         // Remove.
-        database.remove("works", id)?;
+        database.remove(Table::Works, id)?;
 
         let works = database.get_works()?;
         assert_eq!(works.len(), 0);
@@ -534,18 +1536,15 @@ mod tests {
         let database = &Context::new().database;
 
         let error_message = database
-            .add(
-                "works",
-                vec![
-                    ("name", &"name"),
-                    ("progress", &"progress"),
-                    ("status", &1i64),
-                    ("type", &1i64),
-                    ("format", &1i64),
-                    ("updated", &44i64),
-                    ("added", &44i64),
-                ],
-            )
+            .add(&NewWork {
+                name: "name".into(),
+                progress: "progress".into(),
+                status: 1,
+                r#type: 1,
+                format: 1,
+                updated: 44,
+                added: 44,
+            })
             .unwrap_err()
             .to_string();
 
@@ -557,7 +1556,9 @@ mod tests {
         let database = &Context::new().database;
 
         // Add.
-        let id = database.add("creators", vec![("name", &"name")])?;
+        let id = database.add(&NewCreator {
+            name: "name".into(),
+        })?;
         let creators = database.get_creators()?;
         let added_creator = creators.first().unwrap();
 
@@ -566,7 +1567,7 @@ mod tests {
         assert_eq!(added_creator.name, "name");
 
         // Update.
-        database.update("creators", &id, vec![("name", &"new_name")])?;
+        database.update(id, &[CreatorColumn::Name("new_name".into())])?;
         let creators = database.get_creators()?;
         let added_creator = creators.first().unwrap();
 
@@ -575,7 +1576,7 @@ mod tests {
         assert_eq!(added_creator.name, "new_name");
 
         // Remove.
-        database.remove("creators", id)?;
+        database.remove(Table::Creators, id)?;
 
         let creators = database.get_creators()?;
         assert_eq!(creators.len(), 0);
@@ -588,7 +1589,10 @@ mod tests {
         let database = &Context::new().database;
 
         // Add.
-        let id = database.add("statuses", vec![("name", &"status")])?;
+        let id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
         let statuses = database.get_statuses()?;
         let added_status = statuses.first().unwrap();
 
@@ -597,7 +1601,7 @@ mod tests {
         assert_eq!(added_status.name, "status");
 
         // Update.
-        database.update("statuses", &id, vec![("name", &"new_name")])?;
+        database.update(id, &[StatusColumn::Name("new_name".into())])?;
         let statuses = database.get_statuses()?;
         let added_status = statuses.first().unwrap();
 
@@ -606,7 +1610,7 @@ mod tests {
         assert_eq!(added_status.name, "new_name");
 
         // Remove.
-        database.remove("statuses", id)?;
+        database.remove(Table::Statuses, id)?;
 
         let statuses = database.get_statuses()?;
         assert_eq!(statuses.len(), 0);
@@ -619,7 +1623,9 @@ mod tests {
         let database = &Context::new().database;
 
         // Add.
-        let id = database.add("types", vec![("name", &"type")])?;
+        let id = database.add(&NewType {
+            name: "type".into(),
+        })?;
         let types = database.get_types()?;
         let added_type = types.first().unwrap();
 
@@ -628,7 +1634,7 @@ mod tests {
         assert_eq!(added_type.name, "type");
 
         // Update.
-        database.update("types", &id, vec![("name", &"new_name")])?;
+        database.update(id, &[TypeColumn::Name("new_name".into())])?;
         let types = database.get_types()?;
         let added_type = types.first().unwrap();
 
@@ -637,7 +1643,7 @@ mod tests {
         assert_eq!(added_type.name, "new_name");
 
         // Remove.
-        database.remove("types", id)?;
+        database.remove(Table::Types, id)?;
 
         let types = database.get_types()?;
         assert_eq!(types.len(), 0);
@@ -650,7 +1656,9 @@ mod tests {
         let database = &Context::new().database;
 
         // Add.
-        let id = database.add("formats", vec![("name", &"format")])?;
+        let id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
         let formats = database.get_formats()?;
         let added_format = formats.first().unwrap();
 
@@ -659,7 +1667,7 @@ mod tests {
         assert_eq!(added_format.name, "format");
 
         // Update.
-        database.update("formats", &id, vec![("name", &"new_name")])?;
+        database.update(id, &[FormatColumn::Name("new_name".into())])?;
         let formats = database.get_formats()?;
         let added_format = formats.first().unwrap();
 
@@ -668,7 +1676,7 @@ mod tests {
         assert_eq!(added_format.name, "new_name");
 
         // Remove.
-        database.remove("formats", id)?;
+        database.remove(Table::Formats, id)?;
 
         let formats = database.get_formats()?;
         assert_eq!(formats.len(), 0);
@@ -681,28 +1689,35 @@ mod tests {
     ) -> Result<(), Box<dyn std::error::Error>> {
         let database = &Context::new().database;
 
-        let status_id = database.add("statuses", vec![("name", &"status")])?;
-        let type_id = database.add("types", vec![("name", &"type")])?;
-        let format_id = database.add("formats", vec![("name", &"format")])?;
-        database.add(
-            "works",
-            vec![
-                ("name", &"name"),
-                ("progress", &"progress"),
-                ("status", &status_id),
-                ("type", &type_id),
-                ("format", &format_id),
-                ("updated", &44i64),
-                ("added", &44i64),
-            ],
-        )?;
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
         let status_error_message = database
-            .remove("statuses", status_id)
+            .remove(Table::Statuses, status_id)
+            .unwrap_err()
+            .to_string();
+        let type_error_message = database
+            .remove(Table::Types, type_id)
             .unwrap_err()
             .to_string();
-        let type_error_message = database.remove("types", type_id).unwrap_err().to_string();
         let format_error_message = database
-            .remove("formats", format_id)
+            .remove(Table::Formats, format_id)
             .unwrap_err()
             .to_string();
 
@@ -713,26 +1728,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn reports_entity_of_failed_constraint() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+
+        let error = database.remove(Table::Statuses, status_id).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ConstraintViolation { entity: Some(ref entity), id: Some(id), .. }
+                if entity == "statuses" && id == status_id
+        ));
+
+        let error = database
+            .add(&NewStatus {
+                name: "status".into(),
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::UniqueNameConflict { ref entity, name: Some(ref name) }
+                if entity == "statuses" && name == "status"
+        ));
+
+        let error = database.remove(Table::Statuses, 100).unwrap_err();
+        assert!(matches!(error, Error::NotFound { ref entity, id: 100 } if entity == "statuses"));
+
+        let error = database
+            .add(&NewWork {
+                name: "other".into(),
+                progress: "progress".into(),
+                status: status_id,
+                r#type: 100,
+                format: format_id,
+                updated: 44,
+                added: 44,
+            })
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ConstraintViolation { entity: Some(ref entity), id: Some(100), .. }
+                if entity == "types"
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn can_attach_and_detach() -> Result<(), Box<dyn std::error::Error>> {
         let database = &Context::new().database;
 
-        let status_id = database.add("statuses", vec![("name", &"status")])?;
-        let type_id = database.add("types", vec![("name", &"type")])?;
-        let format_id = database.add("formats", vec![("name", &"format")])?;
-        let work_id = database.add(
-            "works",
-            vec![
-                ("name", &"name"),
-                ("progress", &"progress"),
-                ("status", &status_id),
-                ("type", &type_id),
-                ("format", &format_id),
-                ("updated", &44i64),
-                ("added", &44i64),
-            ],
-        )?;
-        let creator_id = database.add("creators", vec![("name", &"name")])?;
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+        let creator_id = database.add(&NewCreator {
+            name: "name".into(),
+        })?;
         database.attach(work_id, creator_id)?;
         let works = database.get_works()?;
         let added_work = works.first().unwrap();
@@ -766,31 +1853,37 @@ mod tests {
 
         let error_message = database.detach(1, 1).unwrap_err().to_string();
 
-        assert_eq!(error_message, "Expected to detach 1 pair not 0");
+        assert_eq!(error_message, "Work [1] is not linked to creators [1]");
     }
 
     #[test]
     fn removing_work_detaches() -> Result<(), Box<dyn std::error::Error>> {
         let database = &Context::new().database;
 
-        let status_id = database.add("statuses", vec![("name", &"status")])?;
-        let type_id = database.add("types", vec![("name", &"type")])?;
-        let format_id = database.add("formats", vec![("name", &"format")])?;
-        let work_id = database.add(
-            "works",
-            vec![
-                ("name", &"name"),
-                ("progress", &"progress"),
-                ("status", &status_id),
-                ("type", &type_id),
-                ("format", &format_id),
-                ("updated", &44i64),
-                ("added", &44i64),
-            ],
-        )?;
-        let creator_id = database.add("creators", vec![("name", &"name")])?;
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+        let creator_id = database.add(&NewCreator {
+            name: "name".into(),
+        })?;
         database.attach(work_id, creator_id)?;
-        database.remove("works", work_id)?;
+        database.remove(Table::Works, work_id)?;
         let creators = database.get_creators()?;
         let added_creator = creators.first().unwrap();
 
@@ -803,24 +1896,30 @@ mod tests {
     fn removing_creator_detaches() -> Result<(), Box<dyn std::error::Error>> {
         let database = &Context::new().database;
 
-        let status_id = database.add("statuses", vec![("name", &"status")])?;
-        let type_id = database.add("types", vec![("name", &"type")])?;
-        let format_id = database.add("formats", vec![("name", &"format")])?;
-        let work_id = database.add(
-            "works",
-            vec![
-                ("name", &"name"),
-                ("progress", &"progress"),
-                ("status", &status_id),
-                ("type", &type_id),
-                ("format", &format_id),
-                ("updated", &44i64),
-                ("added", &44i64),
-            ],
-        )?;
-        let creator_id = database.add("creators", vec![("name", &"name")])?;
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+        let creator_id = database.add(&NewCreator {
+            name: "name".into(),
+        })?;
         database.attach(work_id, creator_id)?;
-        database.remove("creators", creator_id)?;
+        database.remove(Table::Creators, creator_id)?;
         let works = database.get_works()?;
         let added_work = works.first().unwrap();
 
@@ -833,17 +1932,721 @@ mod tests {
     fn can_reorder_works() -> Result<(), Box<dyn std::error::Error>> {
         let database = &Context::new().database;
 
-        let status_id_0 = database.add("statuses", vec![("name", &"status0")])?;
-        let status_id_1 = database.add("statuses", vec![("name", &"status1")])?;
+        let status_id_0 = database.add(&NewStatus {
+            name: "status0".into(),
+            ..Default::default()
+        })?;
+        let status_id_1 = database.add(&NewStatus {
+            name: "status1".into(),
+            ..Default::default()
+        })?;
 
         let statuses = database.get_statuses()?;
         assert_eq!(statuses.first().unwrap().id, status_id_0);
 
-        database.reorder("statuses", &status_id_0, &status_id_1)?;
+        database.reorder(Sorted::Statuses, status_id_0, status_id_1)?;
 
         let statuses = database.get_statuses()?;
         assert_eq!(statuses.first().unwrap().id, status_id_1);
 
         Ok(())
     }
+
+    #[test]
+    fn migrates_existing_database() -> Result<(), Box<dyn std::error::Error>> {
+        let folder = TempFolder::new();
+        let path = folder.join("library.db");
+        rusqlite::Connection::open(&path)?.execute_batch(CREATE_QUERY)?;
+
+        let backup = folder.join("library.backup.db");
+        let mut database = Database::default();
+        database.open(&path, Some(&backup), None)?;
+        let version: usize = database
+            .conn()?
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        database.close()?;
+        database.open(&path, None, None)?;
+        database.add(&NewStatus {
+            name: "status".into(),
+            stale_after: Some(7),
+            ..Default::default()
+        })?;
+        database.close()?;
+
+        let backup_version: usize =
+            rusqlite::Connection::open(&backup)?
+                .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        assert_eq!(version, MIGRATIONS.len());
+        assert_eq!(backup_version, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn opens_encrypted_database_only_with_key() -> Result<(), Box<dyn std::error::Error>> {
+        let folder = TempFolder::new();
+        let path = folder.join("library.db");
+        let backup = folder.join("library.backup.db");
+        let mut database = Database::default();
+        database.open(&path, None, Some("secret"))?;
+        database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        database.backup(&backup)?;
+        database.close()?;
+
+        assert!(matches!(
+            database.open(&path, None, None),
+            Err(Error::WrongKey)
+        ));
+        assert!(matches!(
+            database.open(&path, None, Some("wrong")),
+            Err(Error::WrongKey)
+        ));
+        assert!(matches!(
+            database.open(&backup, None, None),
+            Err(Error::WrongKey)
+        ));
+        database.open(&backup, None, Some("secret"))?;
+        assert_eq!(database.get_statuses()?.len(), 1);
+        database.close()?;
+
+        rekey(&path, Some("secret"), None)?;
+        database.open(&path, None, None)?;
+        let version: usize = database
+            .conn()?
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(version, MIGRATIONS.len());
+        assert_eq!(database.get_statuses()?.len(), 1);
+        database.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn never_logs_key() -> Result<(), Box<dyn std::error::Error>> {
+        struct Logger(std::sync::Mutex<Vec<String>>);
+
+        impl log::Log for Logger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Logger = Logger(std::sync::Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let folder = TempFolder::new();
+        let path = folder.join("library.db");
+        let key = format!("key-{}", uuid::Uuid::new_v4());
+        let mut database = Database::default();
+        database.open(&path, None, Some(&key))?;
+        database.close()?;
+        database.open_reader(&path, Some(&key))?;
+        database.get_works()?;
+        database.close()?;
+        rekey(&path, Some(&key), Some(&format!("{key}-new")))?;
+
+        let logged = LOGGER.0.lock().unwrap();
+        assert!(logged
+            .iter()
+            .any(|line| line.contains("PRAGMA user_version")));
+        assert!(logged.iter().all(|line| !line.contains(&key)));
+        drop(logged);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_get_stale_works() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let update_id = database.add(&NewStatus {
+            name: "update".into(),
+            is_update: true,
+            stale_after: Some(2),
+        })?;
+        let other_id = database.add(&NewStatus {
+            name: "other".into(),
+            stale_after: Some(2),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_ids = [
+            (update_id, 0),
+            (update_id, DAY),
+            (update_id, 5 * DAY),
+            (other_id, 0),
+        ]
+        .iter()
+        .map(|(status_id, updated)| {
+            database.add(&NewWork {
+                name: "name".into(),
+                progress: "progress".into(),
+                status: *status_id,
+                r#type: type_id,
+                format: format_id,
+                updated: *updated,
+                added: 0,
+            })
+        })
+        .collect::<DatabaseResult<Vec<_>>>()?;
+
+        let stale = database.get_stale_works(4 * DAY)?;
+        assert_eq!(
+            stale,
+            vec![
+                StaleWork {
+                    id: work_ids[0],
+                    due: 2 * DAY,
+                    overdue: 2 * DAY
+                },
+                StaleWork {
+                    id: work_ids[1],
+                    due: 3 * DAY,
+                    overdue: DAY
+                },
+            ]
+        );
+
+        database.update(work_ids[0], &[WorkColumn::SnoozedUntil(Some(5 * DAY))])?;
+        let stale = database.get_stale_works(4 * DAY)?;
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale.first().unwrap().id, work_ids[1]);
+
+        let stale = database.get_stale_works(5 * DAY)?;
+        assert_eq!(stale.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_rolls_back_on_error() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let error_message = database
+            .transaction(|db| {
+                let work_id = db.add(&NewWork {
+                    name: "name".into(),
+                    progress: "progress".into(),
+                    status: status_id,
+                    r#type: type_id,
+                    format: format_id,
+                    updated: 44,
+                    added: 44,
+                })?;
+                db.attach(work_id, 1)
+            })
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(error_message, "FOREIGN KEY constraint failed");
+        assert_eq!(database.get_works()?.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_transaction_rolls_back_only_itself() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        database.transaction(|db| {
+            db.add(&NewCreator {
+                name: "outer".into(),
+            })?;
+            let inner = db.transaction(|db| {
+                db.add(&NewCreator {
+                    name: "inner".into(),
+                })?;
+                db.remove(Table::Creators, 100)
+            });
+            assert!(inner.is_err());
+            Ok(())
+        })?;
+        let creators = database.get_creators()?;
+
+        assert_eq!(creators.len(), 1);
+        assert_eq!(creators.first().unwrap().name, "outer");
+
+        Ok(())
+    }
+
+    #[test]
+    fn catches_reorder_with_missing_item() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let error = database
+            .reorder(Sorted::Statuses, status_id, 100)
+            .unwrap_err();
+
+        assert!(matches!(error, Error::NotFound { id: 100, .. }));
+        assert!(database.conn()?.is_autocommit());
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_tag_and_untag() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+        let tag_id = database.add(&NewTag { name: "tag".into() })?;
+        database.tag(work_id, tag_id)?;
+        let works = database.get_works()?;
+
+        assert_eq!(database.get_tags()?.len(), 1);
+        assert_eq!(works.first().unwrap().tags, vec![tag_id]);
+
+        database.untag(work_id, tag_id)?;
+        let works = database.get_works()?;
+
+        assert_eq!(works.first().unwrap().tags.len(), 0);
+
+        let error = database.untag(work_id, tag_id).unwrap_err();
+        assert!(matches!(error, Error::NotLinked { .. }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_get_changes_since() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let creator_id = database.add(&NewCreator {
+            name: "creator".into(),
+        })?;
+        let work_ids = (0..3)
+            .map(|_| {
+                database.add(&NewWork {
+                    name: "name".into(),
+                    progress: "progress".into(),
+                    status: status_id,
+                    r#type: type_id,
+                    format: format_id,
+                    updated: 44,
+                    added: 44,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let all = database.get_changes_since(0)?;
+
+        assert_eq!(all.works.len(), 3);
+        assert_eq!(all.statuses.map(|statuses| statuses.len()), Some(1));
+
+        database.update(work_ids[0], &[WorkColumn::Name("new".into())])?;
+        database.attach(work_ids[1], creator_id)?;
+        database.remove(Table::Works, work_ids[2])?;
+        let changes = database.get_changes_since(all.version)?;
+
+        assert!(changes.version > all.version);
+        assert_eq!(
+            changes.works.iter().map(|work| work.id).collect::<Vec<_>>(),
+            work_ids[0..2]
+        );
+        assert_eq!(changes.creators.len(), 1);
+        assert_eq!(changes.creators.first().unwrap().works, vec![work_ids[1]]);
+        assert!(changes.statuses.is_none());
+        assert_eq!(changes.removed.works, vec![work_ids[2]]);
+
+        let unchanged = database.get_changes_since(changes.version)?;
+
+        assert_eq!(unchanged.version, changes.version);
+        assert!(unchanged.works.is_empty() && unchanged.removed.works.is_empty());
+
+        database.conn()?.execute(
+            "INSERT INTO changes (entity, id, version, deleted) VALUES ('unknown', 1, ?, 1)",
+            [changes.version + 1],
+        )?;
+        assert!(matches!(
+            database.get_changes_since(changes.version),
+            Err(Error::Invalid(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn checks_and_repairs_integrity() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_ids = ["a", "b", "c"].map(|name| {
+            database.add(&NewStatus {
+                name: name.into(),
+                ..Default::default()
+            })
+        });
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        // Left behind by older versions or edits made outside the app without foreign keys.
+        database.conn()?.execute_batch(&format!(
+            "
+            PRAGMA foreign_keys = OFF;
+            UPDATE statuses SET sort = sort * 2 + 1;
+            INSERT INTO works (name, progress, status, type, format, updated, added)
+            VALUES ('name', 'progress', 44, {type_id}, {format_id}, 0, 0);
+            INSERT INTO work_creator (work_id, creator_id) VALUES (last_insert_rowid(), 44);
+            PRAGMA foreign_keys = ON;
+        "
+        ))?;
+        let work_id: i64 = database
+            .conn()?
+            .query_row("SELECT max(id) FROM works", [], |row| row.get(0))?;
+
+        let report = database.check_integrity()?;
+
+        assert!(report.corruption.is_empty());
+        assert_eq!(report.unsorted, vec!["statuses"]);
+        assert_eq!(report.orphaned.len(), 1);
+        assert_eq!(report.orphaned[0].table, "work_creator");
+        assert_eq!(report.broken_works.len(), 1);
+        assert_eq!(report.broken_works[0].rowid, work_id);
+
+        let corrupt = IntegrityReport {
+            corruption: vec!["Page 2 is never used".into()],
+            broken_works: vec![],
+            orphaned: report.orphaned.clone(),
+            unsorted: report.unsorted.clone(),
+        };
+        assert!(!corrupt.is_repairable());
+        assert!(database.repair(&corrupt)?.is_empty());
+        assert_eq!(database.check_integrity()?, report);
+
+        database.repair(&report)?;
+        let remaining = database.check_integrity()?;
+
+        assert!(!remaining.is_ok() && !remaining.is_repairable());
+        assert_eq!(remaining.broken_works, report.broken_works);
+        assert_eq!(
+            database
+                .get_statuses()?
+                .into_iter()
+                .map(|status| status.id)
+                .collect::<Vec<_>>(),
+            status_ids.into_iter().collect::<Result<Vec<_>, _>>()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_edit_reports_failures_per_id() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id_0 = database.add(&NewStatus {
+            name: "status0".into(),
+            ..Default::default()
+        })?;
+        let status_id_1 = database.add(&NewStatus {
+            name: "status1".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_ids = (0..3)
+            .map(|_| {
+                database.add(&NewWork {
+                    name: "name".into(),
+                    progress: "progress".into(),
+                    status: status_id_0,
+                    r#type: type_id,
+                    format: format_id,
+                    updated: 44,
+                    added: 44,
+                })
+            })
+            .collect::<DatabaseResult<Vec<_>>>()?;
+        let creator_id = database.add(&NewCreator {
+            name: "name".into(),
+        })?;
+
+        let ids = [work_ids[0], 100, work_ids[2]];
+        let result = database.bulk_edit(
+            &ids,
+            &BulkEdit::SetStatus {
+                status: status_id_1,
+                timestamp: 55,
+            },
+        )?;
+
+        assert_eq!(result.succeeded, vec![work_ids[0], work_ids[2]]);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.failed[0].id, 100);
+        assert!(matches!(
+            result.failed[0].error,
+            Error::NotFound { id: 100, .. }
+        ));
+
+        let works = database.get_works()?;
+        assert_eq!(works[0].status, status_id_1);
+        assert_eq!(works[0].updated, 55);
+        assert_eq!(works[1].status, status_id_0);
+        assert_eq!(works[2].status, status_id_1);
+
+        let result = database.bulk_edit(&work_ids, &BulkEdit::Attach { creator_id })?;
+        assert_eq!(result.succeeded.len(), 3);
+        let result = database.bulk_edit(&work_ids, &BulkEdit::Attach { creator_id })?;
+        assert_eq!(result.failed.len(), 3);
+
+        let result = database.bulk_edit(&work_ids[..2], &BulkEdit::Remove)?;
+        assert_eq!(result.succeeded.len(), 2);
+        assert_eq!(database.get_works()?.len(), 1);
+        assert_eq!(
+            database.get_creators()?.first().unwrap().works,
+            vec![work_ids[2]]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_edit_rolls_back_failed_id() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+
+        let result = database.bulk_edit(
+            &[work_id],
+            &BulkEdit::SetStatus {
+                status: 100,
+                timestamp: 55,
+            },
+        )?;
+
+        assert_eq!(
+            result.failed.first().unwrap().error.to_string(),
+            "FOREIGN KEY constraint failed"
+        );
+        assert_eq!(database.get_works()?.first().unwrap().status, status_id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_set_and_remove_release_schedule() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_id = database.add(&NewWork {
+            name: "name".into(),
+            progress: "2".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+        })?;
+
+        let schedule = ReleaseSchedule {
+            work_id,
+            recurrence: Recurrence::Dates {
+                dates: vec![10, 20, 30],
+            },
+            offset: 0,
+        };
+        database.set_release_schedule(&schedule)?;
+        let schedules = database.get_release_schedules(30)?;
+        let state = schedules.first().unwrap();
+
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(state.schedule, schedule);
+        assert_eq!(state.released, 3);
+        assert_eq!(state.next_release, None);
+        assert!(state.behind);
+
+        let schedule = ReleaseSchedule {
+            work_id,
+            recurrence: Recurrence::Interval { days: 1, start: 0 },
+            offset: 1,
+        };
+        database.set_release_schedule(&schedule)?;
+        let schedules = database.get_release_schedules(0)?;
+        let state = schedules.first().unwrap();
+
+        assert_eq!(state.schedule, schedule);
+        assert_eq!(state.released, 2);
+        assert!(!state.behind);
+
+        database.remove_release_schedule(work_id)?;
+        assert_eq!(database.get_release_schedules(0)?.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_get_upcoming_releases() -> Result<(), Box<dyn std::error::Error>> {
+        let database = &Context::new().database;
+
+        let status_id = database.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = database.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = database.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let work_ids = (0..2)
+            .map(|_| {
+                database.add(&NewWork {
+                    name: "name".into(),
+                    progress: "progress".into(),
+                    status: status_id,
+                    r#type: type_id,
+                    format: format_id,
+                    updated: 44,
+                    added: 44,
+                })
+            })
+            .collect::<DatabaseResult<Vec<_>>>()?;
+
+        database.set_release_schedule(&ReleaseSchedule {
+            work_id: work_ids[0],
+            recurrence: Recurrence::Dates {
+                dates: vec![15, 40],
+            },
+            offset: 0,
+        })?;
+        database.set_release_schedule(&ReleaseSchedule {
+            work_id: work_ids[1],
+            recurrence: Recurrence::Dates {
+                dates: vec![5, 20, 35],
+            },
+            offset: 4,
+        })?;
+
+        let releases = database.upcoming_releases(10, 40)?;
+        assert_eq!(
+            releases,
+            vec![
+                Release {
+                    work_id: work_ids[0],
+                    date: 15,
+                    number: 1
+                },
+                Release {
+                    work_id: work_ids[1],
+                    date: 20,
+                    number: 6
+                },
+                Release {
+                    work_id: work_ids[1],
+                    date: 35,
+                    number: 7
+                },
+            ]
+        );
+
+        database.remove(Table::Works, work_ids[0])?;
+        assert_eq!(database.upcoming_releases(10, 40)?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn catches_release_schedule_for_invalid_work() {
+        let database = &Context::new().database;
+
+        let error_message = database
+            .set_release_schedule(&ReleaseSchedule {
+                work_id: 1,
+                recurrence: Recurrence::Interval { days: 7, start: 0 },
+                offset: 0,
+            })
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(error_message, "FOREIGN KEY constraint failed");
+    }
 }
@@ -0,0 +1,211 @@
+use rusqlite::ToSql;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Table {
+    Works,
+    Creators,
+    Statuses,
+    Types,
+    Formats,
+    Tags,
+}
+
+impl Table {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Table::Works => "works",
+            Table::Creators => "creators",
+            Table::Statuses => "statuses",
+            Table::Types => "types",
+            Table::Formats => "formats",
+            Table::Tags => "tags",
+        }
+    }
+}
+
+/// Tables with a user defined `sort` order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sorted {
+    Statuses,
+    Types,
+    Formats,
+}
+
+impl Sorted {
+    pub const fn table(self) -> Table {
+        match self {
+            Sorted::Statuses => Table::Statuses,
+            Sorted::Types => Table::Types,
+            Sorted::Formats => Table::Formats,
+        }
+    }
+}
+
+/// A single column of a table together with the value to write to it.
+pub trait Column {
+    const TABLE: Table;
+
+    fn name(&self) -> &'static str;
+    fn value(&self) -> &dyn ToSql;
+}
+
+/// A full row that can be inserted into a table.
+pub trait Insert {
+    const TABLE: Table;
+
+    fn columns(&self) -> Vec<(&'static str, &dyn ToSql)>;
+}
+
+#[derive(Debug)]
+pub enum WorkColumn {
+    Name(String),
+    Progress(String),
+    Status(i64),
+    Type(i64),
+    Format(i64),
+    Updated(i64),
+    SnoozedUntil(Option<i64>),
+}
+
+impl Column for WorkColumn {
+    const TABLE: Table = Table::Works;
+
+    fn name(&self) -> &'static str {
+        match self {
+            WorkColumn::Name(_) => "name",
+            WorkColumn::Progress(_) => "progress",
+            WorkColumn::Status(_) => "status",
+            WorkColumn::Type(_) => "type",
+            WorkColumn::Format(_) => "format",
+            WorkColumn::Updated(_) => "updated",
+            WorkColumn::SnoozedUntil(_) => "snoozed_until",
+        }
+    }
+
+    fn value(&self) -> &dyn ToSql {
+        match self {
+            WorkColumn::Name(value) | WorkColumn::Progress(value) => value,
+            WorkColumn::Status(value)
+            | WorkColumn::Type(value)
+            | WorkColumn::Format(value)
+            | WorkColumn::Updated(value) => value,
+            WorkColumn::SnoozedUntil(value) => value,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StatusColumn {
+    Name(String),
+    IsUpdate(bool),
+    StaleAfter(Option<i64>),
+}
+
+impl Column for StatusColumn {
+    const TABLE: Table = Table::Statuses;
+
+    fn name(&self) -> &'static str {
+        match self {
+            StatusColumn::Name(_) => "name",
+            StatusColumn::IsUpdate(_) => "is_update",
+            StatusColumn::StaleAfter(_) => "stale_after",
+        }
+    }
+
+    fn value(&self) -> &dyn ToSql {
+        match self {
+            StatusColumn::Name(value) => value,
+            StatusColumn::IsUpdate(value) => value,
+            StatusColumn::StaleAfter(value) => value,
+        }
+    }
+}
+
+macro_rules! named_entity {
+    ($column:ident, $new:ident, $table:expr) => {
+        #[derive(Debug)]
+        pub enum $column {
+            Name(String),
+        }
+
+        impl Column for $column {
+            const TABLE: Table = $table;
+
+            fn name(&self) -> &'static str {
+                match self {
+                    $column::Name(_) => "name",
+                }
+            }
+
+            fn value(&self) -> &dyn ToSql {
+                match self {
+                    $column::Name(value) => value,
+                }
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct $new {
+            pub name: String,
+        }
+
+        impl Insert for $new {
+            const TABLE: Table = $table;
+
+            fn columns(&self) -> Vec<(&'static str, &dyn ToSql)> {
+                vec![("name", &self.name)]
+            }
+        }
+    };
+}
+
+named_entity!(CreatorColumn, NewCreator, Table::Creators);
+named_entity!(TypeColumn, NewType, Table::Types);
+named_entity!(FormatColumn, NewFormat, Table::Formats);
+named_entity!(TagColumn, NewTag, Table::Tags);
+
+#[derive(Debug)]
+pub struct NewWork {
+    pub name: String,
+    pub progress: String,
+    pub status: i64,
+    pub r#type: i64,
+    pub format: i64,
+    pub updated: i64,
+    pub added: i64,
+}
+
+impl Insert for NewWork {
+    const TABLE: Table = Table::Works;
+
+    fn columns(&self) -> Vec<(&'static str, &dyn ToSql)> {
+        vec![
+            ("name", &self.name),
+            ("progress", &self.progress),
+            ("status", &self.status),
+            ("type", &self.r#type),
+            ("format", &self.format),
+            ("updated", &self.updated),
+            ("added", &self.added),
+        ]
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NewStatus {
+    pub name: String,
+    pub is_update: bool,
+    pub stale_after: Option<i64>,
+}
+
+impl Insert for NewStatus {
+    const TABLE: Table = Table::Statuses;
+
+    fn columns(&self) -> Vec<(&'static str, &dyn ToSql)> {
+        vec![
+            ("name", &self.name),
+            ("is_update", &self.is_update),
+            ("stale_after", &self.stale_after),
+        ]
+    }
+}
@@ -0,0 +1,262 @@
+use serde::ser::SerializeMap;
+
+#[derive(Debug)]
+pub enum Error {
+    NoDatabaseOpen,
+    NotFound {
+        entity: String,
+        id: i64,
+    },
+    NotLinked {
+        entity: String,
+        work_id: i64,
+        id: i64,
+    },
+    ConstraintViolation {
+        entity: Option<String>,
+        id: Option<i64>,
+        message: String,
+    },
+    UniqueNameConflict {
+        entity: String,
+        name: Option<String>,
+    },
+    Migration {
+        version: usize,
+        message: String,
+    },
+    Invalid(String),
+    WrongKey,
+    /// `error` stopped a change to the database file and putting the old file back failed too.
+    RollbackFailed {
+        error: Box<Error>,
+        rollback: Box<Error>,
+    },
+    Io(std::io::Error),
+    Database(rusqlite::Error),
+    Tauri(tauri::Error),
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NoDatabaseOpen => "noDatabaseOpen",
+            Error::NotFound { .. } => "notFound",
+            Error::NotLinked { .. } => "notLinked",
+            Error::ConstraintViolation { .. } => "constraintViolation",
+            Error::UniqueNameConflict { .. } => "uniqueNameConflict",
+            Error::Migration { .. } => "migration",
+            Error::Invalid(_) => "invalid",
+            Error::WrongKey => "wrongKey",
+            Error::RollbackFailed { error, .. } => error.code(),
+            Error::Io(_) => "io",
+            Error::Database(_) => "database",
+            Error::Tauri(_) => "application",
+        }
+    }
+
+    /// Fills in the entity a constraint failure happened on, SQLite only reports it for some.
+    pub fn context(self, entity: &str, id: Option<i64>) -> Self {
+        match self {
+            Error::ConstraintViolation {
+                entity: None,
+                id: None,
+                message,
+            } => Error::ConstraintViolation {
+                entity: Some(entity.to_string()),
+                id,
+                message,
+            },
+            err => err,
+        }
+    }
+
+    pub fn with_name(self, name: Option<String>) -> Self {
+        match self {
+            Error::UniqueNameConflict { entity, name: None } => {
+                Error::UniqueNameConflict { entity, name }
+            }
+            err => err,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoDatabaseOpen => write!(f, "No connection to database"),
+            Error::NotFound { entity, id } => write!(f, "No {entity} with id {id}"),
+            Error::NotLinked {
+                entity,
+                work_id,
+                id,
+            } => {
+                write!(f, "Work [{work_id}] is not linked to {entity} [{id}]")
+            }
+            Error::ConstraintViolation { message, .. } => write!(f, "{message}"),
+            Error::UniqueNameConflict {
+                entity,
+                name: Some(name),
+            } => {
+                write!(f, "Name '{name}' is already used in {entity}")
+            }
+            Error::UniqueNameConflict { entity, name: None } => {
+                write!(f, "Name is already used in {entity}")
+            }
+            Error::Migration { version, message } => {
+                write!(
+                    f,
+                    "Failed to migrate database to version {version}: {message}"
+                )
+            }
+            Error::Invalid(message) => write!(f, "{message}"),
+            Error::WrongKey => write!(f, "Wrong or missing password for encrypted database"),
+            Error::RollbackFailed { error, rollback } => {
+                write!(
+                    f,
+                    "{error}. Putting the database back failed too: {rollback}"
+                )
+            }
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Database(err) => write!(f, "{err}"),
+            Error::Tauri(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Database(err) => Some(err),
+            Error::Tauri(err) => Some(err),
+            Error::RollbackFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            Error::NotFound { entity, id } => {
+                map.serialize_entry("entity", entity)?;
+                map.serialize_entry("id", id)?;
+            }
+            Error::NotLinked {
+                entity,
+                work_id,
+                id,
+            } => {
+                map.serialize_entry("entity", entity)?;
+                map.serialize_entry("workId", work_id)?;
+                map.serialize_entry("id", id)?;
+            }
+            Error::ConstraintViolation { entity, id, .. } => {
+                map.serialize_entry("entity", entity)?;
+                map.serialize_entry("id", id)?;
+            }
+            Error::UniqueNameConflict { entity, name } => {
+                map.serialize_entry("entity", entity)?;
+                map.serialize_entry("name", name)?;
+            }
+            Error::Migration { version, .. } => {
+                map.serialize_entry("version", version)?;
+            }
+            Error::RollbackFailed { rollback, .. } => {
+                map.serialize_entry("rollback", &rollback.to_string())?;
+            }
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        if let rusqlite::Error::SqliteFailure(failure, Some(message)) = &err {
+            if failure.code == rusqlite::ErrorCode::ConstraintViolation {
+                let entity = message
+                    .strip_prefix("UNIQUE constraint failed: ")
+                    .and_then(|column| column.strip_suffix(".name"));
+
+                return match entity {
+                    Some(entity) => Error::UniqueNameConflict {
+                        entity: entity.to_string(),
+                        name: None,
+                    },
+                    None => Error::ConstraintViolation {
+                        entity: None,
+                        id: None,
+                        message: message.clone(),
+                    },
+                };
+            }
+        }
+
+        Error::Database(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<tauri::Error> for Error {
+    fn from(err: tauri::Error) -> Self {
+        Error::Tauri(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_context() -> Result<(), Box<dyn std::error::Error>> {
+        let err = Error::ConstraintViolation {
+            entity: Some("statuses".to_string()),
+            id: Some(4),
+            message: "FOREIGN KEY constraint failed".to_string(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(err)?,
+            serde_json::json!({
+                "code": "constraintViolation",
+                "message": "FOREIGN KEY constraint failed",
+                "entity": "statuses",
+                "id": 4
+            })
+        );
+
+        assert_eq!(
+            serde_json::to_value(Error::NoDatabaseOpen)?,
+            serde_json::json!({
+                "code": "noDatabaseOpen",
+                "message": "No connection to database"
+            })
+        );
+
+        let err = Error::RollbackFailed {
+            error: Box::new(Error::WrongKey),
+            rollback: Box::new(Error::Invalid("Disk full".to_string())),
+        };
+        assert_eq!(
+            serde_json::to_value(err)?,
+            serde_json::json!({
+                "code": "wrongKey",
+                "message": "Wrong or missing password for encrypted database. \
+                    Putting the database back failed too: Disk full",
+                "rollback": "Disk full"
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -1,2 +1,211 @@
+use crate::csv::CsvImportReport;
+use crate::database::BulkEdit;
+use crate::entity::{Column, Table};
+use crate::library::ImportReport;
+use tauri::Emitter;
+
 pub const OPENED_DATABASE_EVENT: &str = "opened-database";
 pub const CLOSED_DATABASE_EVENT: &str = "closed-database";
+pub const CHANGED_EVENT: &str = "changed";
+/// Emitted with the path of a database that can't be opened without a password.
+pub const PASSWORD_REQUIRED_EVENT: &str = "password-required";
+/// Emitted to the main window to show the backups that can be restored.
+pub const SHOW_BACKUPS_EVENT: &str = "show-backups";
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Entity {
+    Work,
+    Creator,
+    Status,
+    Type,
+    Format,
+    Tag,
+    ReleaseSchedule,
+}
+
+impl From<Table> for Entity {
+    fn from(table: Table) -> Self {
+        match table {
+            Table::Works => Entity::Work,
+            Table::Creators => Entity::Creator,
+            Table::Statuses => Entity::Status,
+            Table::Types => Entity::Type,
+            Table::Formats => Entity::Format,
+            Table::Tags => Entity::Tag,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    Add,
+    Update,
+    Remove,
+    Reorder,
+    Attach,
+    Detach,
+}
+
+/// Payload of [`CHANGED_EVENT`], emitted after every successful edit so that listeners can
+/// patch their state instead of reloading it. `fields` lists the columns or links that changed,
+/// it is empty when whole rows were added or removed. `related` holds the changes one edit made
+/// to other entities, so that it is still a single notification.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub entity: Entity,
+    pub operation: Operation,
+    pub ids: Vec<i64>,
+    pub fields: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<Change>,
+}
+
+impl Change {
+    pub fn new(entity: impl Into<Entity>, operation: Operation, ids: Vec<i64>) -> Self {
+        Change {
+            entity: entity.into(),
+            operation,
+            ids,
+            fields: vec![],
+            related: vec![],
+        }
+    }
+
+    pub fn updated<C: Column>(id: i64, columns: &[C]) -> Self {
+        Change::new(C::TABLE, Operation::Update, vec![id])
+            .with_fields(columns.iter().map(Column::name))
+    }
+
+    pub fn with_fields(mut self, fields: impl IntoIterator<Item = &'static str>) -> Self {
+        self.fields = fields.into_iter().collect();
+        self
+    }
+
+    pub fn with_related(mut self, change: Change) -> Self {
+        self.related.push(change);
+        self
+    }
+
+    /// The change a bulk edit of `ids` made to the works, with the creator they were attached
+    /// to or detached from as related.
+    pub fn bulk(edit: &BulkEdit, ids: &[i64]) -> Self {
+        let works = |operation| Change::new(Entity::Work, operation, ids.to_vec());
+        let creator = |operation, id| Change::new(Entity::Creator, operation, vec![id]);
+
+        match edit {
+            BulkEdit::SetStatus { .. } => {
+                works(Operation::Update).with_fields(["status", "updated"])
+            }
+            BulkEdit::SetType { .. } => works(Operation::Update).with_fields(["type"]),
+            BulkEdit::SetFormat { .. } => works(Operation::Update).with_fields(["format"]),
+            BulkEdit::Attach { creator_id } => works(Operation::Attach)
+                .with_fields(["creators"])
+                .with_related(creator(Operation::Attach, *creator_id).with_fields(["works"])),
+            BulkEdit::Detach { creator_id } => works(Operation::Detach)
+                .with_fields(["creators"])
+                .with_related(creator(Operation::Detach, *creator_id).with_fields(["works"])),
+            BulkEdit::AddTag { .. } => works(Operation::Attach).with_fields(["tags"]),
+            BulkEdit::RemoveTag { .. } => works(Operation::Detach).with_fields(["tags"]),
+            BulkEdit::Remove => works(Operation::Remove),
+        }
+    }
+
+    /// The rows an import added and the existing creators that may have been attached to them,
+    /// the other rows it matched are left as they were.
+    pub fn imported(report: &ImportReport) -> Vec<Self> {
+        let mut changes = [
+            (Entity::Status, &report.statuses),
+            (Entity::Type, &report.types),
+            (Entity::Format, &report.formats),
+            (Entity::Tag, &report.tags),
+            (Entity::Creator, &report.creators),
+            (Entity::Work, &report.works),
+        ]
+        .into_iter()
+        .filter(|(_, count)| !count.added.is_empty())
+        .map(|(entity, count)| Change::new(entity, Operation::Add, count.added.clone()))
+        .collect::<Vec<_>>();
+
+        if !report.creators.matched.is_empty() {
+            let creators = report.creators.matched.clone();
+            changes.push(
+                Change::new(Entity::Creator, Operation::Attach, creators).with_fields(["works"]),
+            );
+        }
+        if !report.updated.is_empty() {
+            changes.push(
+                Change::new(Entity::Work, Operation::Update, report.updated.clone())
+                    .with_fields(["progress", "status", "updated"]),
+            );
+        }
+        changes
+    }
+
+    /// Same as [`Change::imported`] plus the creators and tags of the works a CSV import updated.
+    pub fn imported_csv(report: &CsvImportReport) -> Vec<Self> {
+        let mut changes = Change::imported(&report.import);
+        if !report.import.updated.is_empty() {
+            let works = Change::new(
+                Entity::Work,
+                Operation::Attach,
+                report.import.updated.clone(),
+            );
+            changes.push(works.with_fields(["creators", "tags"]));
+        }
+        changes
+    }
+
+    pub fn emit(self, app: &tauri::AppHandle) -> tauri::Result<()> {
+        log::debug!("Emitting change: {self:?}.");
+        app.emit(CHANGED_EVENT, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::WorkColumn;
+
+    #[test]
+    fn serializes_changed_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let columns = [WorkColumn::Progress("5".into()), WorkColumn::Updated(44)];
+        let change = Change::updated(4, &columns);
+
+        assert_eq!(
+            serde_json::to_value(change)?,
+            serde_json::json!({
+                "entity": "work",
+                "operation": "update",
+                "ids": [4],
+                "fields": ["progress", "updated"]
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bulk_attach_changes_works_and_creator() -> Result<(), Box<dyn std::error::Error>> {
+        let change = Change::bulk(&BulkEdit::Attach { creator_id: 7 }, &[1, 2]);
+
+        assert_eq!(
+            change,
+            Change::new(Entity::Work, Operation::Attach, vec![1, 2])
+                .with_fields(["creators"])
+                .with_related(
+                    Change::new(Entity::Creator, Operation::Attach, vec![7]).with_fields(["works"])
+                )
+        );
+        assert_eq!(
+            serde_json::to_value(change)?["related"],
+            serde_json::json!([
+                {"entity": "creator", "operation": "attach", "ids": [7], "fields": ["works"]}
+            ])
+        );
+
+        Ok(())
+    }
+}
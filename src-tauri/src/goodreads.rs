@@ -0,0 +1,327 @@
+use crate::csv::{parse_timestamp, Sheet};
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{creator_key, Builder, Library};
+use crate::service::{self, ExportReport, Service, Values};
+use crate::store::LibraryStore;
+use chrono::{Local, TimeZone};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// External ids are Goodreads book ids.
+pub const SOURCE: &str = "goodreads";
+const TYPE: &str = "Book";
+/// What Goodreads itself calls books without a binding.
+const UNKNOWN_BINDING: &str = "Unknown Binding";
+/// The columns of a Goodreads export that [`export`] fills, which its import reads.
+const EXPORT_HEADERS: [&str; 12] = [
+    "Book Id",
+    "Title",
+    "Author",
+    "Additional Authors",
+    "ISBN",
+    "My Rating",
+    "Binding",
+    "Number of Pages",
+    "Date Read",
+    "Date Added",
+    "Bookshelves",
+    "Exclusive Shelf",
+];
+/// The built-in exclusive shelves, others keep their own name.
+const SHELVES: [(&str, &str, bool); 3] = [
+    ("read", "Read", false),
+    ("currently-reading", "Reading", true),
+    ("to-read", "To Read", false),
+];
+
+/// Reads a Goodreads library export as a library. Each book becomes a work of the `Book` type
+/// in the status of its exclusive shelf, with its binding as format, its authors as creators and
+/// its other shelves as tags. Read books have their page count as progress. The date added
+/// becomes the time it was added and the date read, if any, the time it was last updated.
+/// Ratings have nowhere to go and are left out. Books are linked to their Goodreads id so they
+/// can be exported back.
+pub fn parse(text: &str, now: i64) -> DatabaseResult<Library> {
+    let sheet = Sheet::parse(text)?;
+    let column = |name: &str| {
+        sheet
+            .column(&[name])
+            .ok_or_else(|| Error::Invalid(format!("Not a Goodreads export: no '{name}' column")))
+    };
+    let title = column("Title")?;
+    let author = column("Author")?;
+    let shelf = column("Exclusive Shelf")?;
+    let additional = sheet.column(&["Additional Authors"]);
+    let binding = sheet.column(&["Binding"]);
+    let pages = sheet.column(&["Number of Pages"]);
+    let shelves = sheet.column(&["Bookshelves"]);
+    let date_read = sheet.column(&["Date Read"]);
+    let date_added = sheet.column(&["Date Added"]);
+    let book_id = sheet.column(&["Book Id"]);
+
+    let mut builder = Builder::new(now);
+    let mut authors = HashMap::new();
+    let r#type = builder.r#type(TYPE);
+    for row in &sheet.rows {
+        let cell = |column: Option<usize>| column.map_or("", |column| Sheet::cell(row, column));
+        let name = Sheet::cell(row, title);
+        if name.is_empty() {
+            continue;
+        }
+
+        let shelf = match Sheet::cell(row, shelf) {
+            "" => "to-read",
+            shelf => shelf,
+        };
+        let status = match SHELVES.iter().find(|(id, ..)| *id == shelf) {
+            Some(&(_, name, is_update)) => builder.status(name, is_update),
+            None => builder.status(shelf, false),
+        };
+        let format = match cell(binding) {
+            "" => builder.format(UNKNOWN_BINDING),
+            binding => builder.format(binding),
+        };
+
+        let names = std::iter::once(Sheet::cell(row, author)).chain(cell(additional).split(','));
+        let mut creators = vec![];
+        for name in names.map(str::trim).filter(|name| !name.is_empty()) {
+            let name = authors
+                .entry(creator_key(name))
+                .or_insert_with(|| name.to_string());
+            let creator = builder.creator(name);
+            if !creators.contains(&creator) {
+                creators.push(creator);
+            }
+        }
+        let tags = cell(shelves)
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != shelf)
+            .map(|name| builder.tag(name))
+            .collect();
+
+        let progress = match (shelf, cell(pages)) {
+            ("read", pages) if !pages.is_empty() => pages,
+            _ => "0",
+        };
+        let added = parse_timestamp(cell(date_added)).unwrap_or(now);
+        let work_id = builder.work(Work {
+            id: 0,
+            name: name.to_string(),
+            progress: progress.to_string(),
+            status,
+            r#type,
+            format,
+            updated: parse_timestamp(cell(date_read)).unwrap_or(added),
+            added,
+            creators,
+            tags,
+        });
+        if !cell(book_id).is_empty() {
+            builder.link(work_id, SOURCE, cell(book_id).to_string());
+        }
+    }
+    Ok(builder.build())
+}
+
+pub fn read(path: &Path, now: i64) -> DatabaseResult<Library> {
+    parse(&std::fs::read_to_string(path)?, now)
+}
+
+fn format_date(timestamp: i64) -> String {
+    let datetime = Local.timestamp_millis_opt(timestamp).single();
+    datetime.map_or_else(String::new, |datetime| {
+        datetime.format("%Y/%m/%d").to_string()
+    })
+}
+
+/// Every work with a Goodreads shelf and type in the mapping stored for it, as a sheet in the
+/// columns of a Goodreads export, which Goodreads can import. Books it linked are written with
+/// their Goodreads id, the others are left for Goodreads to find by title and author. Works
+/// that have no shelf or type there are reported as skipped.
+pub fn export(store: &impl LibraryStore) -> DatabaseResult<(Sheet, ExportReport)> {
+    store.transaction(|store| {
+        let values = Values::new(service::mapping(store, Service::Goodreads)?);
+        let statuses: HashMap<_, _> = store
+            .get_statuses()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let types: HashMap<_, _> = store
+            .get_types()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let formats: HashMap<_, _> = store
+            .get_formats()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let creators: HashMap<_, _> = store
+            .get_creators()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let tags: HashMap<_, _> = store
+            .get_tags()?
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect();
+        let ids = store
+            .get_external_ids()?
+            .into_iter()
+            .filter(|row| row.source == SOURCE)
+            .map(|row| (row.work_id, row.external_id))
+            .collect::<HashMap<_, _>>();
+        let name = |names: &HashMap<i64, String>, id| names.get(&id).cloned().unwrap_or_default();
+
+        let mut report = ExportReport::default();
+        let mut rows = vec![];
+        for work in store.get_works()? {
+            if values.r#type(work.r#type).is_none() {
+                let reason = format!("Type '{}' has no Goodreads type", name(&types, work.r#type));
+                report.skip(&work, reason);
+                continue;
+            }
+            let Some(shelf) = values.status(work.status) else {
+                let status = name(&statuses, work.status);
+                report.skip(&work, format!("Status '{status}' has no Goodreads shelf"));
+                continue;
+            };
+
+            let read = shelf == "read";
+            let mut authors = work.creators.iter().map(|id| name(&creators, *id));
+            let pages = match work.progress.trim().parse::<u32>() {
+                Ok(pages) if read && pages > 0 => pages.to_string(),
+                _ => String::new(),
+            };
+            let shelves = work
+                .tags
+                .iter()
+                .map(|id| name(&tags, *id))
+                .collect::<Vec<_>>();
+            rows.push(vec![
+                ids.get(&work.id).cloned().unwrap_or_default(),
+                work.name.clone(),
+                authors.next().unwrap_or_default(),
+                authors.collect::<Vec<_>>().join(", "),
+                String::new(),
+                "0".to_string(),
+                name(&formats, work.format),
+                pages,
+                if read {
+                    format_date(work.updated)
+                } else {
+                    String::new()
+                },
+                format_date(work.added),
+                shelves.join(", "),
+                shelf.to_string(),
+            ]);
+            report.exported += 1;
+        }
+
+        let headers = EXPORT_HEADERS.map(str::to_string).to_vec();
+        Ok((Sheet { headers, rows }, report))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = "\
+Book Id,Title,Author,Author l-f,Additional Authors,ISBN,My Rating,Binding,Number of Pages,\
+Date Read,Date Added,Bookshelves,Exclusive Shelf
+1,Dune,Frank Herbert,\"Herbert, Frank\",,\"=\"\"0441013597\"\"\",5,Paperback,658,\
+2023/05/01,2023/01/02,\"favorites, read\",read
+2,Dune Messiah,Frank  Herbert,\"Herbert, Frank\",\"Brian Herbert, frank herbert\",,0,,,\
+,2023/06/07,,currently-reading
+3,The Hobbit,J.R.R. Tolkien,,J. R. R. Tolkien,,0,Hardcover,310,,,wishlist,wishlist
+";
+
+    #[test]
+    fn reads_library_export() -> Result<(), Box<dyn std::error::Error>> {
+        let library = parse(EXPORT, 7)?;
+
+        let creators = vec!["Frank Herbert", "Brian Herbert", "J.R.R. Tolkien"];
+        assert_eq!(Library::names(&library.creators), creators);
+        assert_eq!(
+            Library::names(&library.formats),
+            vec!["Paperback", UNKNOWN_BINDING, "Hardcover"]
+        );
+        assert_eq!(Library::names(&library.tags), vec!["favorites"]);
+        assert_eq!(library.status_names(), vec!["Read", "Reading", "wishlist"]);
+
+        let [dune, messiah, hobbit] = library.expect_works();
+        assert_eq!(dune.progress, "658");
+        assert_eq!(dune.added, parse_timestamp("2023/01/02").unwrap());
+        assert_eq!(dune.updated, parse_timestamp("2023/05/01").unwrap());
+        assert_eq!(dune.tags.len(), 1);
+        assert_eq!(messiah.creators.len(), 2);
+        assert_eq!(messiah.creators[0], dune.creators[0]);
+        assert_eq!(messiah.progress, "0");
+        assert_eq!(messiah.updated, messiah.added);
+        assert_eq!(hobbit.creators.len(), 1);
+        assert_eq!((hobbit.added, hobbit.updated), (7, 7));
+
+        assert!(matches!(parse("Name,Status\n", 0), Err(Error::Invalid(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn matches_existing_creators_by_key() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::entity::NewCreator;
+        use crate::library::{import, ImportMode};
+
+        let mut store = crate::database::Database::default();
+        store.open_in_memory()?;
+        let tolkien = store.add(&NewCreator {
+            name: "J. R. R. Tolkien".into(),
+        })?;
+        let herbert = store.add(&NewCreator {
+            name: "FRANK HERBERT".into(),
+        })?;
+
+        let report = import(&store, &parse(EXPORT, 7)?, ImportMode::Add, false)?;
+
+        assert_eq!(report.creators.matched, vec![herbert, tolkien]);
+        assert_eq!(report.creators.added.len(), 1);
+        assert_eq!(store.get_creators()?.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn exports_mapped_shelves() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::library::{import, ImportMode};
+
+        let mut store = crate::database::Database::default();
+        store.open_in_memory()?;
+        import(&store, &parse(EXPORT, 7)?, ImportMode::Add, false)?;
+
+        let (sheet, report) = export(&store)?;
+
+        assert_eq!(report.exported, 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].name, "The Hobbit");
+        let [dune, messiah] = &sheet.rows[..] else {
+            panic!("Expected 2 rows, got {}.", sheet.rows.len());
+        };
+        assert_eq!(dune[..3], ["1", "Dune", "Frank Herbert"]);
+        assert_eq!(
+            dune[7..],
+            ["658", "2023/05/01", "2023/01/02", "favorites", "read"]
+        );
+        assert_eq!(messiah[3], "Brian Herbert");
+        assert_eq!(messiah[11], "currently-reading");
+
+        let library = parse(&sheet.to_string(), 7)?;
+        assert_eq!(library.works.len(), 2);
+        assert_eq!(library.works[0].progress, "658");
+        assert_eq!(library.external_ids[1].external_id, "2");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::database::DatabaseResult;
+use crate::entity::Table;
+
+const SORTED_TABLES: [Table; 3] = [Table::Statuses, Table::Types, Table::Formats];
+
+/// A row whose foreign key points at a row that no longer exists.
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub table: String,
+    pub rowid: i64,
+    pub parent: String,
+}
+
+#[derive(serde::Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    /// What `PRAGMA integrity_check` found wrong with the file itself, only a backup fixes it.
+    pub corruption: Vec<String>,
+    /// Works pointing at a missing status, type or format. Which one they should point at
+    /// instead is up to the user.
+    pub broken_works: Vec<Violation>,
+    /// Links and schedules left behind by a removed row, repaired by removing them.
+    pub orphaned: Vec<Violation>,
+    /// Sorted tables whose `sort` isn't 0 to n - 1, repaired by renumbering in the same order.
+    pub unsorted: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.corruption.is_empty()
+            && self.broken_works.is_empty()
+            && self.orphaned.is_empty()
+            && self.unsorted.is_empty()
+    }
+
+    /// Whether there is something to repair. Nothing is on a corrupt file, its rows can't be
+    /// trusted and only a backup fixes it.
+    pub fn is_repairable(&self) -> bool {
+        self.corruption.is_empty() && (!self.orphaned.is_empty() || !self.unsorted.is_empty())
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_ok() {
+            return "No problems found.".to_string();
+        }
+
+        let mut lines = self.corruption.clone();
+        if !self.broken_works.is_empty() {
+            let ids = self.broken_works.iter().map(|work| work.rowid.to_string());
+            lines.push(format!(
+                "Works pointing at a missing status, type or format: {}.",
+                ids.collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if !self.orphaned.is_empty() {
+            let count = self.orphaned.len();
+            lines.push(format!(
+                "{count} rows left behind by removed works or creators."
+            ));
+        }
+        if !self.unsorted.is_empty() {
+            lines.push(format!("Out of order: {}.", self.unsorted.join(", ")));
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn check(conn: &rusqlite::Connection) -> DatabaseResult<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    for row in rows {
+        let message = row?;
+        if message != "ok" {
+            report.corruption.push(message);
+        }
+    }
+
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Violation {
+            table: row.get(0)?,
+            rowid: row.get(1)?,
+            parent: row.get(2)?,
+        })
+    })?;
+    for row in rows {
+        let violation = row?;
+        match violation.table.as_str() {
+            "works" => report.broken_works.push(violation),
+            _ => report.orphaned.push(violation),
+        }
+    }
+
+    for table in SORTED_TABLES {
+        let sorts = sorts(conn, table)?;
+        if !sorts
+            .iter()
+            .map(|(_, sort)| *sort)
+            .eq(0..sorts.len() as i64)
+        {
+            report.unsorted.push(table.name().to_string());
+        }
+    }
+
+    Ok(report)
+}
+
+/// Ids and `sort` of every row of `table`, in the order the user sees them.
+fn sorts(conn: &rusqlite::Connection, table: Table) -> DatabaseResult<Vec<(i64, i64)>> {
+    let query = format!("SELECT id, sort FROM {} ORDER BY sort, id", table.name());
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.map(|row| Ok(row?)).collect()
+}
+
+/// Applies the repairs `report` allows and returns the rows of each sorted table that were
+/// renumbered. Should run inside a transaction.
+pub fn repair(
+    conn: &rusqlite::Connection,
+    report: &IntegrityReport,
+) -> DatabaseResult<Vec<(Table, Vec<i64>)>> {
+    for violation in &report.orphaned {
+        log::warn!(
+            "Removing row {} of '{}' that points at a missing '{}'.",
+            violation.rowid,
+            violation.table,
+            violation.parent
+        );
+        conn.execute(
+            &format!(
+                "DELETE FROM \"{}\" WHERE rowid = ?",
+                violation.table.replace('"', "\"\"")
+            ),
+            [violation.rowid],
+        )?;
+    }
+
+    let mut renumbered = vec![];
+    for table in SORTED_TABLES {
+        if !report.unsorted.iter().any(|name| name == table.name()) {
+            continue;
+        }
+
+        log::warn!("Renumbering '{}'.", table.name());
+        let query = format!("UPDATE {} SET sort = ? WHERE id = ?", table.name());
+        let mut stmt = conn.prepare(&query)?;
+        let ids = sorts(conn, table)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        for (sort, id) in ids.iter().enumerate() {
+            stmt.execute([sort as i64, *id])?;
+        }
+        renumbered.push((table, ids));
+    }
+
+    Ok(renumbered)
+}
@@ -0,0 +1,220 @@
+use crate::csv::{parse_timestamp, Sheet};
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{earliest, Builder, Library};
+use crate::zip::files;
+use std::collections::HashMap;
+use std::path::Path;
+
+const TYPE: &str = "Film";
+const FORMAT: &str = "Film";
+const WATCHED: &str = "Completed";
+const PLANNED: &str = "Plan to Watch";
+
+#[derive(Default)]
+struct Film {
+    watched: bool,
+    /// Diary entries, each one a watch or a rewatch.
+    watches: usize,
+    added: Option<i64>,
+    last_watched: Option<i64>,
+    tags: Vec<String>,
+}
+
+impl Film {
+    fn saw(&mut self, date: Option<i64>) {
+        self.watched = true;
+        self.last_watched = self.last_watched.max(date);
+    }
+
+    fn logged(&mut self, date: Option<i64>) {
+        self.added = earliest(self.added, date);
+    }
+}
+
+/// The films of an export in the order they first come up, by name and year.
+#[derive(Default)]
+struct Films {
+    films: Vec<(String, Film)>,
+    index: HashMap<(String, String), usize>,
+}
+
+impl Films {
+    /// Reads `text` as one of the export's CSV files and calls `f` with every film in it, the
+    /// row it's in and the column of each header.
+    fn read(
+        &mut self,
+        text: &str,
+        mut f: impl FnMut(&mut Film, &[String], &dyn Fn(&str) -> Option<usize>),
+    ) -> DatabaseResult<()> {
+        let sheet = Sheet::parse(text)?;
+        let column = |name: &str| sheet.column(&[name]);
+        let (Some(name), year) = (column("Name"), column("Year")) else {
+            return Err(Error::Invalid(
+                "Not a Letterboxd export: no 'Name' column".to_string(),
+            ));
+        };
+
+        for row in &sheet.rows {
+            let name = Sheet::cell(row, name).to_string();
+            let year = year.map_or("", |year| Sheet::cell(row, year)).to_string();
+            if name.is_empty() {
+                continue;
+            }
+
+            let index = *self
+                .index
+                .entry((name.clone(), year.clone()))
+                .or_insert_with(|| {
+                    let name = if year.is_empty() {
+                        name
+                    } else {
+                        format!("{name} ({year})")
+                    };
+                    self.films.push((name, Film::default()));
+                    self.films.len() - 1
+                });
+            f(&mut self.films[index].1, row, &column);
+        }
+        Ok(())
+    }
+}
+
+/// Reads a Letterboxd export, `file` giving the contents of each of its files by name, as a
+/// library. Every film in `watched.csv`, `diary.csv` or `watchlist.csv` becomes one work of the
+/// `Film` type and format named with its year, `Completed` once watched and `Plan to Watch`
+/// while only on the watchlist. Progress counts its diary entries, so rewatches show, and its
+/// diary tags become tags. It was added when first logged anywhere and updated when last
+/// watched.
+pub fn parse(
+    file: impl Fn(&str) -> DatabaseResult<Option<String>>,
+    now: i64,
+) -> DatabaseResult<Library> {
+    let mut films = Films::default();
+    let date = |row: &[String], column: Option<usize>| {
+        column.and_then(|column| parse_timestamp(Sheet::cell(row, column)))
+    };
+
+    let watchlist = file("watchlist.csv")?;
+    let watched = file("watched.csv")?;
+    let diary = file("diary.csv")?;
+    if watchlist.is_none() && watched.is_none() && diary.is_none() {
+        return Err(Error::Invalid(
+            "Not a Letterboxd export: no films".to_string(),
+        ));
+    }
+
+    if let Some(text) = watchlist {
+        films.read(&text, |film, row, column| {
+            film.logged(date(row, column("Date")))
+        })?;
+    }
+    if let Some(text) = watched {
+        films.read(&text, |film, row, column| {
+            let watched = date(row, column("Date"));
+            film.logged(watched);
+            film.saw(watched);
+        })?;
+    }
+    if let Some(text) = diary {
+        films.read(&text, |film, row, column| {
+            let watched = date(row, column("Watched Date")).or(date(row, column("Date")));
+            film.logged(watched);
+            film.saw(watched);
+            film.watches += 1;
+
+            let tags = column("Tags").map_or("", |tags| Sheet::cell(row, tags));
+            for tag in tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+                if !film.tags.iter().any(|existing| existing == tag) {
+                    film.tags.push(tag.to_string());
+                }
+            }
+        })?;
+    }
+
+    let mut builder = Builder::new(now);
+    let r#type = builder.r#type(TYPE);
+    let format = builder.format(FORMAT);
+    for (name, film) in films.films {
+        let status = builder.status(if film.watched { WATCHED } else { PLANNED }, false);
+        let progress = match (film.watched, film.watches) {
+            (false, _) => 0,
+            (true, watches) => watches.max(1),
+        };
+        let added = film.added.unwrap_or(now);
+        let tags = film.tags.iter().map(|tag| builder.tag(tag)).collect();
+
+        builder.work(Work {
+            id: 0,
+            name,
+            progress: progress.to_string(),
+            status,
+            r#type,
+            format,
+            updated: film.last_watched.unwrap_or(added),
+            added,
+            creators: vec![],
+            tags,
+        });
+    }
+    Ok(builder.build())
+}
+
+/// Reads the export zip at `path`, or the folder it was extracted to.
+pub fn read(path: &Path, now: i64) -> DatabaseResult<Library> {
+    parse(files(path, false)?, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATCHLIST_CSV: &str = "Date,Name,Year,Letterboxd URI
+2020-01-01,Heat,1995,https://boxd.it/1
+2020-01-02,Ran,1985,https://boxd.it/2
+";
+    const WATCHED_CSV: &str = "Date,Name,Year,Letterboxd URI
+2021-03-04,Heat,1995,https://boxd.it/1
+2021-05-06,Heat,2020,https://boxd.it/3
+";
+    const DIARY_CSV: &str = "Date,Name,Year,Letterboxd URI,Rating,Rewatch,Tags,Watched Date
+2021-03-05,Heat,1995,https://boxd.it/a,5,,\"crime, la\",2021-03-04
+2022-07-08,Heat,1995,https://boxd.it/b,5,Yes,crime,2022-07-07
+";
+
+    #[test]
+    fn reads_export_archive() -> Result<(), Box<dyn std::error::Error>> {
+        let temp = crate::database::TempFolder::new();
+        let path = temp.join("export.zip");
+        let archive = crate::zip::write(&[
+            ("watchlist.csv", WATCHLIST_CSV.as_bytes()),
+            ("watched.csv", WATCHED_CSV.as_bytes()),
+            ("diary.csv", DIARY_CSV.as_bytes()),
+            ("deleted/diary.csv", DIARY_CSV.as_bytes()),
+        ]);
+        std::fs::write(&path, archive)?;
+        let library = read(&path, 9)?;
+
+        let names = library
+            .works
+            .iter()
+            .map(|work| work.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Heat (1995)", "Ran (1985)", "Heat (2020)"]);
+        assert_eq!(library.status_names(), vec![WATCHED, PLANNED]);
+
+        let [heat, ran, remake] = library.expect_works();
+        assert_eq!(heat.progress, "2");
+        assert_eq!(heat.added, parse_timestamp("2020-01-01").unwrap());
+        assert_eq!(heat.updated, parse_timestamp("2022-07-07").unwrap());
+        assert_eq!(heat.tags.len(), 2);
+        assert_eq!(ran.progress, "0");
+        assert_eq!(ran.updated, ran.added);
+        assert_eq!(remake.progress, "1");
+        assert_eq!(remake.added, parse_timestamp("2021-05-06").unwrap());
+
+        assert!(matches!(parse(|_| Ok(None), 0), Err(Error::Invalid(_))));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,939 @@
+use crate::database::{DatabaseResult, ExternalId, Work};
+use crate::entity::{NewCreator, NewFormat, NewStatus, NewTag, NewType, WorkColumn};
+use crate::error::Error;
+use crate::schedule::ReleaseSchedule;
+use crate::store::LibraryStore;
+use crate::tachiyomi::{self, Categories};
+use crate::{anilist, calibre, goodreads, letterboxd, mal, trakt};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::Path;
+
+pub const FORMAT: &str = "zero-library";
+pub const VERSION: u32 = 1;
+
+/// A whole library in a portable form, written by [`export`] and read by [`import`].
+///
+/// Version 1 has `statuses`, `types` and `formats` in the order the user sorted them, then
+/// `tags`, `creators`, `works`, the release `schedules` of works and the `external_ids` linking
+/// works to other apps. Ids only link rows within the document, works refer to their status,
+/// type, format, creators and tags by them and schedules and external ids to their work. Imports
+/// get new ids.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Library {
+    pub format: String,
+    pub version: u32,
+    /// Milliseconds since the epoch.
+    #[serde(default)]
+    pub exported: i64,
+    #[serde(default)]
+    pub statuses: Vec<LibraryStatus>,
+    #[serde(default)]
+    pub types: Vec<Named>,
+    #[serde(default)]
+    pub formats: Vec<Named>,
+    #[serde(default)]
+    pub tags: Vec<Named>,
+    #[serde(default)]
+    pub creators: Vec<Named>,
+    #[serde(default)]
+    pub works: Vec<Work>,
+    #[serde(default)]
+    pub schedules: Vec<ReleaseSchedule>,
+    #[serde(default)]
+    pub external_ids: Vec<ExternalId>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStatus {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub is_update: bool,
+    #[serde(default)]
+    pub stale_after: Option<i64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Named {
+    pub id: i64,
+    pub name: String,
+}
+
+/// Ids in the store of the rows an import added and of the existing rows it used instead.
+#[derive(serde::Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ImportCount {
+    pub added: Vec<i64>,
+    pub matched: Vec<i64>,
+}
+
+/// What an import did, or would do for a dry run. The ids of added rows are not kept by a dry
+/// run, they may be given to other rows later.
+#[derive(serde::Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub statuses: ImportCount,
+    pub types: ImportCount,
+    pub formats: ImportCount,
+    pub tags: ImportCount,
+    pub creators: ImportCount,
+    pub works: ImportCount,
+    /// Existing works that got a new progress or status.
+    pub updated: Vec<i64>,
+    pub schedules: usize,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        let line = |name: &str, count: &ImportCount| {
+            format!(
+                "{name}: {} new, {} existing",
+                count.added.len(),
+                count.matched.len()
+            )
+        };
+        [
+            line("Works", &self.works),
+            format!("Updated works: {}", self.updated.len()),
+            line("Creators", &self.creators),
+            line("Statuses", &self.statuses),
+            line("Types", &self.types),
+            line("Formats", &self.formats),
+            line("Tags", &self.tags),
+            format!("Release schedules: {}", self.schedules),
+        ]
+        .join("\n")
+    }
+}
+
+impl Library {
+    pub fn new(exported: i64) -> Self {
+        Library {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported,
+            statuses: vec![],
+            types: vec![],
+            formats: vec![],
+            tags: vec![],
+            creators: vec![],
+            works: vec![],
+            schedules: vec![],
+            external_ids: vec![],
+        }
+    }
+
+    pub fn read(path: &Path) -> DatabaseResult<Self> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let library: Library = serde_json::from_reader(file)
+            .map_err(|err| Error::Invalid(format!("Invalid library export: {err}")))?;
+
+        if library.format != FORMAT {
+            return Err(Error::Invalid(format!(
+                "Not a library export: '{}'",
+                library.format
+            )));
+        }
+        if library.version > VERSION {
+            return Err(Error::Invalid(format!(
+                "Library export version {} is newer than the supported {VERSION}",
+                library.version
+            )));
+        }
+        Ok(library)
+    }
+
+    pub fn write(&self, path: &Path) -> DatabaseResult<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self).map_err(|err| Error::Io(err.into()))
+    }
+}
+
+#[cfg(test)]
+impl Library {
+    pub fn names(rows: &[Named]) -> Vec<&str> {
+        rows.iter().map(|row| row.name.as_str()).collect()
+    }
+
+    pub fn status_names(&self) -> Vec<&str> {
+        self.statuses
+            .iter()
+            .map(|status| status.name.as_str())
+            .collect()
+    }
+
+    /// The works read, failing the test unless there are exactly `N` of them.
+    pub fn expect_works<const N: usize>(&self) -> &[Work; N] {
+        self.works
+            .as_slice()
+            .try_into()
+            .unwrap_or_else(|_| panic!("Expected {N} works, got {}.", self.works.len()))
+    }
+}
+
+/// What an import does with the works the store already has.
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// Adds the new works and leaves existing ones as they are.
+    #[default]
+    Add,
+    /// Adds the new works and brings the progress and status of existing ones up to date.
+    Sync,
+    /// Only brings the progress of works an earlier import linked to their external id up to
+    /// date, nothing is added.
+    Progress,
+}
+
+/// Files of other apps that can be read as a [`Library`].
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+    /// A file written by [`Library::write`].
+    Library,
+    /// A MyAnimeList anime or manga list export.
+    MyAnimeList,
+    /// A Goodreads library export.
+    Goodreads,
+    /// A Letterboxd export zip or the folder it was extracted to.
+    Letterboxd,
+    /// A Tachiyomi or Mihon backup.
+    Tachiyomi { categories: Categories },
+    /// An AniList data export or list query response.
+    AniList,
+    /// A Trakt export zip or the folder it was extracted to.
+    Trakt,
+    /// A Calibre library folder, read statuses coming from the custom column with the label.
+    Calibre {
+        #[serde(rename = "readColumn")]
+        read_column: Option<String>,
+    },
+}
+
+impl Source {
+    /// Reads the file at `path`, `now` standing in for the time of anything it leaves out.
+    pub fn read(self, path: &Path, now: i64) -> DatabaseResult<Library> {
+        match self {
+            Source::Library => Library::read(path),
+            Source::MyAnimeList => mal::read(path, now),
+            Source::Goodreads => goodreads::read(path, now),
+            Source::Letterboxd => letterboxd::read(path, now),
+            Source::AniList => anilist::read(path, now),
+            Source::Trakt => trakt::read(path, now),
+            Source::Tachiyomi { categories } => tachiyomi::read(path, categories, now),
+            Source::Calibre { read_column } => calibre::read(path, read_column.as_deref(), now),
+        }
+    }
+}
+
+/// Builds a [`Library`] out of works that name their status, type, format, creators and tags,
+/// giving each name an id the first time it comes up.
+pub struct Builder {
+    library: Library,
+    ids: HashMap<(&'static str, String), i64>,
+}
+
+impl Builder {
+    pub fn new(exported: i64) -> Self {
+        Builder {
+            library: Library::new(exported),
+            ids: HashMap::new(),
+        }
+    }
+
+    fn id(&mut self, entity: &'static str, name: &str, add: impl FnOnce(&mut Library, i64)) -> i64 {
+        let next = self.ids.len() as i64 + 1;
+        *self
+            .ids
+            .entry((entity, name.to_string()))
+            .or_insert_with(|| {
+                add(&mut self.library, next);
+                next
+            })
+    }
+
+    pub fn status(&mut self, name: &str, is_update: bool) -> i64 {
+        self.id("status", name, |library, id| {
+            let name = name.to_string();
+            library.statuses.push(LibraryStatus {
+                id,
+                name,
+                is_update,
+                stale_after: None,
+            });
+        })
+    }
+
+    pub fn r#type(&mut self, name: &str) -> i64 {
+        self.id("type", name, |library, id| {
+            library.types.push(Named {
+                id,
+                name: name.to_string(),
+            });
+        })
+    }
+
+    pub fn format(&mut self, name: &str) -> i64 {
+        self.id("format", name, |library, id| {
+            library.formats.push(Named {
+                id,
+                name: name.to_string(),
+            });
+        })
+    }
+
+    pub fn creator(&mut self, name: &str) -> i64 {
+        self.id("creator", name, |library, id| {
+            library.creators.push(Named {
+                id,
+                name: name.to_string(),
+            });
+        })
+    }
+
+    pub fn tag(&mut self, name: &str) -> i64 {
+        self.id("tag", name, |library, id| {
+            library.tags.push(Named {
+                id,
+                name: name.to_string(),
+            });
+        })
+    }
+
+    /// Adds `work` with the next id, its other ids should come from this builder.
+    pub fn work(&mut self, work: Work) -> i64 {
+        let id = self.library.works.len() as i64 + 1;
+        self.library.works.push(Work { id, ..work });
+        id
+    }
+
+    /// Links the work with `work_id` to its id in `source`, so later imports find it again.
+    pub fn link(&mut self, work_id: i64, source: &str, external_id: String) {
+        let source = source.to_string();
+        self.library.external_ids.push(ExternalId {
+            work_id,
+            source,
+            external_id,
+        });
+    }
+
+    pub fn build(self) -> Library {
+        self.library
+    }
+}
+
+pub fn export(store: &impl LibraryStore, now: i64) -> DatabaseResult<Library> {
+    let named = |id, name| Named { id, name };
+
+    store.transaction(|store| {
+        Ok(Library {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported: now,
+            statuses: store
+                .get_statuses()?
+                .into_iter()
+                .map(|status| LibraryStatus {
+                    id: status.id,
+                    name: status.name,
+                    is_update: status.is_update,
+                    stale_after: status.stale_after,
+                })
+                .collect(),
+            types: store
+                .get_types()?
+                .into_iter()
+                .map(|row| named(row.id, row.name))
+                .collect(),
+            formats: store
+                .get_formats()?
+                .into_iter()
+                .map(|row| named(row.id, row.name))
+                .collect(),
+            tags: store
+                .get_tags()?
+                .into_iter()
+                .map(|row| named(row.id, row.name))
+                .collect(),
+            creators: store
+                .get_creators()?
+                .into_iter()
+                .map(|row| named(row.id, row.name))
+                .collect(),
+            works: store.get_works()?,
+            schedules: store
+                .get_release_schedules(now)?
+                .into_iter()
+                .map(|state| state.schedule)
+                .collect(),
+            external_ids: store.get_external_ids()?,
+        })
+    })
+}
+
+/// Merges `library` into `store`. Statuses, types, formats and tags are matched to existing
+/// ones by name, creators by [`creator_key`] and works by external id or else by name, type and
+/// format, only the rest is added. What happens to matched works depends on `mode`. Nothing is
+/// kept when `dry_run` is set or when anything fails.
+pub fn import(
+    store: &impl LibraryStore,
+    library: &Library,
+    mode: ImportMode,
+    dry_run: bool,
+) -> DatabaseResult<ImportReport> {
+    let merge = |store: &_| match mode {
+        ImportMode::Progress => update_progress(store, library),
+        mode => merge(store, library, mode),
+    };
+    if dry_run {
+        store.dry_run(merge)
+    } else {
+        store.transaction(merge)
+    }
+}
+
+/// The earlier of two dates an importer saw a work logged at, either of which may be missing.
+pub fn earliest(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Creators are told apart by letters and digits only, ignoring case, so `J.R.R. Tolkien` and
+/// `J. R. R. Tolkien` are the same creator.
+pub fn creator_key(name: &str) -> String {
+    name.chars()
+        .filter(|char| char.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Maps the document ids of `rows` to ids in the store, using the existing row with the same
+/// key if there is one and adding the row otherwise.
+fn map_rows<T, K: Eq + Hash>(
+    mut existing: HashMap<K, i64>,
+    rows: &[T],
+    id: impl Fn(&T) -> i64,
+    key: impl Fn(&T) -> K,
+    mut add: impl FnMut(&T) -> DatabaseResult<i64>,
+    count: &mut ImportCount,
+) -> DatabaseResult<HashMap<i64, i64>> {
+    rows.iter()
+        .map(|row| {
+            let mapped = match existing.get(&key(row)) {
+                Some(&mapped) => {
+                    count.matched.push(mapped);
+                    mapped
+                }
+                None => {
+                    let mapped = add(row)?;
+                    existing.insert(key(row), mapped);
+                    count.added.push(mapped);
+                    mapped
+                }
+            };
+            Ok((id(row), mapped))
+        })
+        .collect()
+}
+
+fn lookup(ids: &HashMap<i64, i64>, entity: &str, id: i64, work: &Work) -> DatabaseResult<i64> {
+    ids.get(&id).copied().ok_or_else(|| {
+        let work = format!("Work '{}' [{}]", work.name, work.id);
+        Error::Invalid(format!("{work} refers to unknown {entity} [{id}]"))
+    })
+}
+
+/// Finds the works in the store that external ids are linked to.
+struct Links<'a> {
+    linked: HashMap<(String, String), i64>,
+    /// The external ids of each work in the document.
+    external_ids: HashMap<i64, Vec<&'a ExternalId>>,
+}
+
+impl<'a> Links<'a> {
+    fn new(store: &impl LibraryStore, library: &'a Library) -> DatabaseResult<Self> {
+        let linked = store
+            .get_external_ids()?
+            .into_iter()
+            .map(|row| ((row.source, row.external_id), row.work_id))
+            .collect();
+        let mut external_ids = HashMap::<_, Vec<_>>::new();
+        for external_id in &library.external_ids {
+            external_ids
+                .entry(external_id.work_id)
+                .or_default()
+                .push(external_id);
+        }
+        Ok(Links {
+            linked,
+            external_ids,
+        })
+    }
+
+    fn of(&self, work: &Work) -> &[&'a ExternalId] {
+        self.external_ids.get(&work.id).map_or(&[], Vec::as_slice)
+    }
+
+    /// The id in the store of the work `work` is linked to by any of its external ids.
+    fn find(&self, work: &Work) -> Option<i64> {
+        self.of(work).iter().find_map(|row| {
+            self.linked
+                .get(&(row.source.clone(), row.external_id.clone()))
+                .copied()
+        })
+    }
+
+    /// Whether `existing` still has the name a source gives works it has no title for while
+    /// `work` has a real one.
+    fn renames(&self, work: &Work, existing: &Work) -> bool {
+        let placeholder = |name: &str| {
+            self.of(work).iter().any(|row| match row.source.as_str() {
+                anilist::SOURCE => anilist::is_placeholder(&row.external_id, name),
+                _ => false,
+            })
+        };
+        work.name != existing.name && placeholder(&existing.name) && !placeholder(&work.name)
+    }
+
+    /// Links the external ids of `work` to `work_id`.
+    fn link(&self, store: &impl LibraryStore, work: &Work, work_id: i64) -> DatabaseResult<()> {
+        self.of(work).iter().try_for_each(|row| {
+            store.set_external_id(&ExternalId {
+                work_id,
+                ..(*row).clone()
+            })
+        })
+    }
+}
+
+/// Gives `work` the progress of `source`, its name when `rename` is set and, if set, the
+/// `status`. The updated time moves up to that of `source` if anything changed. Returns whether
+/// it did.
+fn sync(
+    store: &impl LibraryStore,
+    work: &Work,
+    source: &Work,
+    rename: bool,
+    status: Option<i64>,
+) -> DatabaseResult<bool> {
+    let mut columns = vec![];
+    if rename {
+        columns.push(WorkColumn::Name(source.name.clone()));
+    }
+    if work.progress != source.progress {
+        columns.push(WorkColumn::Progress(source.progress.clone()));
+    }
+    if let Some(status) = status.filter(|status| *status != work.status) {
+        columns.push(WorkColumn::Status(status));
+    }
+    if columns.is_empty() {
+        return Ok(false);
+    }
+
+    columns.push(WorkColumn::Updated(work.updated.max(source.updated)));
+    store.update(work.id, &columns)?;
+    Ok(true)
+}
+
+fn update_progress(store: &impl LibraryStore, library: &Library) -> DatabaseResult<ImportReport> {
+    let mut report = ImportReport::default();
+    let links = Links::new(store, library)?;
+    let existing = store
+        .get_works()?
+        .into_iter()
+        .map(|work| (work.id, work))
+        .collect::<HashMap<_, _>>();
+
+    for work in &library.works {
+        let Some(existing) = links.find(work).and_then(|id| existing.get(&id)) else {
+            continue;
+        };
+        report.works.matched.push(existing.id);
+        if sync(store, existing, work, links.renames(work, existing), None)? {
+            report.updated.push(existing.id);
+        }
+    }
+    Ok(report)
+}
+
+fn merge(
+    store: &impl LibraryStore,
+    library: &Library,
+    mode: ImportMode,
+) -> DatabaseResult<ImportReport> {
+    let mut report = ImportReport::default();
+    let by_name = |rows: Vec<(i64, String)>| {
+        rows.into_iter()
+            .map(|(id, name)| (name, id))
+            .collect::<HashMap<_, _>>()
+    };
+    let name = |row: &Named| row.name.clone();
+    let id = |row: &Named| row.id;
+
+    let statuses = map_rows(
+        by_name(
+            store
+                .get_statuses()?
+                .into_iter()
+                .map(|row| (row.id, row.name))
+                .collect(),
+        ),
+        &library.statuses,
+        |row| row.id,
+        |row| row.name.clone(),
+        |row| {
+            store.add(&NewStatus {
+                name: row.name.clone(),
+                is_update: row.is_update,
+                stale_after: row.stale_after,
+            })
+        },
+        &mut report.statuses,
+    )?;
+    let types = map_rows(
+        by_name(
+            store
+                .get_types()?
+                .into_iter()
+                .map(|row| (row.id, row.name))
+                .collect(),
+        ),
+        &library.types,
+        id,
+        name,
+        |row| {
+            store.add(&NewType {
+                name: row.name.clone(),
+            })
+        },
+        &mut report.types,
+    )?;
+    let formats = map_rows(
+        by_name(
+            store
+                .get_formats()?
+                .into_iter()
+                .map(|row| (row.id, row.name))
+                .collect(),
+        ),
+        &library.formats,
+        id,
+        name,
+        |row| {
+            store.add(&NewFormat {
+                name: row.name.clone(),
+            })
+        },
+        &mut report.formats,
+    )?;
+    let tags = map_rows(
+        by_name(
+            store
+                .get_tags()?
+                .into_iter()
+                .map(|row| (row.id, row.name))
+                .collect(),
+        ),
+        &library.tags,
+        id,
+        name,
+        |row| {
+            store.add(&NewTag {
+                name: row.name.clone(),
+            })
+        },
+        &mut report.tags,
+    )?;
+    // Creator names aren't unique, the first one with the key is used.
+    let mut existing_creators = HashMap::new();
+    for creator in store.get_creators()? {
+        existing_creators
+            .entry(creator_key(&creator.name))
+            .or_insert(creator.id);
+    }
+    let creators = map_rows(
+        existing_creators,
+        &library.creators,
+        id,
+        |row| creator_key(&row.name),
+        |row| {
+            store.add(&NewCreator {
+                name: row.name.clone(),
+            })
+        },
+        &mut report.creators,
+    )?;
+
+    let links = Links::new(store, library)?;
+    let mut existing = HashMap::new();
+    let mut existing_works = HashMap::new();
+    for work in store.get_works()? {
+        existing_works
+            .entry((work.name.clone(), work.r#type, work.format))
+            .or_insert(work.id);
+        existing.insert(work.id, work);
+    }
+    let mut works = HashMap::new();
+    for work in &library.works {
+        let r#type = lookup(&types, "type", work.r#type, work)?;
+        let format = lookup(&formats, "format", work.format, work)?;
+        let key = (work.name.clone(), r#type, format);
+        let matched = links
+            .find(work)
+            .filter(|id| existing.contains_key(id))
+            .or_else(|| existing_works.get(&key).copied());
+        if let Some(mapped) = matched {
+            report.works.matched.push(mapped);
+            links.link(store, work, mapped)?;
+            if mode == ImportMode::Sync {
+                let status = lookup(&statuses, "status", work.status, work)?;
+                if let Some(existing) = existing.get(&mapped) {
+                    let rename = links.renames(work, existing);
+                    if sync(store, existing, work, rename, Some(status))? {
+                        report.updated.push(mapped);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let mapped = store.add_work(&Work {
+            id: 0,
+            name: work.name.clone(),
+            progress: work.progress.clone(),
+            status: lookup(&statuses, "status", work.status, work)?,
+            r#type,
+            format,
+            updated: work.updated,
+            added: work.added,
+            creators: work
+                .creators
+                .iter()
+                .map(|creator| lookup(&creators, "creator", *creator, work))
+                .collect::<DatabaseResult<_>>()?,
+            tags: work
+                .tags
+                .iter()
+                .map(|tag| lookup(&tags, "tag", *tag, work))
+                .collect::<DatabaseResult<_>>()?,
+        })?;
+        existing_works.insert(key, mapped);
+        links.link(store, work, mapped)?;
+        works.insert(work.id, mapped);
+        report.works.added.push(mapped);
+    }
+
+    // Matched works keep their own schedules.
+    for schedule in &library.schedules {
+        if let Some(&work_id) = works.get(&schedule.work_id) {
+            store.set_release_schedule(&ReleaseSchedule {
+                work_id,
+                ..schedule.clone()
+            })?;
+            report.schedules += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::entity::NewWork;
+    use crate::schedule::Recurrence;
+
+    fn open() -> Database {
+        let mut database = Database::default();
+        database.open_in_memory().unwrap();
+        database
+    }
+
+    #[test]
+    fn exports_and_imports_library() -> Result<(), Box<dyn std::error::Error>> {
+        let source = open();
+        let second = source.add(&NewStatus {
+            name: "second".into(),
+            ..Default::default()
+        })?;
+        let first = source.add(&NewStatus {
+            name: "first".into(),
+            is_update: true,
+            stale_after: Some(7),
+        })?;
+        source.reorder(crate::entity::Sorted::Statuses, first, second)?;
+        let r#type = source.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format = source.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let tag = source.add(&NewTag { name: "tag".into() })?;
+        let creator = source.add(&NewCreator {
+            name: "creator".into(),
+        })?;
+        let work = source.add(&NewWork {
+            name: "work".into(),
+            progress: "4".into(),
+            status: first,
+            r#type,
+            format,
+            updated: 44,
+            added: 4,
+        })?;
+        source.attach(work, creator)?;
+        source.tag(work, tag)?;
+        source.set_release_schedule(&ReleaseSchedule {
+            work_id: work,
+            recurrence: Recurrence::Interval { days: 7, start: 0 },
+            offset: 1,
+        })?;
+
+        let library = export(&source, 0)?;
+        let json = serde_json::to_string(&library)?;
+        let library: Library = serde_json::from_str(&json)?;
+
+        let target = open();
+        target.add(&NewType {
+            name: "type".into(),
+        })?;
+        let dry = import(&target, &library, ImportMode::Add, true)?;
+
+        assert_eq!(dry.works.added.len(), 1);
+        assert!(target.get_works()?.is_empty());
+
+        let report = import(&target, &library, ImportMode::Add, false)?;
+
+        assert_eq!(report.statuses.added.len(), 2);
+        assert_eq!(report.types.matched.len(), 1);
+        assert_eq!(report.schedules, 1);
+        assert_eq!(
+            target
+                .get_statuses()?
+                .into_iter()
+                .map(|status| status.name)
+                .collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+        let works = target.get_works()?;
+        assert_eq!(works.len(), 1);
+        assert_eq!(works[0].progress, "4");
+        assert_eq!(works[0].creators.len(), 1);
+        assert_eq!(works[0].tags.len(), 1);
+        assert_eq!(export(&target, 0)?.schedules[0].offset, 1);
+
+        let again = import(&target, &library, ImportMode::Add, false)?;
+
+        assert!(again.works.added.is_empty());
+        assert_eq!(again.works.matched, vec![works[0].id]);
+        assert_eq!(target.get_works()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reimports_works_by_external_id() -> Result<(), Box<dyn std::error::Error>> {
+        let source = |name: &str, progress: &str, status: &str, updated: i64| {
+            let mut builder = Builder::new(0);
+            let work = Work {
+                id: 0,
+                name: name.into(),
+                progress: progress.into(),
+                status: builder.status(status, true),
+                r#type: builder.r#type("type"),
+                format: builder.format("format"),
+                updated,
+                added: 1,
+                creators: vec![],
+                tags: vec![],
+            };
+            let id = builder.work(work);
+            builder.link(id, "app", "42".into());
+            builder.build()
+        };
+        let target = open();
+
+        let first = import(
+            &target,
+            &source("work", "1", "reading", 1),
+            ImportMode::Add,
+            false,
+        )?;
+        let work_id = first.works.added[0];
+        let renamed = source("renamed", "2", "reading", 2);
+        let added = import(&target, &renamed, ImportMode::Add, false)?;
+
+        assert_eq!(added.works.matched, vec![work_id]);
+        assert!(added.updated.is_empty());
+        assert_eq!(target.get_works()?[0].progress, "1");
+
+        let progress = import(&target, &renamed, ImportMode::Progress, false)?;
+        let finished = source("renamed", "3", "finished", 0);
+        let synced = import(&target, &finished, ImportMode::Sync, false)?;
+
+        assert_eq!(progress.updated, vec![work_id]);
+        assert_eq!(synced.updated, vec![work_id]);
+        let works = target.get_works()?;
+        assert_eq!(works.len(), 1);
+        assert_eq!(
+            (works[0].name.as_str(), works[0].progress.as_str()),
+            ("work", "3")
+        );
+        assert_eq!(works[0].updated, 2);
+        assert_eq!(target.get_statuses()?.len(), 2);
+        assert_eq!(target.get_external_ids()?[0].work_id, work_id);
+
+        let mut unlinked = source("other", "1", "reading", 1);
+        unlinked.external_ids.clear();
+        let ignored = import(&target, &unlinked, ImportMode::Progress, false)?;
+
+        assert_eq!(ignored, ImportReport::default());
+        assert_eq!(target.get_works()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_references() {
+        let library = Library {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported: 0,
+            statuses: vec![],
+            types: vec![Named {
+                id: 1,
+                name: "type".into(),
+            }],
+            formats: vec![Named {
+                id: 1,
+                name: "format".into(),
+            }],
+            tags: vec![],
+            creators: vec![],
+            works: vec![Work {
+                id: 1,
+                name: "work".into(),
+                progress: String::new(),
+                status: 4,
+                r#type: 1,
+                format: 1,
+                updated: 0,
+                added: 0,
+                creators: vec![],
+                tags: vec![],
+            }],
+            schedules: vec![],
+            external_ids: vec![],
+        };
+        let target = open();
+
+        let result = import(&target, &library, ImportMode::Add, false);
+
+        assert!(matches!(result, Err(Error::Invalid(_))));
+        assert!(target.get_types().unwrap().is_empty());
+    }
+}
@@ -2,17 +2,39 @@
 
 use tauri::Manager;
 
+mod anilist;
 mod api;
 mod application;
+mod backup;
+mod calibre;
+mod commands;
 mod config;
+mod csv;
 mod database;
+mod entity;
+mod error;
 mod event;
+mod goodreads;
+mod integrity;
+mod letterboxd;
+mod library;
+mod mal;
 mod menu;
+mod pool;
+mod restore;
+mod schedule;
+mod service;
+mod store;
+mod tachiyomi;
+mod trakt;
+mod window;
+mod zip;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_single_instance::init(|app, _, _| {
-            app.get_webview_window("main").map(|window| window.set_focus());
+            app.get_webview_window(window::MAIN_WINDOW)
+                .map(|window| window.set_focus());
         }))
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
@@ -55,10 +77,30 @@ fn main() {
                 .build(),
         )
         .setup(application::setup)
+        .on_window_event(window::event_handler)
         .invoke_handler(tauri::generate_handler![
             api::open_database,
             api::close_database,
             api::database_path,
+            api::list_backups,
+            api::get_backup_settings,
+            api::set_backup_settings,
+            api::preview_restore,
+            api::restore_backup,
+            api::locked_database,
+            api::set_password,
+            api::check_integrity,
+            api::repair_database,
+            api::export_library,
+            api::import_library,
+            api::calibre_columns,
+            api::export_csv,
+            api::get_export_mapping,
+            api::set_export_mapping,
+            api::export_mal,
+            api::export_goodreads,
+            api::preview_csv,
+            api::import_csv,
             api::get_works,
             api::get_creators,
             api::update_work_name,
@@ -73,6 +115,7 @@ fn main() {
             api::get_statuses,
             api::update_status_name,
             api::update_status_is_update,
+            api::update_status_stale_after,
             api::reorder_statuses,
             api::remove_work,
             api::remove_creator,
@@ -88,7 +131,22 @@ fn main() {
             api::update_format_name,
             api::reorder_formats,
             api::attach,
-            api::detach
+            api::detach,
+            api::add_tag,
+            api::get_tags,
+            api::get_changes_since,
+            api::remove_tag,
+            api::update_tag_name,
+            api::tag,
+            api::untag,
+            api::bulk_edit,
+            api::snooze_work,
+            api::get_stale_works,
+            api::set_release_schedule,
+            api::remove_release_schedule,
+            api::get_release_schedules,
+            api::upcoming_releases,
+            api::open_window
         ])
         .build(tauri::generate_context!())
         .unwrap_or_else(|err| panic!("Failed to build application: {err}."))
@@ -0,0 +1,396 @@
+use crate::csv::parse_timestamp;
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{Builder, Library};
+use crate::service::{self, ExportReport, Service, Values, MAL_ANIME_TYPES};
+use crate::store::LibraryStore;
+use chrono::{Local, TimeZone};
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// External ids are `anime:` or `manga:` then the MyAnimeList id, as anime and manga ids overlap.
+pub const SOURCE: &str = "myanimelist";
+
+/// Statuses of older exports, which wrote them as numbers.
+const STATUSES: [(&str, &str, &str); 5] = [
+    ("1", "Watching", "Reading"),
+    ("2", "Completed", "Completed"),
+    ("3", "On-Hold", "On-Hold"),
+    ("4", "Dropped", "Dropped"),
+    ("6", "Plan to Watch", "Plan to Read"),
+];
+
+/// One `<anime>` or `<manga>` entry, its child elements by name.
+struct Entry {
+    manga: bool,
+    fields: HashMap<String, String>,
+}
+
+impl Entry {
+    fn get(&self, anime: &str, manga: &str) -> &str {
+        let name = if self.manga { manga } else { anime };
+        self.fields.get(name).map_or("", |value| value.trim())
+    }
+
+    fn status(&self) -> &str {
+        let status = self.get("my_status", "my_status");
+        STATUSES
+            .iter()
+            .find(|(number, ..)| *number == status)
+            .map_or(
+                status,
+                |(_, anime, manga)| if self.manga { manga } else { anime },
+            )
+    }
+
+    /// `0000-00-00` and other placeholders for a missing date read as `None`.
+    fn date(&self, name: &str) -> Option<i64> {
+        parse_timestamp(self.get(name, name)).filter(|timestamp| *timestamp != 0)
+    }
+}
+
+fn invalid(err: impl std::fmt::Display) -> Error {
+    Error::Invalid(format!("Not a valid MyAnimeList export: {err}"))
+}
+
+fn entries(xml: &str) -> DatabaseResult<Vec<Entry>> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut entries = vec![];
+    let mut entry: Option<Entry> = None;
+    let mut field: Option<String> = None;
+    loop {
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+                match (&entry, name.as_str()) {
+                    (None, "anime" | "manga") => {
+                        entry = Some(Entry {
+                            manga: name == "manga",
+                            fields: HashMap::new(),
+                        });
+                    }
+                    (Some(_), _) => field = Some(name),
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                if let (Some(entry), Some(field)) = (&mut entry, &field) {
+                    let text = text.unescape().map_err(invalid)?;
+                    entry
+                        .fields
+                        .entry(field.clone())
+                        .or_default()
+                        .push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let (Some(entry), Some(field)) = (&mut entry, &field) {
+                    let text = String::from_utf8_lossy(&data.into_inner()).into_owned();
+                    entry
+                        .fields
+                        .entry(field.clone())
+                        .or_default()
+                        .push_str(&text);
+                }
+            }
+            Event::End(end) => match end.name().as_ref() {
+                b"anime" | b"manga" => entries.extend(entry.take()),
+                _ => field = None,
+            },
+            Event::Eof if entry.is_some() => return Err(invalid("the file ends in an entry")),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads a MyAnimeList anime or manga list export as a library. Each entry becomes a work in
+/// the status it has on MyAnimeList, with the episodes or chapters read so far as progress.
+/// Anime get their series type, manga the `Manga` type, and all of them the `Anime` or `Manga`
+/// format. The start date becomes the time it was added and the finish date, or the start date
+/// before it's finished, the time it was last updated. Entries are linked to their MyAnimeList
+/// id so the works can be exported back.
+pub fn parse(xml: &str, now: i64) -> DatabaseResult<Library> {
+    let entries = entries(xml)?;
+    if entries.is_empty() && !xml.contains("<myanimelist") {
+        return Err(Error::Invalid("Not a MyAnimeList export".to_string()));
+    }
+
+    let mut builder = Builder::new(now);
+    for entry in entries {
+        let name = entry.get("series_title", "manga_title");
+        if name.is_empty() {
+            continue;
+        }
+
+        let status_name = entry.status();
+        let is_update = matches!(status_name, "Watching" | "Reading");
+        let status = builder.status(status_name, is_update);
+        let r#type = match entry.get("series_type", "") {
+            _ if entry.manga => builder.r#type("Manga"),
+            "" => builder.r#type("Anime"),
+            series_type => builder.r#type(series_type),
+        };
+        let format = builder.format(if entry.manga { "Manga" } else { "Anime" });
+        let progress = match entry.get("my_watched_episodes", "my_read_chapters") {
+            "" => "0",
+            progress => progress,
+        };
+        let added = entry.date("my_start_date").unwrap_or(now);
+
+        let work_id = builder.work(Work {
+            id: 0,
+            name: name.to_string(),
+            progress: progress.to_string(),
+            status,
+            r#type,
+            format,
+            updated: entry.date("my_finish_date").unwrap_or(added),
+            added,
+            creators: vec![],
+            tags: vec![],
+        });
+        match entry.get("series_animedb_id", "manga_mangadb_id") {
+            "" | "0" => {}
+            id => {
+                let kind = if entry.manga { "manga" } else { "anime" };
+                builder.link(work_id, SOURCE, format!("{kind}:{id}"));
+            }
+        }
+    }
+    Ok(builder.build())
+}
+
+pub fn read(path: &Path, now: i64) -> DatabaseResult<Library> {
+    parse(&std::fs::read_to_string(path)?, now)
+}
+
+fn format_date(timestamp: i64) -> String {
+    match Local.timestamp_millis_opt(timestamp).single() {
+        Some(datetime) => datetime.format("%Y-%m-%d").to_string(),
+        None => "0000-00-00".to_string(),
+    }
+}
+
+/// Writes the works that map to MyAnimeList anime, or manga when `manga` is set, as a list
+/// MyAnimeList can import, using the status and type mapping stored for it. Works need the
+/// MyAnimeList id an import from it linked them to and a progress that counts episodes or
+/// chapters, the others are reported as skipped. Works of types from the other list are left
+/// for its own export.
+pub fn export(store: &impl LibraryStore, manga: bool) -> DatabaseResult<(String, ExportReport)> {
+    let values = Values::new(service::mapping(store, Service::MyAnimeList)?);
+    let statuses = store.get_statuses()?;
+    let types = store.get_types()?;
+    let kind = if manga { "manga" } else { "anime" };
+    let prefix = format!("{kind}:");
+    let ids = store
+        .get_external_ids()?
+        .into_iter()
+        .filter(|row| row.source == SOURCE)
+        .filter_map(|row| {
+            Some((
+                row.work_id,
+                row.external_id.strip_prefix(&prefix)?.to_string(),
+            ))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut report = ExportReport::default();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>\n<myanimelist>\n");
+    let export_type = if manga { 2 } else { 1 };
+    writeln!(
+        xml,
+        "    <myinfo><user_export_type>{export_type}</user_export_type></myinfo>"
+    )
+    .unwrap();
+    for work in store.get_works()? {
+        let Some(r#type) = values.r#type(work.r#type) else {
+            let r#type = types.iter().find(|row| row.id == work.r#type);
+            let name = r#type.map_or("", |row| &row.name);
+            report.skip(&work, format!("Type '{name}' has no MyAnimeList type"));
+            continue;
+        };
+        if MAL_ANIME_TYPES.contains(&r#type) == manga {
+            continue;
+        }
+        let Some(status) = values.status(work.status) else {
+            let status = statuses.iter().find(|row| row.id == work.status);
+            let name = status.map_or("", |row| &row.name);
+            report.skip(&work, format!("Status '{name}' has no MyAnimeList status"));
+            continue;
+        };
+        let Some(id) = ids.get(&work.id) else {
+            report.skip(&work, format!("Not linked to a MyAnimeList {kind}"));
+            continue;
+        };
+        let Some(progress) = work.progress.trim().parse::<u32>().ok() else {
+            report.skip(
+                &work,
+                format!("Progress '{}' is not a number", work.progress),
+            );
+            continue;
+        };
+
+        let status = match (status, manga) {
+            ("Watching", true) => "Reading",
+            ("Reading", false) => "Watching",
+            ("Plan to Watch", true) => "Plan to Read",
+            ("Plan to Read", false) => "Plan to Watch",
+            (status, _) => status,
+        };
+        let finished = match status {
+            "Completed" => format_date(work.updated),
+            _ => "0000-00-00".to_string(),
+        };
+        let title = quick_xml::escape::escape(work.name.as_str());
+        let fields = if manga {
+            vec![
+                format!("<manga_mangadb_id>{id}</manga_mangadb_id>"),
+                format!("<manga_title>{title}</manga_title>"),
+                format!("<my_read_chapters>{progress}</my_read_chapters>"),
+            ]
+        } else {
+            vec![
+                format!("<series_animedb_id>{id}</series_animedb_id>"),
+                format!("<series_title>{title}</series_title>"),
+                format!(
+                    "<series_type>{}</series_type>",
+                    quick_xml::escape::escape(r#type)
+                ),
+                format!("<my_watched_episodes>{progress}</my_watched_episodes>"),
+            ]
+        };
+
+        writeln!(xml, "    <{kind}>").unwrap();
+        for field in fields.iter().chain(&[
+            format!("<my_start_date>{}</my_start_date>", format_date(work.added)),
+            format!("<my_finish_date>{finished}</my_finish_date>"),
+            format!(
+                "<my_status>{}</my_status>",
+                quick_xml::escape::escape(status)
+            ),
+            "<update_on_import>1</update_on_import>".to_string(),
+        ]) {
+            writeln!(xml, "        {field}").unwrap();
+        }
+        writeln!(xml, "    </{kind}>").unwrap();
+        report.exported += 1;
+    }
+    xml.push_str("</myanimelist>\n");
+    Ok((xml, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANIME: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+        <myanimelist>
+            <myinfo><user_export_type>1</user_export_type></myinfo>
+            <anime>
+                <series_animedb_id>1</series_animedb_id>
+                <series_title><![CDATA[Cowboy Bebop]]></series_title>
+                <series_type>TV</series_type>
+                <my_watched_episodes>26</my_watched_episodes>
+                <my_start_date>2020-01-02</my_start_date>
+                <my_finish_date>2020-02-03</my_finish_date>
+                <my_status>Completed</my_status>
+            </anime>
+            <anime>
+                <series_title>Tom &amp; Jerry</series_title>
+                <series_type>Movie</series_type>
+                <my_watched_episodes>0</my_watched_episodes>
+                <my_start_date>0000-00-00</my_start_date>
+                <my_finish_date>0000-00-00</my_finish_date>
+                <my_status>6</my_status>
+            </anime>
+        </myanimelist>"#;
+
+    const MANGA: &str = r#"<myanimelist>
+            <manga>
+                <manga_mangadb_id>2</manga_mangadb_id>
+                <manga_title><![CDATA[Berserk]]></manga_title>
+                <my_read_volumes>10</my_read_volumes>
+                <my_read_chapters>95</my_read_chapters>
+                <my_start_date>2021-05-06</my_start_date>
+                <my_finish_date>0000-00-00</my_finish_date>
+                <my_status>Reading</my_status>
+            </manga>
+        </myanimelist>"#;
+
+    #[test]
+    fn reads_anime_export() -> Result<(), Box<dyn std::error::Error>> {
+        let library = parse(ANIME, 7)?;
+
+        assert_eq!(Library::names(&library.types), vec!["TV", "Movie"]);
+        assert_eq!(Library::names(&library.formats), vec!["Anime"]);
+        assert_eq!(library.status_names(), vec!["Completed", "Plan to Watch"]);
+
+        let bebop = &library.works[0];
+        assert_eq!(bebop.name, "Cowboy Bebop");
+        assert_eq!(bebop.progress, "26");
+        assert_eq!(bebop.added, parse_timestamp("2020-01-02").unwrap());
+        assert_eq!(bebop.updated, parse_timestamp("2020-02-03").unwrap());
+
+        let movie = &library.works[1];
+        assert_eq!(movie.name, "Tom & Jerry");
+        assert_eq!((movie.added, movie.updated), (7, 7));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_manga_export() -> Result<(), Box<dyn std::error::Error>> {
+        let library = parse(MANGA, 7)?;
+
+        let berserk = &library.works[0];
+        assert_eq!(berserk.progress, "95");
+        assert_eq!(berserk.added, berserk.updated);
+        assert_eq!(library.types[0].name, "Manga");
+        assert!(library.statuses[0].is_update);
+
+        assert!(matches!(parse("<goodreads/>", 0), Err(Error::Invalid(_))));
+        assert!(matches!(
+            parse("<myanimelist><anime>", 0),
+            Err(Error::Invalid(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn exports_linked_entries() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::library::{import, ImportMode};
+
+        let mut store = crate::database::Database::default();
+        store.open_in_memory()?;
+        import(&store, &parse(ANIME, 7)?, ImportMode::Add, false)?;
+        import(&store, &parse(MANGA, 7)?, ImportMode::Add, false)?;
+
+        let (anime, report) = export(&store, false)?;
+
+        assert_eq!(report.exported, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].name, "Tom & Jerry");
+        assert!(anime.contains("<series_animedb_id>1</series_animedb_id>"));
+        assert!(anime.contains("<my_finish_date>2020-02-03</my_finish_date>"));
+        let bebop = &parse(&anime, 7)?.works[0];
+        assert_eq!(
+            (bebop.name.as_str(), bebop.progress.as_str()),
+            ("Cowboy Bebop", "26")
+        );
+
+        let (manga, report) = export(&store, true)?;
+
+        assert_eq!((report.exported, report.skipped.len()), (1, 0));
+        assert!(manga.contains("<my_status>Reading</my_status>"));
+        assert!(manga.contains("<my_finish_date>0000-00-00</my_finish_date>"));
+
+        Ok(())
+    }
+}
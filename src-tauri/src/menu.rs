@@ -1,23 +1,89 @@
+use crate::api;
+use crate::{
+    config::Config,
+    error::Error,
+    event::SHOW_BACKUPS_EVENT,
+    integrity::IntegrityReport,
+    library::{ImportMode, Source},
+    pool::Pool,
+    service::Service,
+    tachiyomi::Categories,
+    window,
+};
+use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri::menu::{Menu, MenuBuilder, MenuId, MenuItemBuilder, SubmenuBuilder};
-use tauri::{webview_version, Manager, Wry, VERSION};
+use tauri::menu::{
+    Menu, MenuBuilder, MenuId, MenuItemBuilder, MenuItemKind, PredefinedMenuItem, Submenu,
+    SubmenuBuilder,
+};
+use tauri::{webview_version, Emitter, Manager, Wry, VERSION};
 use tauri_plugin_clipboard_manager::ClipboardExt;
-use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
+};
 use tauri_plugin_opener::OpenerExt;
-use crate::api;
-use crate::{config::Config, database::Database};
-
 
-
-pub fn create_main_menu(app: &tauri::App) -> Result<tauri::menu::Menu<Wry>, Box<dyn std::error::Error>> {
+pub fn create_main_menu<M: Manager<Wry>>(app: &M, recent: &[PathBuf]) -> tauri::Result<Menu<Wry>> {
     // Database.
-    let new = MenuItemBuilder::with_id("new", "New...")
+    let new = MenuItemBuilder::with_id("new", "New...").build(app)?;
+    let open = MenuItemBuilder::with_id("open", "Open...").build(app)?;
+    let open_recent = SubmenuBuilder::with_id(app, "open_recent", "Open Recent").build()?;
+    set_recent_items(app, &open_recent, recent)?;
+    let import_library =
+        MenuItemBuilder::with_id("import_library", "Library (JSON)...").build(app)?;
+    let import_mal = MenuItemBuilder::with_id("import_mal", "MyAnimeList (XML)...").build(app)?;
+    let import_goodreads =
+        MenuItemBuilder::with_id("import_goodreads", "Goodreads (CSV)...").build(app)?;
+    let import_letterboxd =
+        MenuItemBuilder::with_id("import_letterboxd", "Letterboxd (ZIP)...").build(app)?;
+    let import_anilist =
+        MenuItemBuilder::with_id("import_anilist", "AniList (JSON)...").build(app)?;
+    let import_trakt = MenuItemBuilder::with_id("import_trakt", "Trakt (ZIP)...").build(app)?;
+    let import_tachiyomi =
+        MenuItemBuilder::with_id("import_tachiyomi", "Mihon (TACHIBK)...").build(app)?;
+    let import_tachiyomi_progress =
+        MenuItemBuilder::with_id("import_tachiyomi_progress", "Mihon Progress (TACHIBK)...")
+            .build(app)?;
+    let import = SubmenuBuilder::with_id(app, "import", "Import")
+        .item(&import_library)
+        .item(&import_mal)
+        .item(&import_goodreads)
+        .item(&import_letterboxd)
+        .item(&import_anilist)
+        .item(&import_trakt)
+        .item(&import_tachiyomi)
+        .item(&import_tachiyomi_progress)
+        .build()?;
+    let export_library =
+        MenuItemBuilder::with_id("export_library", "Library (JSON)...").build(app)?;
+    let export_csv = MenuItemBuilder::with_id("export_csv", "Works (CSV)...").build(app)?;
+    let export_mal_anime =
+        MenuItemBuilder::with_id("export_mal_anime", "MyAnimeList Anime (XML)...").build(app)?;
+    let export_mal_manga =
+        MenuItemBuilder::with_id("export_mal_manga", "MyAnimeList Manga (XML)...").build(app)?;
+    let export_goodreads =
+        MenuItemBuilder::with_id("export_goodreads", "Goodreads (CSV)...").build(app)?;
+    let export = SubmenuBuilder::with_id(app, "export", "Export")
+        .item(&export_library)
+        .item(&export_csv)
+        .separator()
+        .item(&export_mal_anime)
+        .item(&export_mal_manga)
+        .item(&export_goodreads)
+        .enabled(false)
+        .build()?;
+    let restore_backup = MenuItemBuilder::with_id("restore_backup", "Restore from Backup...")
+        .enabled(false)
         .build(app)?;
-    let open = MenuItemBuilder::with_id("open", "Open...")
+    let check_integrity = MenuItemBuilder::with_id("check_integrity", "Check Integrity...")
+        .enabled(false)
         .build(app)?;
     let close = MenuItemBuilder::with_id("close", "Close")
         .enabled(false)
         .build(app)?;
+    let close_window = MenuItemBuilder::with_id("close_window", "Close Window")
+        .accelerator("Ctrl+W")
+        .build(app)?;
     let exit = MenuItemBuilder::with_id("exit", "Exit")
         .accelerator("Alt+F4")
         .build(app)?;
@@ -25,30 +91,26 @@ pub fn create_main_menu(app: &tauri::App) -> Result<tauri::menu::Menu<Wry>, Box<
     let database_menu = SubmenuBuilder::with_id(app, "database", "Database")
         .item(&new)
         .item(&open)
+        .item(&open_recent)
+        .separator()
+        .item(&import)
+        .item(&export)
+        .separator()
+        .item(&restore_backup)
+        .item(&check_integrity)
         .separator()
         .item(&close)
         .separator()
+        .item(&close_window)
         .item(&exit)
         .build()?;
 
-
     // Help.
-    let repository = MenuItemBuilder::with_id(
-            "repository",
-            "Repository"
-        )
-        .build(app)?;
-    let dev_tools = MenuItemBuilder::with_id(
-            "dev_tools",
-            "Open Developer Tools"
-        )
+    let repository = MenuItemBuilder::with_id("repository", "Repository").build(app)?;
+    let dev_tools = MenuItemBuilder::with_id("dev_tools", "Open Developer Tools")
         .accelerator("Ctrl+Shift+I")
         .build(app)?;
-    let about = MenuItemBuilder::with_id(
-            "about",
-            "About"
-        )
-        .build(app)?;
+    let about = MenuItemBuilder::with_id("about", "About").build(app)?;
 
     let help_menu = SubmenuBuilder::new(app, "Help")
         .item(&repository)
@@ -65,79 +127,528 @@ pub fn create_main_menu(app: &tauri::App) -> Result<tauri::menu::Menu<Wry>, Box<
         .build()?)
 }
 
+pub fn set_menu_state(handle: &Menu<Wry>, enabled: bool) -> tauri::Result<()> {
+    let item = handle.get(&MenuId::new("database")).unwrap();
+    let item = item.as_submenu_unchecked();
+    ["export", "restore_backup", "check_integrity", "close"]
+        .into_iter()
+        .try_for_each(|id| match item.get(&MenuId::new(id)).unwrap() {
+            MenuItemKind::Submenu(submenu) => submenu.set_enabled(enabled),
+            item => item.as_menuitem_unchecked().set_enabled(enabled),
+        })
+}
 
-pub fn set_menu_state(
+fn set_recent_items<M: Manager<Wry>>(
+    app: &M,
+    submenu: &Submenu<Wry>,
+    recent: &[PathBuf],
+) -> tauri::Result<()> {
+    while submenu.remove_at(0)?.is_some() {}
+
+    for (index, path) in recent.iter().enumerate() {
+        let item = MenuItemBuilder::with_id(format!("recent_{index}"), path.display().to_string())
+            .build(app)?;
+        submenu.append(&item)?;
+    }
+
+    let clear_recent = MenuItemBuilder::with_id("clear_recent", "Clear Recent")
+        .enabled(!recent.is_empty())
+        .build(app)?;
+    submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    submenu.append(&clear_recent)
+}
+
+pub fn set_recent_menu<M: Manager<Wry>>(
+    app: &M,
     handle: &Menu<Wry>,
-    enabled: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+    recent: &[PathBuf],
+) -> tauri::Result<()> {
     let item = handle.get(&MenuId::new("database")).unwrap();
     let item = item.as_submenu_unchecked();
-    let item = item.get(&MenuId::new("close")).unwrap();
-    let item = item.as_menuitem_unchecked();
-    Ok(item.set_enabled(enabled)?)
+    let item = item.get(&MenuId::new("open_recent")).unwrap();
+    set_recent_items(app, item.as_submenu_unchecked(), recent)
+}
+
+fn open_database(app: &tauri::AppHandle, path: PathBuf) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let (config, pool) = (app.state::<Mutex<Config>>(), app.state::<Pool>());
+        let _ = api::open_database(app.clone(), config, pool, path, None).await;
+    });
+}
+
+fn open_recent(app: &tauri::AppHandle, index: usize) {
+    let config_state = app.state::<Mutex<Config>>();
+    let path = config_state
+        .lock()
+        .unwrap()
+        .recent_databases
+        .get(index)
+        .cloned();
+
+    match path {
+        Some(path) if path.try_exists().unwrap_or(false) => open_database(app, path),
+        path => {
+            log::error!("Recent database no longer exists: {path:?}.");
+            let mut config = config_state.lock().unwrap();
+            config.prune_recent_databases();
+            if let Err(err) = window::set_recent_menus(app, &config.recent_databases) {
+                log::error!("Failed to update recent databases menu: {err}.");
+            }
+        }
+    }
+}
+
+/// Brings up the main window on its list of backups, where one can be picked and previewed.
+fn show_backups(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(window::MAIN_WINDOW) {
+        if let Err(err) = window.set_focus() {
+            log::error!("Failed to focus main window: {err}.");
+        }
+    }
+    if let Err(err) = app.emit_to(window::MAIN_WINDOW, SHOW_BACKUPS_EVENT, ()) {
+        log::error!("Failed to show backups: {err}.");
+    }
+}
+
+fn check_integrity(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match app.state::<Pool>().read(|db| db.check_integrity()).await {
+            Ok(report) => show_integrity(&app, report),
+            Err(err) => log::error!("Failed to check database integrity: {err}."),
+        }
+    });
+}
+
+/// Shows what is wrong with the database just opened, if anything. Opening only checks it, the
+/// repairs are made once the user asks for them.
+pub async fn report_integrity(app: tauri::AppHandle) {
+    match app.state::<Pool>().read(|db| db.check_integrity()).await {
+        Ok(report) if !report.is_ok() => {
+            log::warn!("Database has problems: {report:?}.");
+            show_integrity(&app, report);
+        }
+        Ok(_) => {}
+        Err(err) => log::error!("Failed to check database integrity: {err}."),
+    }
+}
+
+fn show_integrity(app: &tauri::AppHandle, report: IntegrityReport) {
+    let mut message = report.summary();
+    if report.is_repairable() {
+        message.push_str("\n\nRepairing removes the rows left behind and renumbers the rest.");
+    }
+
+    let mut dialog = app.dialog().message(message);
+    if let Some(window) = window::focused(app) {
+        dialog = dialog.parent(&window);
+    }
+    let dialog = dialog.title("Check Integrity").kind(if report.is_ok() {
+        MessageDialogKind::Info
+    } else {
+        MessageDialogKind::Warning
+    });
+
+    if !report.is_repairable() {
+        dialog.show(|_| {});
+        return;
+    }
+
+    let app_clone = app.clone();
+    dialog
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Repair".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |result| {
+            if result {
+                tauri::async_runtime::spawn(async move {
+                    let pool = app_clone.state::<Pool>();
+                    let _ = api::repair_database(app_clone.clone(), pool).await;
+                });
+            }
+        });
+}
+
+fn show_error(app: &tauri::AppHandle, title: &str, err: Error) {
+    let mut dialog = app.dialog().message(err.to_string());
+    if let Some(window) = window::focused(app) {
+        dialog = dialog.parent(&window);
+    }
+    dialog
+        .title(title)
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+/// Exports to a file picked by the user for `service`, in the form it imports, and shows which
+/// works were left out.
+fn export_service(app: &tauri::AppHandle, service: Service, manga: bool) {
+    let (title, file_name, extension) = match (service, manga) {
+        (Service::MyAnimeList, false) => ("MyAnimeList Anime", "animelist", "xml"),
+        (Service::MyAnimeList, true) => ("MyAnimeList Manga", "mangalist", "xml"),
+        (Service::Goodreads, _) => ("Goodreads", "goodreads_library_export", "csv"),
+    };
+
+    let app_clone = app.clone();
+    let mut dialog = app.dialog().file();
+    if let Some(window) = window::focused(app) {
+        dialog = dialog.set_parent(&window);
+    }
+
+    dialog
+        .set_title(format!("Export {title}"))
+        .set_file_name(file_name)
+        .add_filter(title, &[extension])
+        .save_file(move |path| {
+            let Some(path) = path else {
+                return;
+            };
+            let path = path.as_path().unwrap().to_path_buf();
+            tauri::async_runtime::spawn(async move {
+                let pool = app_clone.state::<Pool>();
+                let report = match service {
+                    Service::MyAnimeList => api::export_mal(pool, path, manga).await,
+                    Service::Goodreads => api::export_goodreads(pool, path).await,
+                };
+                let report = match report {
+                    Ok(report) => report,
+                    Err(err) => return show_error(&app_clone, &format!("Export {title}"), err),
+                };
+
+                let mut dialog = app_clone.dialog().message(report.summary());
+                if let Some(window) = window::focused(&app_clone) {
+                    dialog = dialog.parent(&window);
+                }
+                let kind = if report.skipped.is_empty() {
+                    MessageDialogKind::Info
+                } else {
+                    MessageDialogKind::Warning
+                };
+                dialog
+                    .title(format!("Export {title}"))
+                    .kind(kind)
+                    .show(|_| {});
+            });
+        });
+}
+
+fn pick_import(
+    app: &tauri::AppHandle,
+    source: Source,
+    mode: ImportMode,
+    name: &str,
+    extensions: &[&str],
+) {
+    let app_clone = app.clone();
+    let mut dialog = app.dialog().file();
+    if let Some(window) = window::focused(app) {
+        dialog = dialog.set_parent(&window);
+    }
+
+    dialog
+        .set_title(format!("Import {name}"))
+        .add_filter(name, extensions)
+        .pick_file(move |path| {
+            if let Some(path) = path {
+                let path = path.as_path().unwrap().to_path_buf();
+                import_library(&app_clone, path, source, mode);
+            }
+        });
+}
+
+/// Imports into the open database, or into a new database picked by the user when none is open,
+/// after confirming what the import would do.
+fn import_library(app: &tauri::AppHandle, path: PathBuf, source: Source, mode: ImportMode) {
+    if app.state::<Pool>().path().is_some() {
+        confirm_import(app.clone(), path, source, mode, None);
+        return;
+    }
+
+    let app_clone = app.clone();
+    let mut dialog = app.dialog().file();
+    if let Some(window) = window::focused(app) {
+        dialog = dialog.set_parent(&window);
+    }
+
+    dialog
+        .set_title("New Database")
+        .set_file_name("database")
+        .add_filter("Database", &["db"])
+        .save_file(move |target| {
+            if let Some(target) = target {
+                let target = target.as_path().unwrap().to_path_buf();
+                confirm_import(app_clone, path, source, mode, Some(target));
+            }
+        });
+}
+
+/// Shows the summary of a dry run of the import and imports for real once the user agrees.
+fn confirm_import(
+    app: tauri::AppHandle,
+    path: PathBuf,
+    source: Source,
+    mode: ImportMode,
+    target: Option<PathBuf>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let report = api::import_library(
+            app.clone(),
+            app.state::<Mutex<Config>>(),
+            app.state::<Pool>(),
+            path.clone(),
+            source.clone(),
+            mode,
+            target.clone(),
+            true,
+        )
+        .await;
+        let report = match report {
+            Ok(report) => report,
+            Err(err) => return show_error(&app, &format!("Import {}", path.display()), err),
+        };
+
+        let mut dialog = app.dialog().message(report.summary());
+        if let Some(window) = window::focused(&app) {
+            dialog = dialog.parent(&window);
+        }
+
+        let app = app.clone();
+        dialog
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Import".to_string(),
+                "Cancel".to_string(),
+            ))
+            .kind(MessageDialogKind::Info)
+            .title(format!("Import {}?", path.display()))
+            .show(move |result| {
+                if result {
+                    tauri::async_runtime::spawn(async move {
+                        let _ = api::import_library(
+                            app.clone(),
+                            app.state::<Mutex<Config>>(),
+                            app.state::<Pool>(),
+                            path,
+                            source,
+                            mode,
+                            target,
+                            false,
+                        )
+                        .await;
+                    });
+                }
+            });
+    });
 }
 
 pub fn event_handler(app: &tauri::AppHandle, event: tauri::menu::MenuEvent) {
     match event.id().as_ref() {
         "new" => {
             let app_clone = app.clone();
-            let window = app.get_webview_window("main").unwrap();
+            let mut dialog = app.dialog().file();
+            if let Some(window) = window::focused(app) {
+                dialog = dialog.set_parent(&window);
+            }
 
-            app.dialog()
-                .file()
+            dialog
                 .set_title("New Database")
                 .set_file_name("database")
                 .add_filter("Database", &["db"])
                 .save_file(move |path| {
                     if let Some(path) = path {
-                        let _ = api::open_database(
-                            app_clone.clone(),
-                            window,
-                            app_clone.state::<Mutex<Config>>(),
-                            app_clone.state::<Mutex<Database>>(),
-                            path.as_path().unwrap().to_path_buf(),
-                        );
+                        open_database(&app_clone, path.as_path().unwrap().to_path_buf());
                     }
                 });
         }
         "open" => {
             let app_clone = app.clone();
-            let window = app.get_webview_window("main").unwrap();
-            app.dialog()
-                .file()
+            let mut dialog = app.dialog().file();
+            if let Some(window) = window::focused(app) {
+                dialog = dialog.set_parent(&window);
+            }
+
+            dialog
                 .set_title("Open Database")
                 .add_filter("Database", &["db"])
                 .pick_file(move |path| {
                     if let Some(path) = path {
-                        let _ = api::open_database(
-                            app_clone.clone(),
-                            window.clone(),
-                            app_clone.state::<Mutex<Config>>(),
-                            app_clone.state::<Mutex<Database>>(),
-                            path.as_path().unwrap().to_path_buf(),
-                        );
+                        open_database(&app_clone, path.as_path().unwrap().to_path_buf());
                     }
                 })
-        },
-        "close" => {
+        }
+        "clear_recent" => {
+            let config_state = app.state::<Mutex<Config>>();
+            let mut config = config_state.lock().unwrap();
+            config.clear_recent_databases();
+            if let Err(err) = window::set_recent_menus(app, &config.recent_databases) {
+                log::error!("Failed to update recent databases menu: {err}.");
+            }
+        }
+        recent if recent.starts_with("recent_") => {
+            match recent.trim_start_matches("recent_").parse() {
+                Ok(index) => open_recent(app, index),
+                Err(err) => log::error!("Invalid recent database menu event '{recent}': {err}."),
+            }
+        }
+        "restore_backup" => show_backups(app),
+        "import_library" => {
+            pick_import(app, Source::Library, ImportMode::Add, "Library", &["json"]);
+        }
+        "import_mal" => {
+            let source = Source::MyAnimeList;
+            pick_import(app, source, ImportMode::Add, "MyAnimeList Export", &["xml"]);
+        }
+        "import_goodreads" => {
+            let source = Source::Goodreads;
+            pick_import(app, source, ImportMode::Add, "Goodreads Export", &["csv"]);
+        }
+        "import_letterboxd" => {
+            let source = Source::Letterboxd;
+            pick_import(app, source, ImportMode::Add, "Letterboxd Export", &["zip"]);
+        }
+        "import_anilist" => {
+            let source = Source::AniList;
+            pick_import(app, source, ImportMode::Sync, "AniList Export", &["json"]);
+        }
+        "import_trakt" => {
+            pick_import(
+                app,
+                Source::Trakt,
+                ImportMode::Sync,
+                "Trakt Export",
+                &["zip"],
+            );
+        }
+        "import_tachiyomi" => {
             let app_clone = app.clone();
-            let window = app.get_webview_window("main").unwrap();
-            let _ = api::close_database(
-                app_clone,
-                window.clone(),
-                app.state::<Mutex<Config>>(),
-                app.state::<Mutex<Database>>(),
+            let mut dialog = app
+                .dialog()
+                .message("Should Mihon categories become tags or statuses of the imported manga?");
+            if let Some(window) = window::focused(app) {
+                dialog = dialog.parent(&window);
+            }
+
+            let (tags, statuses) = ("Tags".to_string(), "Statuses".to_string());
+            dialog
+                .buttons(MessageDialogButtons::YesNoCancelCustom(
+                    tags.clone(),
+                    statuses.clone(),
+                    "Cancel".to_string(),
+                ))
+                .kind(MessageDialogKind::Info)
+                .title("Import Mihon Backup")
+                .show_with_result(move |result| {
+                    // Escape and closing the dialog cancel, only an explicit choice imports.
+                    let categories = match result {
+                        MessageDialogResult::Custom(label) if label == tags => Categories::Tags,
+                        MessageDialogResult::Custom(label) if label == statuses => {
+                            Categories::Statuses
+                        }
+                        _ => return,
+                    };
+                    let source = Source::Tachiyomi { categories };
+                    pick_import(
+                        &app_clone,
+                        source,
+                        ImportMode::Add,
+                        "Mihon Backup",
+                        &["tachibk"],
+                    );
+                });
+        }
+        "import_tachiyomi_progress" => {
+            let source = Source::Tachiyomi {
+                categories: Categories::Tags,
+            };
+            pick_import(
+                app,
+                source,
+                ImportMode::Progress,
+                "Mihon Backup",
+                &["tachibk"],
             );
         }
+        "export_library" => {
+            let app_clone = app.clone();
+            let mut dialog = app.dialog().file();
+            if let Some(window) = window::focused(app) {
+                dialog = dialog.set_parent(&window);
+            }
+
+            dialog
+                .set_title("Export Library")
+                .set_file_name("library")
+                .add_filter("Library", &["json"])
+                .save_file(move |path| {
+                    if let Some(path) = path {
+                        let path = path.as_path().unwrap().to_path_buf();
+                        tauri::async_runtime::spawn(async move {
+                            let pool = app_clone.state::<Pool>();
+                            let _ = api::export_library(pool, path).await;
+                        });
+                    }
+                });
+        }
+        "export_csv" => {
+            let app_clone = app.clone();
+            let mut dialog = app.dialog().file();
+            if let Some(window) = window::focused(app) {
+                dialog = dialog.set_parent(&window);
+            }
+
+            dialog
+                .set_title("Export Works")
+                .set_file_name("works")
+                .add_filter("CSV", &["csv"])
+                .save_file(move |path| {
+                    if let Some(path) = path {
+                        let path = path.as_path().unwrap().to_path_buf();
+                        tauri::async_runtime::spawn(async move {
+                            let pool = app_clone.state::<Pool>();
+                            let _ = api::export_csv(pool, path).await;
+                        });
+                    }
+                });
+        }
+        "export_mal_anime" => export_service(app, Service::MyAnimeList, false),
+        "export_mal_manga" => export_service(app, Service::MyAnimeList, true),
+        "export_goodreads" => export_service(app, Service::Goodreads, false),
+        "check_integrity" => check_integrity(app),
+        "close" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let (config, pool) = (app.state::<Mutex<Config>>(), app.state::<Pool>());
+                let _ = api::close_database(app.clone(), config, pool).await;
+            });
+        }
+        "close_window" => {
+            if let Some(window) = window::focused(app) {
+                if let Err(err) = window.close() {
+                    log::error!("Failed to close window '{}': {err}.", window.label());
+                }
+            }
+        }
         "exit" => {
-            app.get_webview_window("main").unwrap().close().unwrap();
+            if let Some(window) = app.get_webview_window(window::MAIN_WINDOW) {
+                if let Err(err) = window.close() {
+                    log::error!("Failed to close main window: {err}.");
+                }
+            }
         }
         "repository" => {
-            if let Err(err) = app.opener().open_url("https://github.com/NikolaRoev/zero", None::<&str>) {
+            if let Err(err) = app
+                .opener()
+                .open_url("https://github.com/NikolaRoev/zero", None::<&str>)
+            {
                 log::error!("Failed to open repository: {err}.");
             }
         }
-        "dev_tools" => app.get_webview_window("main").unwrap().open_devtools(),
+        "dev_tools" => {
+            if let Some(window) = window::focused(app) {
+                window.open_devtools();
+            }
+        }
         "about" => {
             let message = format!(
                 "Version: {}\nTauri: {}\nWebView/WebKit: {}",
@@ -147,7 +658,8 @@ pub fn event_handler(app: &tauri::AppHandle, event: tauri::menu::MenuEvent) {
             );
 
             let app_clone = app.clone();
-            app.dialog().message(&message)
+            app.dialog()
+                .message(&message)
                 .buttons(MessageDialogButtons::OkCancelCustom(
                     "Copy".to_string(),
                     "Ok".to_string(),
@@ -0,0 +1,338 @@
+use crate::database::{self, Database, DatabaseResult};
+use crate::error::Error;
+use crate::restore;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+const READERS: usize = 4;
+
+#[derive(Default)]
+struct Connections {
+    writer: Mutex<Database>,
+    readers: [Mutex<Database>; READERS],
+    next: AtomicUsize,
+}
+
+/// One connection for writing and a few read only ones. With WAL journaling reads don't wait
+/// for writes or for each other, only writes are serialized. Clones share the same connections.
+#[derive(Clone, Default)]
+pub struct Pool(Arc<Connections>);
+
+impl Pool {
+    pub fn open(
+        &self,
+        path: &PathBuf,
+        backup: Option<&Path>,
+        key: Option<&str>,
+    ) -> DatabaseResult<()> {
+        let mut writer = self.0.writer.lock().unwrap();
+        let mut readers = self.lock_readers();
+
+        Self::open_all(&mut writer, &mut readers, path, backup, key)
+    }
+
+    pub fn close(&self) -> DatabaseResult<()> {
+        let mut writer = self.0.writer.lock().unwrap();
+        let mut readers = self.lock_readers();
+
+        Self::close_all(&mut writer, &mut readers)
+    }
+
+    /// Swaps the open database for a copy of `backup`, backing the replaced one up to `previous`
+    /// first. Every connection stays locked throughout so no command sees a half restored file.
+    /// The copy is rekeyed from `backup_key` to the current key, so a backup taken under an older
+    /// password restores under the current one. If that fails `previous` is put back. The
+    /// connections are opened again either way, on whatever file is there by then.
+    pub fn restore(
+        &self,
+        backup: &Path,
+        previous: &Path,
+        backup_key: Option<&str>,
+    ) -> DatabaseResult<()> {
+        let mut writer = self.0.writer.lock().unwrap();
+        let mut readers = self.lock_readers();
+        let path = writer.path().ok_or(Error::NoDatabaseOpen)?;
+        let key = writer.key().map(str::to_owned);
+
+        // Nothing is closed yet, a backup that can't be read leaves the database as it is.
+        restore::rows(backup, backup_key)?;
+        writer.backup(previous)?;
+        Self::close_all(&mut writer, &mut readers)?;
+        let replaced = restore::replace(&path, backup).and_then(|()| {
+            if backup_key == key.as_deref() {
+                return Ok(());
+            }
+            database::rekey(&path, backup_key, key.as_deref())
+        });
+        let rolled_back = match replaced {
+            Ok(()) => Ok(()),
+            Err(_) => restore::replace(&path, previous),
+        };
+        let opened = Self::open_all(&mut writer, &mut readers, &path, None, key.as_deref());
+        match (replaced, rolled_back) {
+            (Err(error), Err(rollback)) => Err(Error::RollbackFailed {
+                error: Box::new(error),
+                rollback: Box::new(rollback),
+            }),
+            (replaced, _) => replaced.and(opened),
+        }
+    }
+
+    /// Changes the key of the open database, encrypting or decrypting it when the current or the
+    /// new one is missing. Every connection stays locked throughout like in [`Pool::restore`].
+    pub fn set_key(&self, key: Option<&str>) -> DatabaseResult<()> {
+        let mut writer = self.0.writer.lock().unwrap();
+        let mut readers = self.lock_readers();
+        let path = writer.path().ok_or(Error::NoDatabaseOpen)?;
+        let old = writer.key().map(str::to_owned);
+
+        Self::close_all(&mut writer, &mut readers)?;
+        let rekeyed = database::rekey(&path, old.as_deref(), key);
+        let key = if rekeyed.is_ok() { key } else { old.as_deref() };
+        Self::open_all(&mut writer, &mut readers, &path, None, key)?;
+        rekeyed
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        self.0.writer.lock().unwrap().path()
+    }
+
+    pub fn key(&self) -> Option<String> {
+        self.0.writer.lock().unwrap().key().map(str::to_owned)
+    }
+
+    /// Runs `f` on the first idle reader, or waits for one if all of them are busy.
+    pub fn with_reader<T>(
+        &self,
+        f: impl FnOnce(&Database) -> DatabaseResult<T>,
+    ) -> DatabaseResult<T> {
+        let readers = &self.0.readers;
+        let start = self.0.next.fetch_add(1, Ordering::Relaxed);
+        let reader = (0..READERS)
+            .find_map(|offset| readers[(start + offset) % READERS].try_lock().ok())
+            .unwrap_or_else(|| readers[start % READERS].lock().unwrap());
+
+        f(&reader)
+    }
+
+    pub fn with_writer<T>(
+        &self,
+        f: impl FnOnce(&Database) -> DatabaseResult<T>,
+    ) -> DatabaseResult<T> {
+        f(&self.0.writer.lock().unwrap())
+    }
+
+    /// Same as [`Pool::with_reader`] but on a blocking thread, so async commands don't hold up
+    /// the runtime.
+    pub async fn read<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Database) -> DatabaseResult<T> + Send + 'static,
+    ) -> DatabaseResult<T> {
+        let pool = self.clone();
+        tauri::async_runtime::spawn_blocking(move || pool.with_reader(f)).await?
+    }
+
+    /// Same as [`Pool::with_writer`] but on a blocking thread.
+    pub async fn write<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Database) -> DatabaseResult<T> + Send + 'static,
+    ) -> DatabaseResult<T> {
+        let pool = self.clone();
+        tauri::async_runtime::spawn_blocking(move || pool.with_writer(f)).await?
+    }
+
+    fn lock_readers(&self) -> Vec<std::sync::MutexGuard<'_, Database>> {
+        self.0
+            .readers
+            .iter()
+            .map(|reader| reader.lock().unwrap())
+            .collect()
+    }
+
+    fn open_all(
+        writer: &mut Database,
+        readers: &mut [std::sync::MutexGuard<'_, Database>],
+        path: &PathBuf,
+        backup: Option<&Path>,
+        key: Option<&str>,
+    ) -> DatabaseResult<()> {
+        writer.open(path, backup, key)?;
+        let opened = readers
+            .iter_mut()
+            .try_for_each(|reader| reader.open_reader(path, key));
+        if opened.is_err() {
+            let _ = Self::close_all(writer, readers);
+        }
+        opened
+    }
+
+    fn close_all(
+        writer: &mut Database,
+        readers: &mut [std::sync::MutexGuard<'_, Database>],
+    ) -> DatabaseResult<()> {
+        readers
+            .iter_mut()
+            .filter(|reader| reader.path().is_some())
+            .for_each(|reader| {
+                if let Err(err) = reader.close() {
+                    log::error!("Failed to close reader: {err}.");
+                }
+            });
+        writer.close()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TempFolder;
+    use crate::entity::{NewFormat, NewStatus, NewType, NewWork, WorkColumn};
+    use crate::store::LibraryStore;
+    use std::time::{Duration, Instant};
+
+    struct Context {
+        pool: Pool,
+        // Dropped after the pool is closed.
+        _folder: TempFolder,
+    }
+
+    impl Context {
+        pub fn new() -> Self {
+            let folder = TempFolder::new();
+            let pool = Pool::default();
+            pool.open(&folder.join("library.db"), None, None).unwrap();
+            Context {
+                pool,
+                _folder: folder,
+            }
+        }
+    }
+
+    impl Drop for Context {
+        fn drop(&mut self) {
+            self.pool.close().unwrap();
+        }
+    }
+
+    fn add_works(database: &Database, count: usize) -> DatabaseResult<()> {
+        database.transaction(|db| {
+            let status = db.add(&NewStatus {
+                name: "status".into(),
+                ..Default::default()
+            })?;
+            let r#type = db.add(&NewType {
+                name: "type".into(),
+            })?;
+            let format = db.add(&NewFormat {
+                name: "format".into(),
+            })?;
+            (0..count).try_for_each(|index| {
+                db.add(&NewWork {
+                    name: format!("work {index}"),
+                    progress: index.to_string(),
+                    status,
+                    r#type,
+                    format,
+                    updated: 0,
+                    added: 0,
+                })
+                .map(|_| ())
+            })
+        })
+    }
+
+    #[test]
+    fn readers_see_committed_writes() -> Result<(), Box<dyn std::error::Error>> {
+        let context = Context::new();
+
+        context.pool.with_writer(|db| add_works(db, 3))?;
+        let works = context.pool.with_reader(|db| db.get_works())?;
+
+        assert_eq!(works.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn readers_cannot_write() -> Result<(), Box<dyn std::error::Error>> {
+        let context = Context::new();
+
+        let result = context.pool.with_reader(|db| {
+            db.add(&NewType {
+                name: "type".into(),
+            })
+        });
+
+        assert!(matches!(result, Err(Error::Database(_))));
+        assert_eq!(context.pool.with_reader(|db| db.get_types())?.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn closed_pool_has_no_connections() {
+        let pool = Pool::default();
+
+        assert!(matches!(
+            pool.with_reader(|db| db.get_works()),
+            Err(Error::NoDatabaseOpen)
+        ));
+        assert!(matches!(
+            pool.with_writer(|db| db.get_works()),
+            Err(Error::NoDatabaseOpen)
+        ));
+        assert!(matches!(pool.close(), Err(Error::NoDatabaseOpen)));
+    }
+
+    /// How long the thread issuing an edit command is blocked while the list of a 50k work
+    /// library loads in the background, first with every command on one connection and then with
+    /// the pool. Run with `cargo test --release -- --ignored edit_latency`.
+    #[test]
+    #[ignore]
+    fn edit_latency_during_reads() -> Result<(), Box<dyn std::error::Error>> {
+        use tauri::async_runtime::block_on;
+
+        let context = Context::new();
+        let pool = &context.pool;
+        pool.with_writer(|db| add_works(db, 50_000))?;
+        let work_id = pool.with_writer(|db| db.get_works())?.first().unwrap().id;
+
+        // The longest the calling thread waits for an edit while the works are read 10 times.
+        let blocked = |single_connection: bool| {
+            std::thread::scope(|scope| {
+                let reads = scope.spawn(|| {
+                    for _ in 0..10 {
+                        let works = if single_connection {
+                            block_on(pool.write(|db| db.get_works()))
+                        } else {
+                            block_on(pool.read(|db| db.get_works()))
+                        };
+                        works.unwrap();
+                    }
+                });
+                let mut worst = Duration::ZERO;
+                while !reads.is_finished() {
+                    let start = Instant::now();
+                    block_on(pool.write(move |db| {
+                        db.update(work_id, &[WorkColumn::Progress("edited".into())])
+                    }))
+                    .unwrap();
+                    worst = worst.max(start.elapsed());
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+                worst
+            })
+        };
+
+        let before = blocked(true);
+        let after = blocked(false);
+
+        assert!(
+            after * 4 < before,
+            "Edits blocked for {after:?} with the pool, {before:?} with one connection."
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use crate::database::{self, DatabaseResult};
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+
+#[derive(serde::Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RowCounts {
+    pub works: i64,
+    pub creators: i64,
+    pub statuses: i64,
+    pub types: i64,
+    pub formats: i64,
+    pub tags: i64,
+}
+
+/// What restoring a backup would do to the current database. `added` are works only the backup
+/// has, `removed` works only the current database has and `changed` works that differ.
+#[derive(serde::Serialize, Debug)]
+pub struct RestorePreview {
+    pub backup: RowCounts,
+    pub current: RowCounts,
+    pub added: Vec<i64>,
+    pub removed: Vec<i64>,
+    pub changed: Vec<i64>,
+}
+
+fn count(conn: &rusqlite::Connection, schema: &str, table: &str) -> DatabaseResult<i64> {
+    // Backups taken before a migration may not have every table yet.
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {schema}.sqlite_master WHERE name = ?)"),
+        [table],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(0);
+    }
+
+    let query = format!("SELECT count(*) FROM {schema}.{table}");
+    Ok(conn.query_row(&query, [], |row| row.get(0))?)
+}
+
+fn counts(conn: &rusqlite::Connection, schema: &str) -> DatabaseResult<RowCounts> {
+    Ok(RowCounts {
+        works: count(conn, schema, "works")?,
+        creators: count(conn, schema, "creators")?,
+        statuses: count(conn, schema, "statuses")?,
+        types: count(conn, schema, "types")?,
+        formats: count(conn, schema, "formats")?,
+        tags: count(conn, schema, "tags")?,
+    })
+}
+
+fn ids(conn: &rusqlite::Connection, query: &str) -> DatabaseResult<Vec<i64>> {
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.map(|row| Ok(row?)).collect()
+}
+
+/// The key to open `backup` with: `password` when given, otherwise the `current` key of the
+/// database if the backup is encrypted at all.
+pub fn backup_key<'a>(
+    backup: &Path,
+    current: Option<&'a str>,
+    password: Option<&'a str>,
+) -> DatabaseResult<Option<&'a str>> {
+    match password {
+        Some(password) => Ok(Some(password)),
+        None if database::is_encrypted(backup)? => Ok(current),
+        None => Ok(None),
+    }
+}
+
+fn open_backup(backup: &Path, key: Option<&str>) -> DatabaseResult<rusqlite::Connection> {
+    let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY;
+    let conn = rusqlite::Connection::open_with_flags(backup, flags)?;
+    database::unlock(&conn, backup, key).map_err(|err| match err {
+        Error::WrongKey => err,
+        err => Error::Invalid(format!("Not a valid backup: {err}")),
+    })?;
+    Ok(conn)
+}
+
+/// Rows in the backup at `backup`, encrypted with `key` if given.
+pub fn rows(backup: &Path, key: Option<&str>) -> DatabaseResult<RowCounts> {
+    counts(&open_backup(backup, key)?, "main")
+}
+
+/// Compares the backup at `backup`, encrypted with `backup_key` if given, against the database
+/// at `current`, encrypted with `key` if given, without modifying either.
+pub fn preview(
+    current: &Path,
+    backup: &Path,
+    key: Option<&str>,
+    backup_key: Option<&str>,
+) -> DatabaseResult<RestorePreview> {
+    let conn = open_backup(backup, backup_key)?;
+    conn.execute(
+        "ATTACH DATABASE ? AS current KEY ?",
+        [current.to_string_lossy().as_ref(), key.unwrap_or_default()],
+    )?;
+
+    Ok(RestorePreview {
+        backup: counts(&conn, "main")?,
+        current: counts(&conn, "current")?,
+        added: ids(
+            &conn,
+            "
+            SELECT id FROM main.works WHERE id NOT IN (SELECT id FROM current.works) ORDER BY id
+        ",
+        )?,
+        removed: ids(
+            &conn,
+            "
+            SELECT id FROM current.works WHERE id NOT IN (SELECT id FROM main.works) ORDER BY id
+        ",
+        )?,
+        changed: ids(
+            &conn,
+            "
+            SELECT backup.id FROM main.works AS backup
+            JOIN current.works AS current ON backup.id = current.id
+            WHERE backup.name     IS NOT current.name
+               OR backup.progress IS NOT current.progress
+               OR backup.status   IS NOT current.status
+               OR backup.type     IS NOT current.type
+               OR backup.format   IS NOT current.format
+               OR backup.updated  IS NOT current.updated
+            ORDER BY backup.id
+        ",
+        )?,
+    })
+}
+
+/// Overwrites the closed database at `current` with a copy of `backup`. The copy is written next
+/// to it first and then renamed over it, so a failure midway leaves `current` untouched.
+pub fn replace(current: &Path, backup: &Path) -> DatabaseResult<()> {
+    let restoring = current.with_extension("restoring.db");
+    std::fs::copy(backup, &restoring)?;
+
+    // Left over journal files belong to the old database and would be applied to the new one.
+    for suffix in ["-wal", "-shm"] {
+        let mut journal = PathBuf::from(current).into_os_string();
+        journal.push(suffix);
+        let journal = PathBuf::from(journal);
+        if journal.try_exists()? {
+            std::fs::remove_file(journal)?;
+        }
+    }
+
+    Ok(std::fs::rename(restoring, current)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TempFolder;
+    use crate::entity::{NewFormat, NewStatus, NewType, NewWork, Table, WorkColumn};
+    use crate::pool::Pool;
+    use crate::store::LibraryStore;
+
+    fn new_work(name: &str, status: i64, r#type: i64, format: i64) -> NewWork {
+        NewWork {
+            name: name.to_string(),
+            progress: "0".into(),
+            status,
+            r#type,
+            format,
+            updated: 0,
+            added: 0,
+        }
+    }
+
+    #[test]
+    fn previews_and_restores_backup() -> Result<(), Box<dyn std::error::Error>> {
+        let folder = TempFolder::new();
+        let current = folder.join("library.db");
+        let backup = folder.join("library.backup.db");
+        let previous = folder.join("library.previous.db");
+
+        let pool = Pool::default();
+        pool.open(&current, None, None)?;
+        let (work_ids, new_id) = pool.with_writer(|db| {
+            let status = db.add(&NewStatus {
+                name: "status".into(),
+                ..Default::default()
+            })?;
+            let r#type = db.add(&NewType {
+                name: "type".into(),
+            })?;
+            let format = db.add(&NewFormat {
+                name: "format".into(),
+            })?;
+            let work_ids = (0..3)
+                .map(|index| db.add(&new_work(&format!("work {index}"), status, r#type, format)))
+                .collect::<DatabaseResult<Vec<_>>>()?;
+            db.backup(&backup)?;
+
+            db.update(work_ids[0], &[WorkColumn::Progress("1".into())])?;
+            db.remove(Table::Works, work_ids[1])?;
+            let new_id = db.add(&new_work("new", status, r#type, format))?;
+            Ok((work_ids, new_id))
+        })?;
+
+        let diff = preview(&current, &backup, None, None)?;
+
+        assert_eq!(diff.backup.works, 3);
+        assert_eq!(diff.current.works, 3);
+        assert_eq!(diff.added, vec![work_ids[1]]);
+        assert_eq!(diff.removed, vec![new_id]);
+        assert_eq!(diff.changed, vec![work_ids[0]]);
+
+        pool.restore(&backup, &previous, None)?;
+
+        let works = pool.with_reader(|db| db.get_works())?;
+        let works = works.into_iter().map(|work| work.id).collect::<Vec<_>>();
+        let replaced = preview(&current, &previous, None, None)?;
+        pool.close()?;
+
+        assert_eq!(works, work_ids);
+        assert_eq!(replaced.added, vec![new_id]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn restores_backup_taken_under_older_password() -> Result<(), Box<dyn std::error::Error>> {
+        let folder = TempFolder::new();
+        let current = folder.join("library.db");
+        let backup = folder.join("library.backup.db");
+        let previous = folder.join("library.previous.db");
+
+        let pool = Pool::default();
+        pool.open(&current, None, Some("old"))?;
+        pool.with_writer(|db| {
+            db.add(&NewStatus {
+                name: "status".into(),
+                ..Default::default()
+            })?;
+            db.backup(&backup)
+        })?;
+        pool.set_key(Some("new"))?;
+
+        assert!(matches!(rows(&backup, Some("new")), Err(Error::WrongKey)));
+        assert!(matches!(
+            pool.restore(&backup, &previous, Some("wrong")),
+            Err(Error::WrongKey)
+        ));
+        assert!(!previous.try_exists()?);
+
+        let key = backup_key(&backup, Some("new"), Some("old"))?;
+        assert_eq!(
+            preview(&current, &backup, Some("new"), key)?
+                .backup
+                .statuses,
+            1
+        );
+        pool.restore(&backup, &previous, key)?;
+
+        assert_eq!(pool.key().as_deref(), Some("new"));
+        assert_eq!(pool.with_reader(|db| db.get_statuses())?.len(), 1);
+        pool.close()?;
+
+        Ok(())
+    }
+}
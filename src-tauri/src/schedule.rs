@@ -0,0 +1,361 @@
+use chrono::{Datelike, Days, Duration, LocalResult, NaiveDateTime, TimeZone};
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+/// Most releases [`Recurrence::between`] lists, a daily schedule over almost three years.
+pub const MAX_RELEASES: usize = 1000;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Recurrence {
+    /// Every week on `weekday` (0 is Monday), starting from the first such day at or after `start`.
+    Weekly { weekday: u32, start: i64 },
+    /// Every `days` days starting at `start`.
+    Interval { days: i64, start: i64 },
+    /// An explicit list of release timestamps.
+    Dates { dates: Vec<i64> },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseSchedule {
+    pub work_id: i64,
+    #[serde(flatten)]
+    pub recurrence: Recurrence,
+    /// Number of releases that came out before the first scheduled one.
+    #[serde(default)]
+    pub offset: i64,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleState {
+    #[serde(flatten)]
+    pub schedule: ReleaseSchedule,
+    pub next_release: Option<i64>,
+    pub released: i64,
+    pub behind: bool,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Release {
+    pub work_id: i64,
+    pub date: i64,
+    pub number: i64,
+}
+
+impl Recurrence {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Recurrence::Weekly { weekday, .. } if *weekday > 6 => {
+                Err(format!("Invalid weekday {weekday}"))
+            }
+            Recurrence::Interval { days, .. } if *days < 1 => {
+                Err(format!("Invalid interval of {days} days"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Local time of the first release and the days between releases for recurrences that
+    /// repeat. Releases keep their local time of day across daylight saving changes.
+    fn periodic(&self) -> Option<(NaiveDateTime, i64)> {
+        let local = |start: i64| chrono::Local.timestamp_millis_opt(start).earliest();
+        match self {
+            Recurrence::Weekly { weekday, start } => {
+                let start = local(*start)?;
+                let current = start.weekday().num_days_from_monday();
+                let days = (i64::from(*weekday) - i64::from(current)).rem_euclid(7);
+                Some((
+                    start
+                        .naive_local()
+                        .checked_add_days(Days::new(days as u64))?,
+                    7,
+                ))
+            }
+            Recurrence::Interval { days, start } => Some((local(*start)?.naive_local(), *days)),
+            Recurrence::Dates { .. } => None,
+        }
+    }
+
+    /// Release number `index`, counted from 0, of a periodic recurrence. `None` when it is too
+    /// far off to be represented.
+    fn release(first: NaiveDateTime, days: i64, index: i64) -> Option<i64> {
+        let offset = u64::try_from(index.checked_mul(days)?).ok()?;
+        let date = first.checked_add_days(Days::new(offset))?;
+        let date = match chrono::Local.from_local_datetime(&date) {
+            LocalResult::Single(date) | LocalResult::Ambiguous(date, _) => date,
+            // The clocks skipped over the time, release when it would have been.
+            LocalResult::None => chrono::Local
+                .from_local_datetime(&(date + Duration::hours(1)))
+                .earliest()?,
+        };
+        Some(date.timestamp_millis())
+    }
+
+    /// Number of releases at or before `timestamp`.
+    pub fn released_by(&self, timestamp: i64) -> i64 {
+        match self.periodic() {
+            Some((first, days)) => {
+                let before =
+                    |index| Self::release(first, days, index).is_some_and(|date| date <= timestamp);
+                let Some(start) = Self::release(first, days, 0).filter(|start| *start <= timestamp)
+                else {
+                    return 0;
+                };
+                // Local days last 23 to 25 hours, so the release after the estimate is the last
+                // one that can have come out. The first that didn't is searched for below it.
+                let estimate = timestamp.saturating_sub(start) / days.saturating_mul(DAY) + 1;
+                let (mut low, mut high) = (1, estimate.saturating_add(1));
+                while low < high {
+                    let middle = low + (high - low) / 2;
+                    if before(middle) {
+                        low = middle + 1;
+                    } else {
+                        high = middle;
+                    }
+                }
+                low
+            }
+            None => match self {
+                Recurrence::Dates { dates } => {
+                    dates.iter().filter(|&&date| date <= timestamp).count() as i64
+                }
+                _ => 0,
+            },
+        }
+    }
+
+    /// First release strictly after `timestamp`.
+    pub fn next_after(&self, timestamp: i64) -> Option<i64> {
+        match self.periodic() {
+            Some((first, days)) => Self::release(first, days, self.released_by(timestamp)),
+            None => match self {
+                Recurrence::Dates { dates } => dates
+                    .iter()
+                    .filter(|&&date| date > timestamp)
+                    .min()
+                    .copied(),
+                _ => None,
+            },
+        }
+    }
+
+    /// Releases in `[from, to)` paired with their index counted from the first release. Only the
+    /// first [`MAX_RELEASES`] are listed, so a far off `to` doesn't step through every day until
+    /// the dates overflow.
+    pub fn between(&self, from: i64, to: i64) -> Vec<(i64, i64)> {
+        match self.periodic() {
+            Some((first, days)) => {
+                let mut index = self.released_by(from.saturating_sub(1));
+                let mut releases = vec![];
+                while releases.len() < MAX_RELEASES {
+                    let Some(date) = Self::release(first, days, index).filter(|date| *date < to)
+                    else {
+                        break;
+                    };
+                    index += 1;
+                    releases.push((date, index));
+                }
+                releases
+            }
+            None => match self {
+                Recurrence::Dates { dates } => {
+                    let mut dates = dates.clone();
+                    dates.sort_unstable();
+                    dates
+                        .into_iter()
+                        .enumerate()
+                        .filter(|&(_, date)| date >= from && date < to)
+                        .map(|(index, date)| (date, index as i64 + 1))
+                        .take(MAX_RELEASES)
+                        .collect()
+                }
+                _ => vec![],
+            },
+        }
+    }
+}
+
+impl ReleaseSchedule {
+    pub fn state(self, progress: &str, timestamp: i64) -> ScheduleState {
+        let released = self.offset + self.recurrence.released_by(timestamp);
+        let behind = parse_progress(progress).is_some_and(|progress| (released as f64) > progress);
+
+        ScheduleState {
+            next_release: self.recurrence.next_after(timestamp),
+            released,
+            behind,
+            schedule: self,
+        }
+    }
+
+    pub fn releases(&self, from: i64, to: i64) -> Vec<Release> {
+        self.recurrence
+            .between(from, to)
+            .into_iter()
+            .map(|(date, index)| Release {
+                work_id: self.work_id,
+                date,
+                number: self.offset + index,
+            })
+            .collect()
+    }
+}
+
+/// Extracts the first number out of a progress string like "12", "Ch. 45" or "3/12".
+pub fn parse_progress(progress: &str) -> Option<f64> {
+    let start = progress.find(|c: char| c.is_ascii_digit())?;
+    let number: String = progress[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.trim_end_matches('.').parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(year: i32, month: u32, day: u32) -> i64 {
+        chrono::Local
+            .with_ymd_and_hms(year, month, day, 12, 0, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn can_parse_progress() {
+        assert_eq!(parse_progress("12"), Some(12.0));
+        assert_eq!(parse_progress("Ch. 45"), Some(45.0));
+        assert_eq!(parse_progress("10.5/20"), Some(10.5));
+        assert_eq!(parse_progress("Vol. 3."), Some(3.0));
+        assert_eq!(parse_progress("none"), None);
+    }
+
+    #[test]
+    fn weekly_starts_on_weekday() {
+        // 2024-01-01 is a Monday.
+        let recurrence = Recurrence::Weekly {
+            weekday: 2,
+            start: local(2024, 1, 1),
+        };
+
+        assert_eq!(recurrence.released_by(local(2024, 1, 2)), 0);
+        assert_eq!(recurrence.released_by(local(2024, 1, 3)), 1);
+        assert_eq!(recurrence.released_by(local(2024, 1, 17)), 3);
+        assert_eq!(
+            recurrence.next_after(local(2024, 1, 3)),
+            Some(local(2024, 1, 10))
+        );
+        assert_eq!(
+            recurrence.between(local(2024, 1, 4), local(2024, 1, 18)),
+            vec![(local(2024, 1, 10), 2), (local(2024, 1, 17), 3)]
+        );
+    }
+
+    #[test]
+    fn weekly_keeps_local_time_of_day() {
+        let start = chrono::Local
+            .with_ymd_and_hms(2024, 1, 1, 20, 30, 0)
+            .unwrap();
+        let recurrence = Recurrence::Weekly {
+            weekday: 0,
+            start: start.timestamp_millis(),
+        };
+        let end = local(2025, 1, 1);
+
+        let releases = recurrence.between(start.timestamp_millis(), end);
+
+        assert_eq!(releases.len(), 53);
+        for (index, (date, number)) in releases.into_iter().enumerate() {
+            let date = chrono::Local.timestamp_millis_opt(date).unwrap();
+            assert_eq!(
+                date.naive_local(),
+                start.naive_local() + Duration::weeks(index as i64)
+            );
+            assert_eq!(recurrence.released_by(date.timestamp_millis()), number);
+            assert_eq!(
+                recurrence.released_by(date.timestamp_millis() - 1),
+                number - 1
+            );
+        }
+        assert_eq!(recurrence.released_by(end), 53);
+        assert_eq!(
+            recurrence
+                .between(i64::MIN, start.timestamp_millis() + 1)
+                .len(),
+            1
+        );
+        assert_eq!(
+            recurrence.released_by(i64::MAX),
+            recurrence.released_by(i64::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn interval_repeats_every_n_days() {
+        let recurrence = Recurrence::Interval { days: 3, start: 0 };
+
+        assert_eq!(recurrence.released_by(-1), 0);
+        assert_eq!(recurrence.released_by(0), 1);
+        assert_eq!(recurrence.released_by(7 * DAY), 3);
+        assert_eq!(recurrence.next_after(0), Some(3 * DAY));
+        assert_eq!(recurrence.between(0, 6 * DAY), vec![(0, 1), (3 * DAY, 2)]);
+    }
+
+    #[test]
+    fn caps_releases_between() {
+        let recurrence = Recurrence::Interval { days: 1, start: 0 };
+
+        let releases = recurrence.between(0, i64::MAX);
+
+        assert_eq!(releases.len(), MAX_RELEASES);
+        assert_eq!(releases.last(), Some(&(999 * DAY, 1000)));
+    }
+
+    #[test]
+    fn dates_are_counted_in_order() {
+        let recurrence = Recurrence::Dates {
+            dates: vec![30, 10, 20],
+        };
+
+        assert_eq!(recurrence.released_by(20), 2);
+        assert_eq!(recurrence.next_after(20), Some(30));
+        assert_eq!(recurrence.next_after(30), None);
+        assert_eq!(recurrence.between(15, 31), vec![(20, 2), (30, 3)]);
+    }
+
+    #[test]
+    fn marks_behind_when_releases_outpace_progress() {
+        let schedule = ReleaseSchedule {
+            work_id: 1,
+            recurrence: Recurrence::Interval { days: 1, start: 0 },
+            offset: 10,
+        };
+
+        let state = schedule.clone().state("Ch. 12", DAY);
+        assert_eq!(state.released, 12);
+        assert!(!state.behind);
+
+        let state = schedule.clone().state("Ch. 12", 2 * DAY);
+        assert_eq!(state.released, 13);
+        assert!(state.behind);
+
+        assert!(!schedule.state("unknown", 2 * DAY).behind);
+    }
+
+    #[test]
+    fn catches_invalid_recurrence() {
+        assert!(Recurrence::Weekly {
+            weekday: 7,
+            start: 0
+        }
+        .validate()
+        .is_err());
+        assert!(Recurrence::Interval { days: 0, start: 0 }
+            .validate()
+            .is_err());
+        assert!(Recurrence::Dates { dates: vec![] }.validate().is_ok());
+    }
+}
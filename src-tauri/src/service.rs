@@ -0,0 +1,262 @@
+use crate::database::{DatabaseResult, ExportMapping, Mapped};
+use crate::store::LibraryStore;
+use std::collections::HashMap;
+
+const MAL_STATUSES: [&str; 7] = [
+    "Watching",
+    "Reading",
+    "Completed",
+    "On-Hold",
+    "Dropped",
+    "Plan to Watch",
+    "Plan to Read",
+];
+pub const MAL_ANIME_TYPES: [&str; 6] = ["TV", "OVA", "Movie", "Special", "ONA", "Music"];
+const MAL_TYPES: [&str; 13] = [
+    "TV",
+    "OVA",
+    "Movie",
+    "Special",
+    "ONA",
+    "Music",
+    "Manga",
+    "Light Novel",
+    "Novel",
+    "One-shot",
+    "Doujinshi",
+    "Manhwa",
+    "Manhua",
+];
+const GOODREADS_STATUSES: [&str; 3] = ["read", "currently-reading", "to-read"];
+const GOODREADS_TYPES: [&str; 1] = ["Book"];
+/// Names the importers give statuses and what they are called on Goodreads.
+const GOODREADS_ALIASES: [(&str, &str); 5] = [
+    ("Read", "read"),
+    ("Completed", "read"),
+    ("Reading", "currently-reading"),
+    ("To Read", "to-read"),
+    ("Plan to Read", "to-read"),
+];
+
+/// Other services the library can be exported to.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Service {
+    MyAnimeList,
+    Goodreads,
+}
+
+impl Service {
+    /// What the mapping of the service is stored under.
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::MyAnimeList => "myAnimeList",
+            Service::Goodreads => "goodreads",
+        }
+    }
+
+    pub fn statuses(self) -> &'static [&'static str] {
+        match self {
+            Service::MyAnimeList => &MAL_STATUSES,
+            Service::Goodreads => &GOODREADS_STATUSES,
+        }
+    }
+
+    pub fn types(self) -> &'static [&'static str] {
+        match self {
+            Service::MyAnimeList => &MAL_TYPES,
+            Service::Goodreads => &GOODREADS_TYPES,
+        }
+    }
+
+    /// The value of `values` named like `name`, ignoring case, or the one the service calls it.
+    fn guess(self, values: &[&str], name: &str) -> Option<String> {
+        let alias = match self {
+            Service::MyAnimeList => None,
+            Service::Goodreads => GOODREADS_ALIASES.iter().find(|(alias, _)| *alias == name),
+        };
+        let value = values.iter().find(|value| value.eq_ignore_ascii_case(name));
+        alias
+            .map(|(_, value)| value)
+            .or(value)
+            .map(|value| value.to_string())
+    }
+}
+
+/// The values the user can pick from for a service along with the mapping they picked.
+#[derive(serde::Serialize, Debug)]
+pub struct MappingOptions {
+    pub mapping: ExportMapping,
+    pub statuses: &'static [&'static str],
+    pub types: &'static [&'static str],
+}
+
+/// The stored mapping of `service`, statuses and types that were never mapped getting the
+/// value of the service named like them. An empty value leaves them out on purpose.
+pub fn mapping(store: &impl LibraryStore, service: Service) -> DatabaseResult<ExportMapping> {
+    let stored = store.get_export_mapping(service.name())?;
+    let fill = |stored: Vec<Mapped>, rows: Vec<(i64, String)>, values| {
+        let stored = stored
+            .into_iter()
+            .map(|row| (row.id, row.value))
+            .collect::<HashMap<_, _>>();
+        rows.into_iter()
+            .filter_map(|(id, name)| {
+                let value = stored
+                    .get(&id)
+                    .cloned()
+                    .or_else(|| service.guess(values, &name))?;
+                Some(Mapped { id, value })
+            })
+            .collect()
+    };
+
+    Ok(ExportMapping {
+        service: service.name().to_string(),
+        statuses: fill(
+            stored.statuses,
+            store
+                .get_statuses()?
+                .into_iter()
+                .map(|row| (row.id, row.name))
+                .collect(),
+            service.statuses(),
+        ),
+        types: fill(
+            stored.types,
+            store
+                .get_types()?
+                .into_iter()
+                .map(|row| (row.id, row.name))
+                .collect(),
+            service.types(),
+        ),
+    })
+}
+
+/// The values of a mapping by status and type id, without the ones left out.
+pub struct Values {
+    statuses: HashMap<i64, String>,
+    types: HashMap<i64, String>,
+}
+
+impl Values {
+    pub fn new(mapping: ExportMapping) -> Self {
+        let values = |rows: Vec<Mapped>| {
+            rows.into_iter()
+                .filter(|row| !row.value.is_empty())
+                .map(|row| (row.id, row.value))
+                .collect()
+        };
+        Values {
+            statuses: values(mapping.statuses),
+            types: values(mapping.types),
+        }
+    }
+
+    pub fn status(&self, id: i64) -> Option<&str> {
+        self.statuses.get(&id).map(String::as_str)
+    }
+
+    pub fn r#type(&self, id: i64) -> Option<&str> {
+        self.types.get(&id).map(String::as_str)
+    }
+}
+
+/// A work an export left out and why.
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub id: i64,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    pub exported: usize,
+    pub skipped: Vec<Skipped>,
+}
+
+impl ExportReport {
+    pub fn skip(&mut self, work: &crate::database::Work, reason: String) {
+        self.skipped.push(Skipped {
+            id: work.id,
+            name: work.name.clone(),
+            reason,
+        });
+    }
+
+    pub fn summary(&self) -> String {
+        let skipped = self
+            .skipped
+            .iter()
+            .map(|skipped| format!("{}: {}", skipped.name, skipped.reason));
+        std::iter::once(format!(
+            "Exported {} works, skipped {}.",
+            self.exported,
+            self.skipped.len()
+        ))
+        .chain(skipped)
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::entity::{NewStatus, NewType};
+
+    #[test]
+    fn stored_mapping_overrides_guesses() -> Result<(), Box<dyn std::error::Error>> {
+        let mut store = Database::default();
+        store.open_in_memory()?;
+        let read = store.add(&NewStatus {
+            name: "Read".into(),
+            ..Default::default()
+        })?;
+        let reading = store.add(&NewStatus {
+            name: "reading".into(),
+            ..Default::default()
+        })?;
+        let shelved = store.add(&NewStatus {
+            name: "Shelved".into(),
+            ..Default::default()
+        })?;
+        let book = store.add(&NewType {
+            name: "book".into(),
+        })?;
+
+        let guessed = mapping(&store, Service::Goodreads)?;
+
+        let value = |id, value: &str| Mapped {
+            id,
+            value: value.into(),
+        };
+        assert_eq!(guessed.statuses, vec![value(read, "read")]);
+        assert_eq!(guessed.types, vec![value(book, "Book")]);
+
+        store.set_export_mapping(&ExportMapping {
+            service: Service::Goodreads.name().to_string(),
+            statuses: vec![value(read, ""), value(shelved, "to-read")],
+            types: vec![],
+        })?;
+        let mapped = mapping(&store, Service::Goodreads)?;
+        let values = Values::new(mapped.clone());
+
+        assert_eq!(
+            mapped.statuses,
+            vec![value(read, ""), value(shelved, "to-read")]
+        );
+        assert_eq!(values.status(read), None);
+        assert_eq!(values.status(reading), None);
+        assert_eq!(values.status(shelved), Some("to-read"));
+        assert_eq!(
+            mapping(&store, Service::MyAnimeList)?.statuses,
+            vec![value(reading, "Reading")]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::database::{
+    BulkEdit, BulkFailure, BulkResult, Changes, Creator, DatabaseResult, ExportMapping, ExternalId,
+    Format, StaleWork, Status, Tag, Type, Work,
+};
+use crate::entity::{Column, Insert, NewCreator, NewWork, Sorted, Table, WorkColumn};
+use crate::schedule::{Release, ReleaseSchedule, ScheduleState};
+
+/// Operations the commands need from a library, independent of how it is stored.
+pub trait LibraryStore {
+    /// Runs `f` so that either all of its changes are kept or, if it fails, none of them are.
+    /// Calls can be nested, an inner failure only undoes the inner call.
+    fn transaction<T>(&self, f: impl FnOnce(&Self) -> DatabaseResult<T>) -> DatabaseResult<T>;
+    /// Runs `f` and then undoes all of its changes, whether it succeeded or not.
+    fn dry_run<T>(&self, f: impl FnOnce(&Self) -> DatabaseResult<T>) -> DatabaseResult<T>;
+
+    fn add<R: Insert>(&self, row: &R) -> DatabaseResult<i64>;
+    fn update<C: Column>(&self, id: i64, columns: &[C]) -> DatabaseResult<()>;
+    fn remove(&self, table: Table, id: i64) -> DatabaseResult<()>;
+    fn reorder(&self, table: Sorted, active_id: i64, over_id: i64) -> DatabaseResult<()>;
+
+    fn attach(&self, work_id: i64, creator_id: i64) -> DatabaseResult<()>;
+    fn detach(&self, work_id: i64, creator_id: i64) -> DatabaseResult<()>;
+    fn tag(&self, work_id: i64, tag_id: i64) -> DatabaseResult<()>;
+    fn untag(&self, work_id: i64, tag_id: i64) -> DatabaseResult<()>;
+
+    fn get_works(&self) -> DatabaseResult<Vec<Work>>;
+    fn get_creators(&self) -> DatabaseResult<Vec<Creator>>;
+    fn get_statuses(&self) -> DatabaseResult<Vec<Status>>;
+    fn get_types(&self) -> DatabaseResult<Vec<Type>>;
+    fn get_formats(&self) -> DatabaseResult<Vec<Format>>;
+    fn get_tags(&self) -> DatabaseResult<Vec<Tag>>;
+
+    /// Rows added, updated or removed after `version`, together with the current version to ask
+    /// from next time. Version `0` returns the whole library.
+    fn get_changes_since(&self, version: i64) -> DatabaseResult<Changes>;
+
+    /// Works in update statuses with a staleness threshold that have not been updated within it,
+    /// most overdue first. Works snoozed past `timestamp` are left out.
+    fn get_stale_works(&self, timestamp: i64) -> DatabaseResult<Vec<StaleWork>>;
+
+    fn set_release_schedule(&self, schedule: &ReleaseSchedule) -> DatabaseResult<()>;
+    fn remove_release_schedule(&self, work_id: i64) -> DatabaseResult<()>;
+    fn get_release_schedules(&self, timestamp: i64) -> DatabaseResult<Vec<ScheduleState>>;
+    /// Releases of every scheduled work in `[from, to)` by date, at most
+    /// [`crate::schedule::MAX_RELEASES`] of them per work.
+    fn upcoming_releases(&self, from: i64, to: i64) -> DatabaseResult<Vec<Release>>;
+
+    /// Links a work to its id in another app, replacing whatever work that id was linked to.
+    fn set_external_id(&self, external_id: &ExternalId) -> DatabaseResult<()>;
+    fn get_external_ids(&self) -> DatabaseResult<Vec<ExternalId>>;
+
+    /// Replaces everything stored for the service of `mapping`.
+    fn set_export_mapping(&self, mapping: &ExportMapping) -> DatabaseResult<()>;
+    fn get_export_mapping(&self, service: &str) -> DatabaseResult<ExportMapping>;
+
+    fn add_work(&self, work: &Work) -> DatabaseResult<i64> {
+        self.transaction(|store| {
+            let work_id = store.add(&NewWork {
+                name: work.name.clone(),
+                progress: work.progress.clone(),
+                status: work.status,
+                r#type: work.r#type,
+                format: work.format,
+                updated: work.updated,
+                added: work.added,
+            })?;
+            work.creators
+                .iter()
+                .try_for_each(|creator_id| store.attach(work_id, *creator_id))?;
+            work.tags
+                .iter()
+                .try_for_each(|tag_id| store.tag(work_id, *tag_id))?;
+            Ok(work_id)
+        })
+    }
+
+    fn add_creator(&self, creator: &Creator) -> DatabaseResult<i64> {
+        self.transaction(|store| {
+            let creator_id = store.add(&NewCreator {
+                name: creator.name.clone(),
+            })?;
+            creator
+                .works
+                .iter()
+                .try_for_each(|work_id| store.attach(*work_id, creator_id))?;
+            Ok(creator_id)
+        })
+    }
+
+    /// Applies `edit` to every work in `ids` inside a single transaction. Each work gets its own
+    /// nested transaction so a failing id is rolled back and reported without affecting the others.
+    fn bulk_edit(&self, ids: &[i64], edit: &BulkEdit) -> DatabaseResult<BulkResult> {
+        self.transaction(|store| {
+            let mut result = BulkResult::default();
+
+            for &id in ids {
+                let applied = store.transaction(|store| match edit {
+                    BulkEdit::SetStatus { status, timestamp } => store.update(
+                        id,
+                        &[WorkColumn::Status(*status), WorkColumn::Updated(*timestamp)],
+                    ),
+                    BulkEdit::SetType { r#type } => store.update(id, &[WorkColumn::Type(*r#type)]),
+                    BulkEdit::SetFormat { format } => {
+                        store.update(id, &[WorkColumn::Format(*format)])
+                    }
+                    BulkEdit::Attach { creator_id } => store.attach(id, *creator_id),
+                    BulkEdit::Detach { creator_id } => store.detach(id, *creator_id),
+                    BulkEdit::AddTag { tag_id } => store.tag(id, *tag_id),
+                    BulkEdit::RemoveTag { tag_id } => store.untag(id, *tag_id),
+                    BulkEdit::Remove => store.remove(Table::Works, id),
+                });
+
+                match applied {
+                    Ok(()) => result.succeeded.push(id),
+                    Err(err) => result.failed.push(BulkFailure { id, error: err }),
+                }
+            }
+
+            Ok(result)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::entity::{NewFormat, NewStatus, NewType};
+
+    fn memory() -> Database {
+        let mut database = Database::default();
+        database.open_in_memory().unwrap();
+        database
+    }
+
+    #[test]
+    fn can_add_work_with_creators() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+
+        let status_id = store.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = store.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = store.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let creator_id = store.add(&NewCreator {
+            name: "creator".into(),
+        })?;
+        let work_id = store.add_work(&Work {
+            id: 0,
+            name: "name".into(),
+            progress: "progress".into(),
+            status: status_id,
+            r#type: type_id,
+            format: format_id,
+            updated: 44,
+            added: 44,
+            creators: vec![creator_id],
+            tags: vec![],
+        })?;
+        let works = store.get_works()?;
+
+        assert_eq!(works.len(), 1);
+        assert_eq!(works.first().unwrap().id, work_id);
+        assert_eq!(works.first().unwrap().creators, vec![creator_id]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_add_work_leaves_nothing_behind() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+
+        let status_id = store.add(&NewStatus {
+            name: "status".into(),
+            ..Default::default()
+        })?;
+        let type_id = store.add(&NewType {
+            name: "type".into(),
+        })?;
+        let format_id = store.add(&NewFormat {
+            name: "format".into(),
+        })?;
+        let error_message = store
+            .add_work(&Work {
+                id: 0,
+                name: "name".into(),
+                progress: "progress".into(),
+                status: status_id,
+                r#type: type_id,
+                format: format_id,
+                updated: 44,
+                added: 44,
+                creators: vec![100],
+                tags: vec![],
+            })
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(error_message, "FOREIGN KEY constraint failed");
+        assert_eq!(store.get_works()?.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_add_creator_leaves_nothing_behind() -> Result<(), Box<dyn std::error::Error>> {
+        let store = &memory();
+
+        let error_message = store
+            .add_creator(&Creator {
+                id: 0,
+                name: "name".into(),
+                works: vec![100],
+            })
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(error_message, "FOREIGN KEY constraint failed");
+        assert_eq!(store.get_creators()?.len(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,365 @@
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{Builder, Library};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+const TYPE: &str = "Manga";
+const FORMAT: &str = "Manga";
+const READING: &str = "Reading";
+const PLANNED: &str = "Plan to Read";
+/// External ids are the id of the Mihon or Tachiyomi extension a manga is from and its url there.
+pub const SOURCE: &str = "mihon";
+
+/// What the categories of a backup become.
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Categories {
+    Tags,
+    /// The first category of a manga becomes its status, uncategorized ones get one by whether
+    /// any chapter was read.
+    Statuses,
+}
+
+fn invalid(message: &str) -> Error {
+    Error::Invalid(format!("Not a valid Tachiyomi backup: {message}"))
+}
+
+/// One field of a protobuf message as written on the wire.
+#[derive(Debug, Clone, Copy)]
+enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+fn varint(bytes: &mut &[u8]) -> DatabaseResult<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or_else(|| invalid("truncated"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+fn take<'a>(bytes: &mut &'a [u8], length: usize) -> DatabaseResult<&'a [u8]> {
+    if bytes.len() < length {
+        return Err(invalid("truncated"));
+    }
+    let (field, rest) = bytes.split_at(length);
+    *bytes = rest;
+    Ok(field)
+}
+
+/// The fields of a protobuf message by number, in the order they were written.
+struct Message<'a> {
+    fields: Vec<(u64, Value<'a>)>,
+}
+
+impl<'a> Message<'a> {
+    fn parse(mut bytes: &'a [u8]) -> DatabaseResult<Self> {
+        let mut fields = vec![];
+        while !bytes.is_empty() {
+            let key = varint(&mut bytes)?;
+            let value = match key & 7 {
+                0 => Value::Varint(varint(&mut bytes)?),
+                1 => Value::Fixed64(u64::from_le_bytes(take(&mut bytes, 8)?.try_into().unwrap())),
+                2 => {
+                    let length = varint(&mut bytes)? as usize;
+                    Value::Bytes(take(&mut bytes, length)?)
+                }
+                5 => Value::Fixed32(u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap())),
+                wire_type => return Err(invalid(&format!("unsupported wire type {wire_type}"))),
+            };
+            fields.push((key >> 3, value));
+        }
+        Ok(Message { fields })
+    }
+
+    fn all(&self, number: u64) -> impl Iterator<Item = Value<'a>> + '_ {
+        self.fields
+            .iter()
+            .filter(move |(field, _)| *field == number)
+            .map(|(_, value)| *value)
+    }
+
+    /// The last value of a field, which is the one that counts when it's written more than once.
+    fn get(&self, number: u64) -> Option<Value<'a>> {
+        self.all(number).last()
+    }
+
+    fn int(&self, number: u64) -> Option<i64> {
+        match self.get(number)? {
+            Value::Varint(value) | Value::Fixed64(value) => Some(value as i64),
+            Value::Fixed32(value) => Some(i64::from(value)),
+            Value::Bytes(_) => None,
+        }
+    }
+
+    fn string(&self, number: u64) -> &'a str {
+        match self.get(number) {
+            Some(Value::Bytes(bytes)) => std::str::from_utf8(bytes).unwrap_or_default(),
+            _ => "",
+        }
+    }
+
+    fn float(&self, number: u64) -> Option<f32> {
+        match self.get(number)? {
+            Value::Fixed32(bits) => Some(f32::from_bits(bits)),
+            _ => None,
+        }
+    }
+
+    fn messages(&self, number: u64) -> DatabaseResult<Vec<Message<'a>>> {
+        self.all(number)
+            .map(|value| match value {
+                Value::Bytes(bytes) => Message::parse(bytes),
+                _ => Err(invalid(&format!("field {number} is not a message"))),
+            })
+            .collect()
+    }
+
+    /// A repeated integer field, either packed or written one value at a time.
+    fn ints(&self, number: u64) -> DatabaseResult<Vec<i64>> {
+        let mut ints = vec![];
+        for value in self.all(number) {
+            match value {
+                Value::Bytes(mut bytes) => {
+                    while !bytes.is_empty() {
+                        ints.push(varint(&mut bytes)? as i64);
+                    }
+                }
+                Value::Varint(value) => ints.push(value as i64),
+                _ => return Err(invalid(&format!("field {number} is not an integer"))),
+            }
+        }
+        Ok(ints)
+    }
+}
+
+/// `10.0` as `10` and `10.5` as it is.
+fn chapter(number: f32) -> String {
+    if number.fract() == 0.0 {
+        format!("{number:.0}")
+    } else {
+        number.to_string()
+    }
+}
+
+/// Reads a Tachiyomi or Mihon backup, gzip compressed or not, as a library. Each manga in the
+/// library becomes a work of the `Manga` type and format with its authors and artists as
+/// creators and the highest read chapter as progress. It is `Reading` once a chapter was read
+/// and `Plan to Read` before, its `categories` become tags or statuses. Every work is linked
+/// to its source and URL so importing a later backup finds it again.
+pub fn parse(bytes: &[u8], categories: Categories, now: i64) -> DatabaseResult<Library> {
+    let mut decompressed = vec![];
+    let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+        flate2::read::GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        &decompressed[..]
+    } else {
+        bytes
+    };
+
+    let backup = Message::parse(bytes)?;
+    let mangas = backup.messages(1)?;
+    if mangas.is_empty() && backup.fields.iter().all(|(number, _)| *number != 2) {
+        return Err(invalid("no manga or categories"));
+    }
+    // Manga refer to their categories by the order they're sorted in.
+    let names = backup
+        .messages(2)?
+        .iter()
+        .map(|category| (category.int(2).unwrap_or(0), category.string(1)))
+        .collect::<HashMap<_, _>>();
+
+    let mut builder = Builder::new(now);
+    let r#type = builder.r#type(TYPE);
+    let format = builder.format(FORMAT);
+    for manga in &mangas {
+        let name = manga.string(3).trim();
+        if name.is_empty() || manga.int(100) == Some(0) {
+            continue;
+        }
+
+        let chapters = manga.messages(16)?;
+        let read = chapters
+            .iter()
+            .filter(|chapter| chapter.int(4).unwrap_or(0) != 0);
+        let progress = read
+            .clone()
+            .filter_map(|chapter| chapter.float(9))
+            .filter(|number| *number >= 0.0)
+            .max_by(f32::total_cmp)
+            .map_or_else(|| read.clone().count().to_string(), chapter);
+        let last_read = read
+            .filter_map(|chapter| chapter.int(11))
+            .max()
+            .filter(|at| *at > 0);
+
+        let mut category_names = manga
+            .ints(17)?
+            .into_iter()
+            .filter_map(|order| names.get(&order).copied())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        category_names.dedup();
+        let status = match (categories, category_names.first()) {
+            (Categories::Statuses, Some(category)) => builder.status(category, false),
+            _ if last_read.is_some() || progress != "0" => builder.status(READING, true),
+            _ => builder.status(PLANNED, false),
+        };
+        let tags = match categories {
+            Categories::Tags => category_names
+                .iter()
+                .map(|name| builder.tag(name))
+                .collect(),
+            Categories::Statuses => vec![],
+        };
+
+        let mut creators = vec![];
+        let people = manga.string(5).split(',').chain(manga.string(4).split(','));
+        for person in people.map(str::trim).filter(|person| !person.is_empty()) {
+            let creator = builder.creator(person);
+            if !creators.contains(&creator) {
+                creators.push(creator);
+            }
+        }
+
+        let added = manga.int(13).filter(|added| *added > 0).unwrap_or(now);
+        let work_id = builder.work(Work {
+            id: 0,
+            name: name.to_string(),
+            progress,
+            status,
+            r#type,
+            format,
+            // Chapters keep the time they were last changed in seconds.
+            updated: last_read.map_or(added, |seconds| seconds * 1000),
+            added,
+            creators,
+            tags,
+        });
+        let source = manga.int(1).unwrap_or(0);
+        builder.link(work_id, SOURCE, format!("{source}:{}", manga.string(2)));
+    }
+    Ok(builder.build())
+}
+
+pub fn read(path: &Path, categories: Categories, now: i64) -> DatabaseResult<Library> {
+    parse(&std::fs::read(path)?, categories, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn key(number: u64, wire_type: u64) -> Vec<u8> {
+        encode(number << 3 | wire_type)
+    }
+
+    fn encode(mut value: u64) -> Vec<u8> {
+        let mut bytes = vec![];
+        while value >= 0x80 {
+            bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+        bytes
+    }
+
+    fn int(number: u64, value: u64) -> Vec<u8> {
+        [key(number, 0), encode(value)].concat()
+    }
+
+    fn bytes(number: u64, value: &[u8]) -> Vec<u8> {
+        [key(number, 2), encode(value.len() as u64), value.to_vec()].concat()
+    }
+
+    fn float(number: u64, value: f32) -> Vec<u8> {
+        [key(number, 5), value.to_bits().to_le_bytes().to_vec()].concat()
+    }
+
+    fn chapter(number: f32, read: bool, modified: u64) -> Vec<u8> {
+        bytes(
+            16,
+            &[int(4, read as u64), float(9, number), int(11, modified)].concat(),
+        )
+    }
+
+    fn backup() -> Vec<u8> {
+        let berserk = [
+            int(1, 2),
+            bytes(2, b"/manga/1"),
+            bytes(3, "Berserk".as_bytes()),
+            bytes(4, b"Kentarou Miura"),
+            bytes(5, b"Kentarou Miura, Studio Gaga"),
+            int(13, 1_000),
+            chapter(1.0, true, 5),
+            chapter(2.5, true, 7),
+            chapter(3.0, false, 9),
+            bytes(17, &[encode(1), encode(0)].concat()),
+        ]
+        .concat();
+        let vagabond = [
+            int(1, 2),
+            bytes(2, b"/manga/2"),
+            bytes(3, b"Vagabond"),
+            int(17, 1),
+        ];
+        let history = [bytes(3, b"Only read once"), int(100, 0)].concat();
+        let backup = [
+            bytes(1, &berserk),
+            bytes(1, &vagabond.concat()),
+            bytes(1, &history),
+            bytes(2, &[bytes(1, b"Seinen"), int(2, 0)].concat()),
+            bytes(2, &[bytes(1, b"Favorites"), int(2, 1)].concat()),
+        ]
+        .concat();
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&backup).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_backup() -> Result<(), Box<dyn std::error::Error>> {
+        let library = parse(&backup(), Categories::Tags, 3)?;
+
+        assert_eq!(
+            Library::names(&library.creators),
+            vec!["Kentarou Miura", "Studio Gaga"]
+        );
+        assert_eq!(Library::names(&library.tags), vec!["Favorites", "Seinen"]);
+        assert_eq!(library.status_names(), vec![READING, PLANNED]);
+
+        let [berserk, vagabond] = library.expect_works();
+        assert_eq!(berserk.progress, "2.5");
+        assert_eq!((berserk.added, berserk.updated), (1_000, 7_000));
+        assert_eq!(berserk.creators.len(), 2);
+        assert_eq!(vagabond.progress, "0");
+        assert_eq!((vagabond.added, vagabond.updated), (3, 3));
+        assert_eq!(library.external_ids[1].external_id, "2:/manga/2");
+
+        let library = parse(&backup(), Categories::Statuses, 3)?;
+        assert_eq!(library.status_names(), vec!["Favorites"]);
+        assert!(library.tags.is_empty());
+
+        assert!(matches!(
+            parse(b"", Categories::Tags, 0),
+            Err(Error::Invalid(_))
+        ));
+        assert!(matches!(
+            parse(&[0x0a, 0x05], Categories::Tags, 0),
+            Err(Error::Invalid(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use crate::database::{DatabaseResult, Work};
+use crate::error::Error;
+use crate::library::{earliest, Builder, Library};
+use crate::zip::files;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// External ids are `movie:` or `show:` and the Trakt id.
+pub const SOURCE: &str = "trakt";
+/// Movies are named and typed like the Letterboxd importer does, so both find the same work.
+const MOVIE: (&str, &str) = ("Film", "Film");
+const SHOW: (&str, &str) = ("Show", "TV");
+const WATCHED: &str = "Completed";
+const WATCHING: &str = "Watching";
+const PLANNED: &str = "Plan to Watch";
+
+#[derive(serde::Deserialize)]
+struct Ids {
+    trakt: Option<i64>,
+    slug: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct Media {
+    title: Option<String>,
+    year: Option<i32>,
+    ids: Ids,
+}
+
+/// An entry of `watched-movies.json` or `watched-shows.json`.
+#[derive(serde::Deserialize)]
+struct Watched {
+    plays: Option<i64>,
+    last_watched_at: Option<String>,
+    movie: Option<Media>,
+    show: Option<Media>,
+    #[serde(default)]
+    seasons: Vec<Season>,
+}
+
+#[derive(serde::Deserialize)]
+struct Season {
+    #[serde(default)]
+    episodes: Vec<serde_json::Value>,
+}
+
+/// An entry of a watchlist or ratings file.
+#[derive(serde::Deserialize)]
+struct Listed {
+    r#type: Option<String>,
+    listed_at: Option<String>,
+    rated_at: Option<String>,
+    movie: Option<Media>,
+    show: Option<Media>,
+}
+
+#[derive(Default)]
+struct Title {
+    show: bool,
+    name: String,
+    external_id: String,
+    watched: bool,
+    /// Plays of a movie or episodes of a show watched.
+    progress: i64,
+    added: Option<i64>,
+    last_watched: Option<i64>,
+}
+
+impl Title {
+    fn logged(&mut self, date: Option<i64>) {
+        self.added = earliest(self.added, date);
+    }
+}
+
+fn parse_timestamp(value: &Option<String>) -> Option<i64> {
+    let datetime = chrono::DateTime::parse_from_rfc3339(value.as_deref()?).ok()?;
+    Some(datetime.timestamp_millis())
+}
+
+/// The movies and shows of an export in the order they first come up, by Trakt id.
+#[derive(Default)]
+struct Titles {
+    titles: Vec<Title>,
+    index: HashMap<String, usize>,
+}
+
+impl Titles {
+    fn get(&mut self, movie: Option<Media>, show: Option<Media>) -> Option<&mut Title> {
+        let (is_show, media) = match (movie, show) {
+            (Some(movie), _) => (false, movie),
+            (None, Some(show)) => (true, show),
+            (None, None) => return None,
+        };
+        let title = media.title.filter(|title| !title.trim().is_empty())?;
+        let id = match (media.ids.trakt, media.ids.slug) {
+            (Some(id), _) => id.to_string(),
+            (None, Some(slug)) => slug,
+            (None, None) => format!("{title} {}", media.year.unwrap_or(0)),
+        };
+        let external_id = format!("{}:{id}", if is_show { "show" } else { "movie" });
+
+        let index = *self.index.entry(external_id.clone()).or_insert_with(|| {
+            let name = match media.year {
+                Some(year) => format!("{} ({year})", title.trim()),
+                None => title.trim().to_string(),
+            };
+            self.titles.push(Title {
+                show: is_show,
+                name,
+                external_id,
+                ..Default::default()
+            });
+            self.titles.len() - 1
+        });
+        Some(&mut self.titles[index])
+    }
+}
+
+fn entries<T>(name: &str, text: Option<String>) -> DatabaseResult<Vec<T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let Some(text) = text else {
+        return Ok(vec![]);
+    };
+    serde_json::from_str(&text)
+        .map_err(|err| Error::Invalid(format!("Not a Trakt export: '{name}': {err}")))
+}
+
+/// Reads a Trakt export, `file` giving the contents of each of its files by name, as a library.
+/// Every movie or show that was watched, is on the watchlist or was rated becomes a work named
+/// with its year. Movies are `Film`s like Letterboxd's, `Completed` once watched, with the
+/// number of plays as progress. Shows are of the `Show` type and `TV` format, `Watching` once an
+/// episode was watched, with the number of episodes watched as progress. Anything only on the
+/// watchlist is `Plan to Watch`. Ratings have nowhere to go, only the rating itself shows a
+/// title was watched. Every work is linked to its Trakt id so importing again updates it.
+pub fn parse(
+    file: impl Fn(&str) -> DatabaseResult<Option<String>>,
+    now: i64,
+) -> DatabaseResult<Library> {
+    let mut titles = Titles::default();
+    let mut found = false;
+    let mut read = |name: &str| {
+        let text = file(name)?;
+        found |= text.is_some();
+        DatabaseResult::Ok(text)
+    };
+
+    for name in ["watched-movies.json", "watched-shows.json"] {
+        for entry in entries::<Watched>(name, read(name)?)? {
+            let episodes = entry
+                .seasons
+                .iter()
+                .map(|season| season.episodes.len() as i64)
+                .sum();
+            let Some(title) = titles.get(entry.movie, entry.show) else {
+                continue;
+            };
+            let watched = parse_timestamp(&entry.last_watched_at);
+            title.watched = true;
+            title.progress = if title.show {
+                episodes
+            } else {
+                entry.plays.unwrap_or(1)
+            };
+            title.logged(watched);
+            title.last_watched = title.last_watched.max(watched);
+        }
+    }
+    for name in ["watchlist-movies.json", "watchlist-shows.json"] {
+        for entry in entries::<Listed>(name, read(name)?)? {
+            if !matches!(entry.r#type.as_deref(), None | Some("movie" | "show")) {
+                continue;
+            }
+            if let Some(title) = titles.get(entry.movie, entry.show) {
+                title.logged(parse_timestamp(&entry.listed_at));
+            }
+        }
+    }
+    for name in ["ratings-movies.json", "ratings-shows.json"] {
+        for entry in entries::<Listed>(name, read(name)?)? {
+            if !matches!(entry.r#type.as_deref(), None | Some("movie" | "show")) {
+                continue;
+            }
+            if let Some(title) = titles.get(entry.movie, entry.show) {
+                title.watched = true;
+                title.logged(parse_timestamp(&entry.rated_at));
+            }
+        }
+    }
+    if !found {
+        return Err(Error::Invalid(
+            "Not a Trakt export: no watched, watchlist or ratings".into(),
+        ));
+    }
+
+    let mut builder = Builder::new(now);
+    for title in titles.titles {
+        let (r#type, format) = if title.show { SHOW } else { MOVIE };
+        let (r#type, format) = (builder.r#type(r#type), builder.format(format));
+        let status = match (title.watched, title.show) {
+            (false, _) => builder.status(PLANNED, false),
+            (true, false) => builder.status(WATCHED, false),
+            (true, true) => builder.status(WATCHING, true),
+        };
+        let progress = match (title.watched, title.show) {
+            (false, _) => 0,
+            (true, false) => title.progress.max(1),
+            (true, true) => title.progress,
+        };
+        let added = title.added.unwrap_or(now);
+
+        let work_id = builder.work(Work {
+            id: 0,
+            name: title.name,
+            progress: progress.to_string(),
+            status,
+            r#type,
+            format,
+            updated: title.last_watched.unwrap_or(added),
+            added,
+            creators: vec![],
+            tags: vec![],
+        });
+        builder.link(work_id, SOURCE, title.external_id);
+    }
+    Ok(builder.build())
+}
+
+/// Reads the export zip at `path`, or the folder it was extracted to. Its files may be in a
+/// folder of their own.
+pub fn read(path: &Path, now: i64) -> DatabaseResult<Library> {
+    parse(files(path, true)?, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATCHED_MOVIES: &str = r#"[
+        {"plays": 2, "last_watched_at": "2022-07-07T20:00:00.000Z",
+            "movie": {"title": "Heat", "year": 1995, "ids": {"trakt": 1, "slug": "heat-1995"}}}
+    ]"#;
+    const WATCHED_SHOWS: &str = r#"[
+        {"plays": 5, "last_watched_at": "2023-02-03T20:00:00.000Z",
+            "show": {"title": "Twin Peaks", "year": 1990, "ids": {"trakt": 7}},
+            "seasons": [{"number": 1, "episodes": [{"number": 1}, {"number": 2}]},
+                {"number": 2, "episodes": [{"number": 1}]}]}
+    ]"#;
+    const WATCHLIST_MOVIES: &str = r#"[
+        {"listed_at": "2020-01-01T00:00:00.000Z", "type": "movie",
+            "movie": {"title": "Heat", "year": 1995, "ids": {"trakt": 1}}},
+        {"listed_at": "2020-01-02T00:00:00.000Z", "type": "movie",
+            "movie": {"title": "Ran", "year": 1985, "ids": {"trakt": 2}}}
+    ]"#;
+    const RATINGS_SHOWS: &str = r#"[
+        {"rated_at": "2021-01-01T00:00:00.000Z", "rating": 8, "type": "show",
+            "show": {"title": "Dark", "year": 2017, "ids": {"trakt": 9}}},
+        {"rated_at": "2021-01-01T00:00:00.000Z", "rating": 9, "type": "episode",
+            "show": {"title": "Lost", "year": 2004, "ids": {"trakt": 10}}}
+    ]"#;
+
+    #[test]
+    fn reads_export_archive() -> Result<(), Box<dyn std::error::Error>> {
+        let temp = crate::database::TempFolder::new();
+        let path = temp.join("export.zip");
+        let archive = crate::zip::write(&[
+            ("user/watched-movies.json", WATCHED_MOVIES.as_bytes()),
+            ("user/watched-shows.json", WATCHED_SHOWS.as_bytes()),
+            ("user/watchlist-movies.json", WATCHLIST_MOVIES.as_bytes()),
+            ("user/ratings-shows.json", RATINGS_SHOWS.as_bytes()),
+        ]);
+        std::fs::write(&path, archive)?;
+        let library = read(&path, 9)?;
+
+        let names = library
+            .works
+            .iter()
+            .map(|work| work.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "Heat (1995)",
+                "Twin Peaks (1990)",
+                "Ran (1985)",
+                "Dark (2017)"
+            ]
+        );
+        assert_eq!(library.status_names(), vec![WATCHED, WATCHING, PLANNED]);
+
+        let [heat, peaks, ran, dark] = library.expect_works();
+        assert_eq!(heat.progress, "2");
+        assert_eq!(
+            heat.added,
+            parse_timestamp(&Some("2020-01-01T00:00:00Z".into())).unwrap()
+        );
+        assert_eq!(
+            heat.updated,
+            parse_timestamp(&Some("2022-07-07T20:00:00Z".into())).unwrap()
+        );
+        assert_eq!(peaks.progress, "3");
+        assert_eq!(ran.progress, "0");
+        assert_eq!(dark.status, peaks.status);
+        assert_eq!(library.external_ids[1].external_id, "show:7");
+
+        assert!(matches!(parse(|_| Ok(None), 0), Err(Error::Invalid(_))));
+
+        Ok(())
+    }
+}
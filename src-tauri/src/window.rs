@@ -0,0 +1,134 @@
+use std::{path::PathBuf, sync::Mutex};
+use tauri::{Manager, WebviewWindow, Wry};
+
+use crate::{
+    config::Config,
+    menu::{self, set_menu_state, set_recent_menu},
+    pool::Pool,
+};
+
+pub const MAIN_WINDOW: &str = "main";
+
+/// What a window shows. Each view has a fixed label, so opening the same view again focuses the
+/// existing window and the window state plugin restores its size and position by that label.
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum View {
+    Library { index: u32 },
+    Work { id: i64 },
+    Creator { id: i64 },
+    Update,
+}
+
+impl View {
+    pub fn label(&self) -> String {
+        match self {
+            View::Library { index } => format!("library-{index}"),
+            View::Work { id } => format!("work-{id}"),
+            View::Creator { id } => format!("creator-{id}"),
+            View::Update => "update".to_string(),
+        }
+    }
+
+    /// Route the frontend reads from the location hash to decide what to render.
+    fn route(&self) -> String {
+        match self {
+            View::Library { .. } => "library".to_string(),
+            View::Work { id } => format!("work/{id}"),
+            View::Creator { id } => format!("creator/{id}"),
+            View::Update => "update".to_string(),
+        }
+    }
+
+    fn title(&self) -> String {
+        match self {
+            View::Library { .. } => "zero - Library".to_string(),
+            View::Work { id } => format!("zero - Work [{id}]"),
+            View::Creator { id } => format!("zero - Creator [{id}]"),
+            View::Update => "zero - Update".to_string(),
+        }
+    }
+
+    fn size(&self) -> (f64, f64) {
+        match self {
+            View::Update => (420.0, 640.0),
+            _ => (800.0, 600.0),
+        }
+    }
+}
+
+pub fn open<M: Manager<Wry>>(manager: &M, view: View) -> tauri::Result<WebviewWindow> {
+    let label = view.label();
+    if let Some(window) = manager.get_webview_window(&label) {
+        window.unminimize()?;
+        window.set_focus()?;
+        return Ok(window);
+    }
+
+    let (width, height) = view.size();
+    let recent = manager
+        .state::<Mutex<Config>>()
+        .lock()
+        .unwrap()
+        .recent_databases
+        .clone();
+    let window = tauri::WebviewWindowBuilder::new(
+        manager,
+        label,
+        tauri::WebviewUrl::App(format!("index.html#{}", view.route()).into()),
+    )
+    .title(view.title())
+    .inner_size(width, height)
+    .menu(menu::create_main_menu(manager, &recent)?)
+    .always_on_top(view == View::Update)
+    .build()?;
+
+    set_menu_state(
+        &window.menu().unwrap(),
+        manager.state::<Pool>().path().is_some(),
+    )?;
+
+    Ok(window)
+}
+
+/// The window menu events should act on, the focused one if any.
+pub fn focused(app: &tauri::AppHandle) -> Option<WebviewWindow> {
+    app.webview_windows()
+        .into_values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .or_else(|| app.get_webview_window(MAIN_WINDOW))
+}
+
+/// Enables or disables the database dependent menu items in every open window.
+pub fn set_menus_state(app: &tauri::AppHandle, enabled: bool) -> tauri::Result<()> {
+    app.webview_windows()
+        .values()
+        .filter_map(|window| window.menu())
+        .try_for_each(|menu| set_menu_state(&menu, enabled))
+}
+
+/// Rebuilds the Open Recent submenu in every open window.
+pub fn set_recent_menus(app: &tauri::AppHandle, recent: &[PathBuf]) -> tauri::Result<()> {
+    app.webview_windows()
+        .values()
+        .filter_map(|window| window.menu())
+        .try_for_each(|menu| set_recent_menu(app, &menu, recent))
+}
+
+pub fn event_handler(window: &tauri::Window, event: &tauri::WindowEvent) {
+    // Secondary windows can't do anything without the main one, so they go with it.
+    if let tauri::WindowEvent::CloseRequested { .. } = event {
+        if window.label() == MAIN_WINDOW {
+            window
+                .app_handle()
+                .webview_windows()
+                .into_values()
+                .filter(|other| other.label() != MAIN_WINDOW)
+                .for_each(|other| {
+                    if let Err(err) = other.close() {
+                        log::error!("Failed to close window '{}': {err}.", other.label());
+                    }
+                });
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::database::DatabaseResult;
+use crate::error::Error;
+use std::io::Read;
+use std::path::Path;
+
+const END_SIGNATURE: u32 = 0x0605_4b50;
+const ENTRY_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+/// The end of central directory record and the longest comment it can have.
+const MAX_END_SIZE: usize = 22 + u16::MAX as usize;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+fn invalid(message: &str) -> Error {
+    Error::Invalid(format!("Not a valid zip archive: {message}"))
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> DatabaseResult<u16> {
+    let field = bytes
+        .get(offset..offset + 2)
+        .ok_or_else(|| invalid("truncated"))?;
+    Ok(u16::from_le_bytes([field[0], field[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> DatabaseResult<u32> {
+    let field = bytes
+        .get(offset..offset + 4)
+        .ok_or_else(|| invalid("truncated"))?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    size: usize,
+    offset: usize,
+}
+
+/// The files of a zip archive, enough of the format for the exports other apps write: stored
+/// or deflated files without encryption or ZIP64.
+pub struct Archive {
+    bytes: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl Archive {
+    pub fn new(bytes: Vec<u8>) -> DatabaseResult<Self> {
+        let search = bytes.len().saturating_sub(MAX_END_SIZE);
+        let end = (search..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&offset| u32_at(&bytes, offset).ok() == Some(END_SIGNATURE))
+            .ok_or_else(|| invalid("no end of central directory"))?;
+
+        let count = u16_at(&bytes, end + 10)?;
+        let mut offset = u32_at(&bytes, end + 16)? as usize;
+        let mut entries = vec![];
+        for _ in 0..count {
+            if u32_at(&bytes, offset)? != ENTRY_SIGNATURE {
+                return Err(invalid("bad central directory entry"));
+            }
+            let name_length = u16_at(&bytes, offset + 28)? as usize;
+            let name = bytes
+                .get(offset + 46..offset + 46 + name_length)
+                .ok_or_else(|| invalid("truncated"))?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(&bytes, offset + 10)?,
+                size: u32_at(&bytes, offset + 20)? as usize,
+                offset: u32_at(&bytes, offset + 42)? as usize,
+            });
+            offset += 46
+                + name_length
+                + u16_at(&bytes, offset + 30)? as usize
+                + u16_at(&bytes, offset + 32)? as usize;
+        }
+
+        Ok(Archive { bytes, entries })
+    }
+
+    pub fn read(path: &Path) -> DatabaseResult<Self> {
+        Self::new(std::fs::read(path)?)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+
+    /// The contents of the file called `name`, if the archive has one.
+    pub fn file(&self, name: &str) -> DatabaseResult<Option<Vec<u8>>> {
+        let Some(entry) = self.entries.iter().find(|entry| entry.name == name) else {
+            return Ok(None);
+        };
+
+        if u32_at(&self.bytes, entry.offset)? != LOCAL_SIGNATURE {
+            return Err(invalid("bad local file header"));
+        }
+        let start = entry.offset
+            + 30
+            + u16_at(&self.bytes, entry.offset + 26)? as usize
+            + u16_at(&self.bytes, entry.offset + 28)? as usize;
+        let data = self
+            .bytes
+            .get(start..start + entry.size)
+            .ok_or_else(|| invalid("truncated"))?;
+
+        match entry.method {
+            STORED => Ok(Some(data.to_vec())),
+            DEFLATED => {
+                let mut contents = vec![];
+                flate2::read::DeflateDecoder::new(data).read_to_end(&mut contents)?;
+                Ok(Some(contents))
+            }
+            method => Err(invalid(&format!("unsupported compression method {method}"))),
+        }
+    }
+}
+
+/// Text of an export's files by name, `None` for the ones it doesn't have.
+pub type Files<'a> = Box<dyn Fn(&str) -> DatabaseResult<Option<String>> + 'a>;
+
+/// Reads the text files of the export zip at `path`, or of the folder it was extracted to, by
+/// name. With `nested` a name also finds a file in a folder of the archive, for exports that
+/// put all their files in one.
+pub fn files(path: &Path, nested: bool) -> DatabaseResult<Files<'_>> {
+    if path.is_dir() {
+        return Ok(Box::new(|name| {
+            let file = path.join(name);
+            Ok(if file.is_file() {
+                Some(std::fs::read_to_string(file)?)
+            } else {
+                None
+            })
+        }));
+    }
+
+    let archive = Archive::read(path)?;
+    Ok(Box::new(move |name| {
+        let suffix = format!("/{name}");
+        let entry = archive
+            .names()
+            .find(|entry| *entry == name || nested && entry.ends_with(&suffix));
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        let file = archive.file(entry)?;
+        Ok(file.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+    }))
+}
+
+/// Writes `files` as a zip archive, deflating them. Only for tests of the importers.
+#[cfg(test)]
+pub fn write(files: &[(&str, &[u8])]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut bytes = vec![];
+    let mut directory = vec![];
+    for (name, contents) in files {
+        let compression = flate2::Compression::default();
+        let mut encoder = flate2::write::DeflateEncoder::new(vec![], compression);
+        encoder.write_all(contents).unwrap();
+        let data = encoder.finish().unwrap();
+        let crc = {
+            let mut crc = flate2::Crc::new();
+            crc.update(contents);
+            crc.sum()
+        };
+
+        let mut header = vec![];
+        header.extend(20u16.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(DEFLATED.to_le_bytes());
+        header.extend([0u8; 4]);
+        header.extend(crc.to_le_bytes());
+        header.extend((data.len() as u32).to_le_bytes());
+        header.extend((contents.len() as u32).to_le_bytes());
+        header.extend((name.len() as u16).to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+
+        directory.extend(ENTRY_SIGNATURE.to_le_bytes());
+        directory.extend(20u16.to_le_bytes());
+        directory.extend(&header);
+        directory.extend([0u8; 10]);
+        directory.extend((bytes.len() as u32).to_le_bytes());
+        directory.extend(name.as_bytes());
+
+        bytes.extend(LOCAL_SIGNATURE.to_le_bytes());
+        bytes.extend(&header);
+        bytes.extend(name.as_bytes());
+        bytes.extend(data);
+    }
+
+    let offset = bytes.len() as u32;
+    let size = directory.len() as u32;
+    bytes.extend(directory);
+    bytes.extend(END_SIGNATURE.to_le_bytes());
+    bytes.extend([0u8; 4]);
+    bytes.extend((files.len() as u16).to_le_bytes());
+    bytes.extend((files.len() as u16).to_le_bytes());
+    bytes.extend(size.to_le_bytes());
+    bytes.extend(offset.to_le_bytes());
+    bytes.extend(0u16.to_le_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_written_archive() -> Result<(), Box<dyn std::error::Error>> {
+        let archive = Archive::new(write(&[("a.csv", b"a,b\n1,2\n"), ("dir/b.txt", b"")]))?;
+
+        assert_eq!(
+            archive.names().collect::<Vec<_>>(),
+            vec!["a.csv", "dir/b.txt"]
+        );
+        assert_eq!(archive.file("a.csv")?, Some(b"a,b\n1,2\n".to_vec()));
+        assert_eq!(archive.file("dir/b.txt")?, Some(vec![]));
+        assert_eq!(archive.file("c.csv")?, None);
+        assert!(matches!(
+            Archive::new(b"not a zip".to_vec()),
+            Err(Error::Invalid(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
-import type { Creator, Format, Status, Type, Work } from "./data";
+import type { Backup, BackupSettings, BulkEdit, BulkResult, CalibreColumn, Changes, ColumnMapping, Creator, CsvImportReport, CsvPreview, Duplicates, ErrorCode, ExportMapping, ExportReport, Format, ImportMode, ImportReport, ImportSource, IntegrityReport, MappingOptions, Release, ReleaseSchedule, RestorePreview, ScheduleState, SerializedError, Service, StaleWork, Status, Tag, Type, WindowView, Work } from "./data";
 import { type LogOptions, error as tauriError } from "@tauri-apps/plugin-log";
-import { invoke } from "@tauri-apps/api/core";
+import { type InvokeArgs, invoke } from "@tauri-apps/api/core";
 
 
 
@@ -9,130 +9,298 @@ export function error(message: string, options?: LogOptions) {
 }
 
 
-export function openDatabase(path: string): Promise<void> {
-    return invoke("open_database", { path });
+export type { ErrorCode };
+
+export class ApiError extends Error {
+    readonly code: ErrorCode;
+    readonly context: Record<string, unknown>;
+
+    constructor({ code, message, ...context }: SerializedError) {
+        super(message);
+        this.name = "ApiError";
+        this.code = code;
+        this.context = context;
+    }
+
+    override toString() {
+        return this.message;
+    }
+}
+
+function call<T>(command: string, args?: InvokeArgs): Promise<T> {
+    return invoke<T>(command, args).catch((reason: unknown) => {
+        if (typeof reason === "object" && reason !== null && "code" in reason && "message" in reason) {
+            throw new ApiError(reason as SerializedError);
+        }
+        throw reason;
+    });
+}
+
+
+export function openDatabase(path: string, password?: string): Promise<void> {
+    return call("open_database", { path, password });
 }
 
 export function databasePath(): Promise<string | null> {
-    return invoke("database_path");
+    return call("database_path");
+}
+
+export function lockedDatabase(): Promise<string | null> {
+    return call("locked_database");
+}
+
+export function setPassword(password: string | null): Promise<void> {
+    return call("set_password", { password });
+}
+
+export function listBackups(): Promise<Backup[]> {
+    return call("list_backups");
+}
+
+export function getBackupSettings(): Promise<BackupSettings> {
+    return call("get_backup_settings");
+}
+
+export function setBackupSettings(settings: BackupSettings): Promise<void> {
+    return call("set_backup_settings", { settings });
+}
+
+export function previewRestore(path: string, password: string | null): Promise<RestorePreview> {
+    return call("preview_restore", { path, password });
+}
+
+export function restoreBackup(path: string, password: string | null): Promise<void> {
+    return call("restore_backup", { path, password });
+}
+
+export function checkIntegrity(): Promise<IntegrityReport> {
+    return call("check_integrity");
+}
+
+export function repairDatabase(): Promise<IntegrityReport> {
+    return call("repair_database");
+}
+
+export function exportLibrary(path: string): Promise<void> {
+    return call("export_library", { path });
+}
+
+export function importLibrary(path: string, source: ImportSource, mode: ImportMode, target: string | null, dryRun: boolean): Promise<ImportReport> {
+    return call("import_library", { path, source, mode, target, dryRun });
+}
+
+export function calibreColumns(path: string): Promise<CalibreColumn[]> {
+    return call("calibre_columns", { path });
+}
+
+export function exportCsv(path: string): Promise<void> {
+    return call("export_csv", { path });
+}
+
+export function getExportMapping(service: Service): Promise<MappingOptions> {
+    return call("get_export_mapping", { service });
+}
+
+export function setExportMapping(service: Service, mapping: ExportMapping): Promise<void> {
+    return call("set_export_mapping", { service, mapping });
+}
+
+export function exportMal(path: string, manga: boolean): Promise<ExportReport> {
+    return call("export_mal", { path, manga });
+}
+
+export function exportGoodreads(path: string): Promise<ExportReport> {
+    return call("export_goodreads", { path });
+}
+
+export function previewCsv(path: string): Promise<CsvPreview> {
+    return call("preview_csv", { path });
+}
+
+export function importCsv(path: string, mapping: ColumnMapping, duplicates: Duplicates, dryRun: boolean): Promise<CsvImportReport> {
+    return call("import_csv", { path, mapping, duplicates, dryRun });
 }
 
 export function getWorks(): Promise<Work[]> {
-    return invoke("get_works");
+    return call("get_works");
 }
 
 export function getCreators(): Promise<Creator[]> {
-    return invoke("get_creators");
+    return call("get_creators");
 }
 
 export function updateWorkName(id: number, name: string): Promise<void> {
-    return invoke("update_work_name", { id, name });
+    return call("update_work_name", { id, name });
 }
 
 export function updateWorkProgress(id: number, progress: string, timestamp: number): Promise<void> {
-    return invoke("update_work_progress", { id, progress, timestamp });
+    return call("update_work_progress", { id, progress, timestamp });
 }
 
 export function updateWorkStatus(id: number, status: number, timestamp: number): Promise<void> {
-    return invoke("update_work_status", { id, status, timestamp });
+    return call("update_work_status", { id, status, timestamp });
 }
 
 export function updateWorkType(id: number, type: number): Promise<void> {
-    return invoke("update_work_type", { id, type });
+    return call("update_work_type", { id, type });
 }
 
 export function updateWorkFormat(id: number, format: number): Promise<void> {
-    return invoke("update_work_format", { id, format });
+    return call("update_work_format", { id, format });
 }
 
 export function updateCreatorName(id: number, name: string): Promise<void> {
-    return invoke("update_creator_name", { id, name });
+    return call("update_creator_name", { id, name });
 }
 
 export function addWork(work: Work): Promise<number> {
-    return invoke("add_work", { work });
+    return call("add_work", { work });
 }
 
 export function addCreator(creator: Creator): Promise<number> {
-    return invoke("add_creator", { creator });
+    return call("add_creator", { creator });
 }
 
 export function addStatus(name: string): Promise<number> {
-    return invoke("add_status", { name });
+    return call("add_status", { name });
 }
 
 export function getStatuses(): Promise<Status[]> {
-    return invoke("get_statuses");
+    return call("get_statuses");
 }
 
 export function updateStatusName(id: number, name: string): Promise<void> {
-    return invoke("update_status_name", { id, name });
+    return call("update_status_name", { id, name });
 }
 
 export function updateStatusIsUpdate(id: number, isUpdate: boolean): Promise<void> {
-    return invoke("update_status_is_update", { id, isUpdate });
+    return call("update_status_is_update", { id, isUpdate });
+}
+
+export function updateStatusStaleAfter(id: number, staleAfter: number | null): Promise<void> {
+    return call("update_status_stale_after", { id, staleAfter });
 }
 
 export function reorderStatuses(activeId: number, overId: number): Promise<void> {
-    return invoke("reorder_statuses", { activeId, overId });
+    return call("reorder_statuses", { activeId, overId });
 }
 
 export function removeWork(id: number): Promise<void> {
-    return invoke("remove_work", { id });
+    return call("remove_work", { id });
 }
 
 export function removeCreator(id: number): Promise<void> {
-    return invoke("remove_creator", { id });
+    return call("remove_creator", { id });
 }
 
 export function removeStatus(id: number): Promise<void> {
-    return invoke("remove_status", { id });
+    return call("remove_status", { id });
 }
 
 export function addType(name: string): Promise<number> {
-    return invoke("add_type", { name });
+    return call("add_type", { name });
 }
 
 export function getTypes(): Promise<Type[]> {
-    return invoke("get_types");
+    return call("get_types");
 }
 
 export function removeType(id: number): Promise<void> {
-    return invoke("remove_type", { id });
+    return call("remove_type", { id });
 }
 
 export function updateTypeName(id: number, name: string): Promise<void> {
-    return invoke("update_type_name", { id, name });
+    return call("update_type_name", { id, name });
 }
 
 export function reorderTypes(activeId: number, overId: number): Promise<void> {
-    return invoke("reorder_types", { activeId, overId });
+    return call("reorder_types", { activeId, overId });
 }
 
 export function addFormat(name: string): Promise<number> {
-    return invoke("add_format", { name });
+    return call("add_format", { name });
 }
 
 export function getFormats(): Promise<Format[]> {
-    return invoke("get_formats");
+    return call("get_formats");
 }
 
 export function removeFormat(id: number): Promise<void> {
-    return invoke("remove_format", { id });
+    return call("remove_format", { id });
 }
 
 export function updateFormatName(id: number, name: string): Promise<void> {
-    return invoke("update_format_name", { id, name });
+    return call("update_format_name", { id, name });
 }
 
 export function reorderFormats(activeId: number, overId: number): Promise<void> {
-    return invoke("reorder_formats", { activeId, overId });
+    return call("reorder_formats", { activeId, overId });
 }
 
 export function attach(workId: number, creatorId: number): Promise<void> {
-    return invoke("attach", { workId, creatorId });
+    return call("attach", { workId, creatorId });
 }
 
 export function detach(workId: number, creatorId: number): Promise<void> {
-    return invoke("detach", { workId, creatorId });
+    return call("detach", { workId, creatorId });
+}
+
+export function addTag(name: string): Promise<number> {
+    return call("add_tag", { name });
+}
+
+export function getTags(): Promise<Tag[]> {
+    return call("get_tags");
+}
+
+export function getChangesSince(version: number): Promise<Changes> {
+    return call("get_changes_since", { version });
+}
+
+export function removeTag(id: number): Promise<void> {
+    return call("remove_tag", { id });
+}
+
+export function updateTagName(id: number, name: string): Promise<void> {
+    return call("update_tag_name", { id, name });
+}
+
+export function tag(workId: number, tagId: number): Promise<void> {
+    return call("tag", { workId, tagId });
+}
+
+export function untag(workId: number, tagId: number): Promise<void> {
+    return call("untag", { workId, tagId });
+}
+
+export function bulkEdit(ids: number[], edit: BulkEdit): Promise<BulkResult> {
+    return call("bulk_edit", { ids, edit });
+}
+
+export function snoozeWork(id: number, until: number | null): Promise<void> {
+    return call("snooze_work", { id, until });
+}
+
+export function getStaleWorks(timestamp: number): Promise<StaleWork[]> {
+    return call("get_stale_works", { timestamp });
+}
+
+export function setReleaseSchedule(schedule: ReleaseSchedule): Promise<void> {
+    return call("set_release_schedule", { schedule });
+}
+
+export function removeReleaseSchedule(workId: number): Promise<void> {
+    return call("remove_release_schedule", { workId });
+}
+
+export function getReleaseSchedules(timestamp: number): Promise<ScheduleState[]> {
+    return call("get_release_schedules", { timestamp });
+}
+
+export function upcomingReleases(from: number, to: number): Promise<Release[]> {
+    return call("upcoming_releases", { from, to });
+}
+
+export function openWindow(view: WindowView): Promise<void> {
+    return call("open_window", { view });
 }
@@ -7,7 +7,8 @@ export type Work = {
     format: number,
     updated: number,
     added: number,
-    creators: number[]
+    creators: number[],
+    tags: number[]
 }
 
 export type Creator = {
@@ -19,7 +20,79 @@ export type Creator = {
 export type Status = {
     id: number,
     name: string,
-    isUpdate: boolean
+    isUpdate: boolean,
+    staleAfter: number | null
+}
+
+export type Tag = {
+    id: number,
+    name: string
+}
+
+export type BulkEdit =
+    | { kind: "setStatus", status: number, timestamp: number }
+    | { kind: "setType", type: number }
+    | { kind: "setFormat", format: number }
+    | { kind: "attach", creatorId: number }
+    | { kind: "detach", creatorId: number }
+    | { kind: "addTag", tagId: number }
+    | { kind: "removeTag", tagId: number }
+    | { kind: "remove" }
+
+export type ErrorCode =
+    | "noDatabaseOpen"
+    | "notFound"
+    | "notLinked"
+    | "constraintViolation"
+    | "uniqueNameConflict"
+    | "migration"
+    | "invalid"
+    | "wrongKey"
+    | "io"
+    | "database"
+    | "application"
+
+/** An error the way the backend serializes it, with any context of its code besides. */
+export type SerializedError = {
+    code: ErrorCode,
+    message: string
+} & Record<string, unknown>
+
+export type BulkResult = {
+    succeeded: number[],
+    failed: { id: number, error: SerializedError }[]
+}
+
+export type Changes = {
+    version: number,
+    works: Work[],
+    creators: Creator[],
+    statuses: Status[] | null,
+    types: Type[] | null,
+    formats: Format[] | null,
+    tags: Tag[],
+    removed: {
+        works: number[],
+        creators: number[],
+        statuses: number[],
+        types: number[],
+        formats: number[],
+        tags: number[]
+    }
+}
+
+export type Change = {
+    entity: "work" | "creator" | "status" | "type" | "format" | "tag" | "releaseSchedule",
+    operation: "add" | "update" | "remove" | "reorder" | "attach" | "detach",
+    ids: number[],
+    fields: string[],
+    related?: Change[]
+}
+
+export type StaleWork = {
+    id: number,
+    due: number,
+    overdue: number
 }
 
 export type Type = {
@@ -31,3 +104,163 @@ export type Format = {
     id: number,
     name: string
 }
+
+export type Recurrence =
+    | { kind: "weekly", weekday: number, start: number }
+    | { kind: "interval", days: number, start: number }
+    | { kind: "dates", dates: number[] }
+
+export type ReleaseSchedule = Recurrence & {
+    workId: number,
+    offset: number
+}
+
+export type ScheduleState = ReleaseSchedule & {
+    nextRelease: number | null,
+    released: number,
+    behind: boolean
+}
+
+export type Release = {
+    workId: number,
+    date: number,
+    number: number
+}
+
+export type WindowView =
+    { kind: "library", index: number } |
+    { kind: "work", id: number } |
+    { kind: "creator", id: number } |
+    { kind: "update" }
+
+export type Backup = {
+    path: string,
+    created: number,
+    size: number,
+    rows: RowCounts | null
+}
+
+export type BackupSettings = {
+    folder: string | null,
+    keepLast: number,
+    keepDaily: number,
+    keepWeekly: number,
+    interval: number | null
+}
+
+export type RowCounts = {
+    works: number,
+    creators: number,
+    statuses: number,
+    types: number,
+    formats: number,
+    tags: number
+}
+
+export type RestorePreview = {
+    backup: RowCounts,
+    current: RowCounts,
+    added: number[],
+    removed: number[],
+    changed: number[]
+}
+
+export type Violation = {
+    table: string,
+    rowid: number,
+    parent: string
+}
+
+export type IntegrityReport = {
+    corruption: string[],
+    brokenWorks: Violation[],
+    orphaned: Violation[],
+    unsorted: string[]
+}
+
+export type ImportSource = "library" | "myAnimeList" | "goodreads" | "letterboxd" | "aniList" | "trakt"
+    | { tachiyomi: { categories: "tags" | "statuses" } }
+    | { calibre: { readColumn: string | null } }
+
+export type ImportMode = "add" | "sync" | "progress"
+
+export type ImportCount = {
+    added: number[],
+    matched: number[]
+}
+
+export type ImportReport = {
+    statuses: ImportCount,
+    types: ImportCount,
+    formats: ImportCount,
+    tags: ImportCount,
+    creators: ImportCount,
+    works: ImportCount,
+    updated: number[],
+    schedules: number
+}
+
+export type CalibreColumn = {
+    label: string,
+    name: string,
+    datatype: string
+}
+
+export type Service = "myAnimeList" | "goodreads"
+
+export type Mapped = {
+    id: number,
+    value: string
+}
+
+export type ExportMapping = {
+    service: string,
+    statuses: Mapped[],
+    types: Mapped[]
+}
+
+export type MappingOptions = {
+    mapping: ExportMapping,
+    statuses: string[],
+    types: string[]
+}
+
+export type Skipped = {
+    id: number,
+    name: string,
+    reason: string
+}
+
+export type ExportReport = {
+    exported: number,
+    skipped: Skipped[]
+}
+
+export type ColumnMapping = {
+    name: number,
+    progress: number | null,
+    status: number,
+    type: number,
+    format: number,
+    creators: number | null,
+    tags: number | null,
+    updated: number | null,
+    added: number | null
+}
+
+export type CsvPreview = {
+    headers: string[],
+    rows: string[][],
+    mapping: ColumnMapping | null
+}
+
+export type Duplicates = "skip" | "update" | "create"
+
+export type RowError = {
+    row: number,
+    message: string
+}
+
+export type CsvImportReport = ImportReport & {
+    errors: RowError[]
+}
@@ -1,2 +1,5 @@
 export const OPENED_DATABASE_EVENT = "opened-database";
 export const CLOSED_DATABASE_EVENT = "closed-database";
+export const CHANGED_EVENT = "changed";
+export const PASSWORD_REQUIRED_EVENT = "password-required";
+export const SHOW_BACKUPS_EVENT = "show-backups";
@@ -8,12 +8,14 @@ import useTauriEvent from "./tauri-event-hook";
 
 export function useDatabase() {
     const [path, setPath] = useState<string | null>(null);
+    const [lockedPath, setLockedPath] = useState<string | null>(null);
     const [isLoaded, setIsLoaded] = useState(false);
 
 
     useEffect(() => {
-        api.databasePath().then((value) => {
+        Promise.all([api.databasePath(), api.lockedDatabase()]).then(([value, locked]) => {
             setPath(value);
+            setLockedPath(locked);
         }).catch(async (reason: unknown) => {
             await message(`${reason}`, { title: "Failed to get database path.", kind: "error" });
         }).finally(() => { setIsLoaded(true); });
@@ -25,9 +27,33 @@ export function useDatabase() {
 
     useTauriEvent(event.OPENED_DATABASE_EVENT, (event: Event<string>) => {
         sessionStorage.clear();
+        setLockedPath(null);
         setPath(event.payload);
     });
 
+    useTauriEvent(event.PASSWORD_REQUIRED_EVENT, (event: Event<string>) => {
+        setLockedPath(event.payload);
+    });
+
+
+    function unlock(password: string) {
+        if (lockedPath === null) {
+            return;
+        }
+
+        api.openDatabase(lockedPath, password).catch(async (reason: unknown) => {
+            if (reason instanceof api.ApiError && reason.code === "wrongKey") {
+                await message("Wrong password.", { title: "Failed to open database.", kind: "error" });
+                return;
+            }
+            await message(`${reason}`, { title: "Failed to open database.", kind: "error" });
+        });
+    }
+
+    function cancelUnlock() {
+        setLockedPath(null);
+    }
+
 
-    return { path, isLoaded };
+    return { path, lockedPath, isLoaded, unlock, cancelUnlock };
 }
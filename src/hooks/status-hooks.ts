@@ -31,7 +31,7 @@ export function useStatuses() {
 
     function addStatus(name: string, callback: () => void, cleanUp: () => void) {
         api.addStatus(name).then((id) => {
-            setStatuses([...statuses, { id: id, name: name, isUpdate: false }]);
+            setStatuses([...statuses, { id: id, name: name, isUpdate: false, staleAfter: null }]);
             callback();
         }).catch(async (reason: unknown) => {
             getStatuses();
@@ -1,12 +1,19 @@
+import * as event from "../data/events";
 import NavigationContextProvider, { NavigationContext } from "../contexts/navigation-context";
 import { Tab, Tabs } from "../components/Tabs";
+import { useEffect, useState } from "react";
+import Button from "../components/Button";
 import ConfigurationTab from "./configuration/ConfigurationTab";
+import CreatorPage from "./library/CreatorPage";
 import DataContextProvider from "../contexts/data-context";
+import Dialog from "../components/Dialog";
+import Input from "../components/Input";
 import LibraryTab from "./library/LibraryTab";
 import UpdateTab from "./update/UpdateTab";
+import WorkPage from "./library/WorkPage";
 import { useDatabase } from "../hooks/database-hooks";
-import { useEffect } from "react";
 import useSafeContext from "../hooks/safe-context-hook";
+import useTauriEvent from "../hooks/tauri-event-hook";
 
 
 
@@ -18,9 +25,40 @@ function StartScreen() {
     );
 }
 
+function PasswordDialog({ path, onUnlock, onCancel }: { path: string, onUnlock: (password: string) => void, onCancel: () => void }) {
+    const [password, setPassword] = useState("");
+
+    return (
+        <Dialog isOpen={true} onClose={onCancel}>
+            <form
+                className="p-[10px] flex flex-col gap-[10px]"
+                onSubmit={(event) => { event.preventDefault(); onUnlock(password); }}
+            >
+                <span>{path} is encrypted.</span>
+                <Input
+                    type="password"
+                    placeholder="Password"
+                    value={password}
+                    onChange={(event) => { setPassword(event.target.value); }}
+                    autoFocus
+                />
+                <div className="flex gap-[10px] justify-end">
+                    <Button type="button" onClick={onCancel}>Cancel</Button>
+                    <Button type="submit">Open</Button>
+                </div>
+            </form>
+        </Dialog>
+    );
+}
+
 function MainScreenContent() {
     const { navigationData, navigationDispatch } = useSafeContext(NavigationContext);
 
+    useTauriEvent(event.SHOW_BACKUPS_EVENT, () => {
+        navigationDispatch({ action: "Tab Change", level: "main", tabIndex: 2 });
+        navigationDispatch({ action: "Tab Change", level: "config", tabIndex: 7 });
+    });
+
     return (
         <Tabs
             index={navigationData.tabsIndex.main}
@@ -35,11 +73,24 @@ function MainScreenContent() {
 }
 
 
+function WindowContent() {
+    const [route, id] = window.location.hash.slice(1).split("/");
+
+    switch (route) {
+        case "library": return <LibraryTab />;
+        case "work": return <WorkPage id={Number(id)} />;
+        case "creator": return <CreatorPage id={Number(id)} />;
+        case "update": return <UpdateTab />;
+        default: return <MainScreenContent />;
+    }
+}
+
+
 function MainScreen() {
     return (
         <DataContextProvider>
             <NavigationContextProvider>
-                <MainScreenContent />
+                <WindowContent />
             </NavigationContextProvider>
         </DataContextProvider>
     );
@@ -47,7 +98,7 @@ function MainScreen() {
 
 
 export default function App() {
-    const { path, isLoaded } = useDatabase();
+    const { path, lockedPath, isLoaded, unlock, cancelUnlock } = useDatabase();
 
     useEffect(() => {
         const handleFindEvent = (event: KeyboardEvent) => {
@@ -65,6 +116,7 @@ export default function App() {
     return (
         <>
             {isLoaded && (path === null ? <StartScreen /> : <MainScreen key={path} />)}
+            {lockedPath !== null && <PasswordDialog key={lockedPath} path={lockedPath} onUnlock={unlock} onCancel={cancelUnlock} />}
         </>
     );
 }
@@ -0,0 +1,80 @@
+import * as api from "../../data/api";
+import type { Backup, RestorePreview } from "../../data/data";
+import { confirm, message } from "@tauri-apps/plugin-dialog";
+import { useEffect, useState } from "react";
+import Button from "../../components/Button";
+import Input from "../../components/Input";
+
+
+
+function summary(preview: RestorePreview) {
+    return [
+        `Works: ${preview.backup.works} (currently ${preview.current.works})`,
+        `Creators: ${preview.backup.creators} (currently ${preview.current.creators})`,
+        "",
+        `${preview.added.length} works will be restored, ${preview.removed.length} removed and ${preview.changed.length} changed.`
+    ].join("\n");
+}
+
+function size(bytes: number) {
+    return bytes < 1024 * 1024 ? `${Math.ceil(bytes / 1024)} KB` : `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
+
+export default function BackupsTab() {
+    const [backups, setBackups] = useState<Backup[]>([]);
+    const [passwords, setPasswords] = useState<Record<string, string>>({});
+    const [restored, setRestored] = useState(0);
+
+
+    useEffect(() => {
+        api.listBackups().then(setBackups).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to list backups.", kind: "error" });
+        });
+    }, [restored]);
+
+    function restore(backup: Backup) {
+        const password = backup.rows === null ? passwords[backup.path] ?? null : null;
+        api.previewRestore(backup.path, password).then(async (preview) => {
+            const restore = await confirm(
+                summary(preview),
+                { title: `Restore backup from ${new Date(backup.created).toLocaleString()}?`, okLabel: "Restore", kind: "warning" }
+            );
+            if (!restore) {
+                return;
+            }
+            await api.restoreBackup(backup.path, password);
+            await message("The replaced database was backed up first.", { title: "Backup restored.", kind: "info" });
+            setRestored(restored + 1);
+        }).catch(async (reason: unknown) => {
+            const title = reason instanceof api.ApiError && reason.code === "wrongKey"
+                ? "Wrong password for this backup."
+                : "Failed to restore backup.";
+            await message(`${reason}`, { title, kind: "error" });
+        });
+    }
+
+
+    return (
+        <div className="px-[5px] py-[10px] grow flex flex-col gap-y-[10px] overflow-y-auto">
+            {backups.length === 0 && <span>No backups yet.</span>}
+            {backups.map((backup) => <div
+                key={backup.path}
+                className="p-[5px] flex gap-x-[10px] items-center border border-neutral-700 rounded"
+            >
+                <span className="w-[200px]" title={backup.path}>{new Date(backup.created).toLocaleString()}</span>
+                <span className="w-[80px]">{size(backup.size)}</span>
+                {backup.rows === null
+                    ? <Input
+                        type="password"
+                        className="grow"
+                        value={passwords[backup.path] ?? ""}
+                        onChange={(event) => { setPasswords({ ...passwords, [backup.path]: event.target.value }); }}
+                        placeholder="Taken under another password"
+                    />
+                    : <span className="grow">{backup.rows.works} works, {backup.rows.creators} creators</span>}
+                <Button onClick={() => { restore(backup); }}>Restore</Button>
+            </div>)}
+        </div>
+    );
+}
@@ -0,0 +1,102 @@
+import * as api from "../../data/api";
+import type { CalibreColumn, ImportMode, ImportReport } from "../../data/data";
+import { Option, Select } from "../../components/Select";
+import { message, open } from "@tauri-apps/plugin-dialog";
+import Button from "../../components/Button";
+import { useState } from "react";
+
+
+
+function summary(report: ImportReport) {
+    const count = (label: string, added: number[]) => `${added.length} ${label}`;
+    return [
+        `${count("books added", report.works.added)}, ${report.updated.length} updated, ${report.works.matched.length - report.updated.length} unchanged.`,
+        `New: ${[
+            count("statuses", report.statuses.added),
+            count("formats", report.formats.added),
+            count("creators", report.creators.added),
+            count("tags", report.tags.added)
+        ].join(", ")}.`
+    ].join("\n");
+}
+
+
+export default function CalibreTab() {
+    const [path, setPath] = useState<string | null>(null);
+    const [columns, setColumns] = useState<CalibreColumn[]>([]);
+    const [readColumn, setReadColumn] = useState("");
+    const [mode, setMode] = useState<ImportMode>("sync");
+    const [report, setReport] = useState<ImportReport | null>(null);
+
+
+    function pickFolder() {
+        open({ title: "Import Calibre Library", directory: true }).then(async (path) => {
+            if (path === null) {
+                return;
+            }
+            const columns = await api.calibreColumns(path);
+            setPath(path);
+            setColumns(columns);
+            setReadColumn(columns.find((column) => column.label === "read")?.label ?? "");
+            setReport(null);
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to read Calibre library.", kind: "error" });
+        });
+    }
+
+    function importLibrary(dryRun: boolean) {
+        if (path === null) {
+            return;
+        }
+
+        const source = { calibre: { readColumn: readColumn === "" ? null : readColumn } };
+        api.importLibrary(path, source, mode, null, dryRun).then(async (report) => {
+            setReport(report);
+            if (!dryRun) {
+                await message(summary(report), { title: "Imported Calibre library.", kind: "info" });
+            }
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to import Calibre library.", kind: "error" });
+        });
+    }
+
+
+    return (
+        <div className="px-[5px] py-[10px] grow flex flex-col gap-y-[10px] overflow-y-auto">
+            <div className="flex gap-x-[3px]">
+                <Button onClick={pickFolder}>Import Library...</Button>
+            </div>
+            {path !== null && <>
+                <span className="break-all">{path}</span>
+                <div className="grid grid-cols-[max-content_1fr] gap-x-[10px] gap-y-[3px] items-center">
+                    <label htmlFor="calibre-read-select">Read Status</label>
+                    <select
+                        id="calibre-read-select"
+                        className="px-[3px] py-[2px] border border-neutral-700 rounded focus:outline-none"
+                        value={readColumn}
+                        onChange={(event) => { setReadColumn(event.target.value); setReport(null); }}
+                    >
+                        <option value="">None</option>
+                        {columns.map((column) => <option key={column.label} value={column.label}>
+                            {column.name} (#{column.label})
+                        </option>)}
+                    </select>
+                    <label htmlFor="calibre-mode-select">Imported Books</label>
+                    <Select
+                        id="calibre-mode-select"
+                        value={mode}
+                        onChange={(value) => { setMode(value); setReport(null); }}
+                    >
+                        <Option value="sync">Update</Option>
+                        <Option value="add">Skip</Option>
+                    </Select>
+                </div>
+                <div className="flex gap-x-[3px]">
+                    <Button onClick={() => { importLibrary(true); }}>Preview</Button>
+                    <Button onClick={() => { importLibrary(false); }}>Import</Button>
+                </div>
+            </>}
+            {report !== null && <span className="whitespace-pre-line">{summary(report)}</span>}
+        </div>
+    );
+}
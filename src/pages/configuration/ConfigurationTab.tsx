@@ -1,7 +1,12 @@
 
 import { Tab, Tabs } from "../../components/Tabs";
+import BackupsTab from "./BackupsTab";
+import CalibreTab from "./CalibreTab";
+import CsvTab from "./CsvTab";
+import ExportTab from "./ExportTab";
 import FormatsTab from "./FormatsTab";
 import { NavigationContext } from "../../contexts/navigation-context";
+import PasswordTab from "./PasswordTab";
 import StatusesTab from "./StatusesTab";
 import TypesTab from "./TypesTab";
 import useSafeContext from "../../hooks/safe-context-hook";
@@ -20,6 +25,11 @@ export default function ConfigurationTab() {
             <Tab label="Statuses"><StatusesTab /></Tab>
             <Tab label="Types"><TypesTab /></Tab>
             <Tab label="Formats"><FormatsTab /></Tab>
+            <Tab label="CSV"><CsvTab /></Tab>
+            <Tab label="Calibre"><CalibreTab /></Tab>
+            <Tab label="Export"><ExportTab /></Tab>
+            <Tab label="Password"><PasswordTab /></Tab>
+            <Tab label="Backups"><BackupsTab /></Tab>
         </Tabs>
     );
 }
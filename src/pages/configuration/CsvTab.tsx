@@ -0,0 +1,147 @@
+import * as api from "../../data/api";
+import type { ColumnMapping, CsvImportReport, CsvPreview, Duplicates } from "../../data/data";
+import { Option, Select } from "../../components/Select";
+import { message, open, save } from "@tauri-apps/plugin-dialog";
+import Button from "../../components/Button";
+import { useState } from "react";
+
+
+
+const FIELDS: { key: keyof ColumnMapping, label: string, required: boolean }[] = [
+    { key: "name", label: "Name", required: true },
+    { key: "progress", label: "Progress", required: false },
+    { key: "status", label: "Status", required: true },
+    { key: "type", label: "Type", required: true },
+    { key: "format", label: "Format", required: true },
+    { key: "creators", label: "Creators", required: false },
+    { key: "tags", label: "Tags", required: false },
+    { key: "updated", label: "Updated", required: false },
+    { key: "added", label: "Added", required: false }
+];
+
+type Columns = Record<keyof ColumnMapping, string>;
+
+function toColumns(mapping: ColumnMapping | null): Columns {
+    return Object.fromEntries(FIELDS.map(({ key }) => [key, mapping?.[key]?.toString() ?? ""])) as Columns;
+}
+
+function toMapping(columns: Columns): ColumnMapping | null {
+    if (FIELDS.some(({ key, required }) => required && columns[key] === "")) {
+        return null;
+    }
+    return Object.fromEntries(FIELDS.map(({ key }) => [key, columns[key] === "" ? null : Number(columns[key])])) as ColumnMapping;
+}
+
+function summary(report: CsvImportReport) {
+    const count = (label: string, added: number[]) => `${added.length} ${label}`;
+    return [
+        `${count("works added", report.works.added)}, ${report.updated.length} updated, ${report.works.matched.length - report.updated.length} skipped.`,
+        `New: ${[
+            count("statuses", report.statuses.added),
+            count("types", report.types.added),
+            count("formats", report.formats.added),
+            count("creators", report.creators.added),
+            count("tags", report.tags.added)
+        ].join(", ")}.`
+    ].join("\n");
+}
+
+
+export default function CsvTab() {
+    const [path, setPath] = useState<string | null>(null);
+    const [preview, setPreview] = useState<CsvPreview | null>(null);
+    const [columns, setColumns] = useState<Columns>(toColumns(null));
+    const [duplicates, setDuplicates] = useState<Duplicates>("skip");
+    const [report, setReport] = useState<CsvImportReport | null>(null);
+    const mapping = toMapping(columns);
+
+
+    function exportCsv() {
+        save({ title: "Export Works", defaultPath: "works.csv", filters: [{ name: "CSV", extensions: ["csv"] }] }).then(async (path) => {
+            if (path !== null) {
+                await api.exportCsv(path);
+            }
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to export CSV.", kind: "error" });
+        });
+    }
+
+    function pickFile() {
+        open({ title: "Import Works", filters: [{ name: "CSV", extensions: ["csv"] }] }).then(async (path) => {
+            if (path === null) {
+                return;
+            }
+            const preview = await api.previewCsv(path);
+            setPath(path);
+            setPreview(preview);
+            setColumns(toColumns(preview.mapping));
+            setReport(null);
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to read CSV.", kind: "error" });
+        });
+    }
+
+    function importCsv(dryRun: boolean) {
+        if (path === null || mapping === null) {
+            return;
+        }
+
+        api.importCsv(path, mapping, duplicates, dryRun).then(async (report) => {
+            setReport(report);
+            if (!dryRun) {
+                await message(summary(report), { title: "Imported works.", kind: "info" });
+            }
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to import CSV.", kind: "error" });
+        });
+    }
+
+
+    return (
+        <div className="px-[5px] py-[10px] grow flex flex-col gap-y-[10px] overflow-y-auto">
+            <div className="flex gap-x-[3px]">
+                <Button onClick={exportCsv}>Export Works</Button>
+                <Button onClick={pickFile}>Import Works...</Button>
+            </div>
+            {preview !== null && <>
+                <span className="break-all">{path}</span>
+                <div className="grid grid-cols-[max-content_1fr] gap-x-[10px] gap-y-[3px] items-center">
+                    {FIELDS.map(({ key, label, required }) => <div key={key} className="contents">
+                        <label htmlFor={`csv-${key}-select`}>{label}</label>
+                        <select
+                            id={`csv-${key}-select`}
+                            className="px-[3px] py-[2px] border border-neutral-700 rounded focus:outline-none"
+                            value={columns[key]}
+                            onChange={(event) => { setColumns({ ...columns, [key]: event.target.value }); setReport(null); }}
+                        >
+                            <option value="" disabled={required}>{required ? "Select column" : "None"}</option>
+                            {preview.headers.map((header, index) => <option key={index} value={index.toString()}>
+                                {header || `Column ${index + 1}`}{preview.rows[0]?.[index] ? ` (${preview.rows[0][index]})` : ""}
+                            </option>)}
+                        </select>
+                    </div>)}
+                    <label htmlFor="csv-duplicates-select">Duplicates</label>
+                    <Select
+                        id="csv-duplicates-select"
+                        value={duplicates}
+                        onChange={(value) => { setDuplicates(value); setReport(null); }}
+                    >
+                        <Option value="skip">Skip</Option>
+                        <Option value="update">Update</Option>
+                        <Option value="create">Create</Option>
+                    </Select>
+                </div>
+                <div className="flex gap-x-[3px]">
+                    <Button onClick={() => { importCsv(true); }} disabled={mapping === null}>Preview</Button>
+                    <Button onClick={() => { importCsv(false); }} disabled={mapping === null}>Import</Button>
+                </div>
+            </>}
+            {report !== null && <div className="flex flex-col gap-y-[3px]">
+                <span className="whitespace-pre-line">{summary(report)}</span>
+                {report.errors.map((error) => <span key={error.row} className="text-red-600">
+                    Row {error.row}: {error.message}.
+                </span>)}
+            </div>}
+        </div>
+    );
+}
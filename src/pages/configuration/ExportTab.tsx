@@ -0,0 +1,137 @@
+import * as api from "../../data/api";
+import type { ExportReport, Mapped, MappingOptions, Service } from "../../data/data";
+import { Option, Select } from "../../components/Select";
+import { message, save } from "@tauri-apps/plugin-dialog";
+import { useEffect, useState } from "react";
+import Button from "../../components/Button";
+import { DataContext } from "../../contexts/data-context";
+import useSafeContext from "../../hooks/safe-context-hook";
+
+
+
+function summary(report: ExportReport) {
+    return [
+        `Exported ${report.exported} works, skipped ${report.skipped.length}.`,
+        ...report.skipped.map((skipped) => `${skipped.name}: ${skipped.reason}`)
+    ].join("\n");
+}
+
+
+type MappingListProps = {
+    label: string,
+    rows: { id: number, name: string }[],
+    mapped: Mapped[],
+    values: string[],
+    onChange: (mapped: Mapped[]) => void
+}
+
+function MappingList({ label, rows, mapped, values, onChange }: MappingListProps) {
+    const value = (id: number) => mapped.find((row) => row.id === id)?.value ?? "";
+
+    return (
+        <div className="grid grid-cols-[max-content_1fr] gap-x-[10px] gap-y-[3px] items-center">
+            <span className="col-span-2 font-bold">{label}</span>
+            {rows.map((row) => <div key={row.id} className="contents">
+                <label htmlFor={`export-${label}-${row.id}`}>{row.name}</label>
+                <select
+                    id={`export-${label}-${row.id}`}
+                    className="px-[3px] py-[2px] border border-neutral-700 rounded focus:outline-none"
+                    value={value(row.id)}
+                    onChange={(event) => {
+                        onChange(rows.map(({ id }) => ({
+                            id,
+                            value: id === row.id ? event.target.value : value(id)
+                        })));
+                    }}
+                >
+                    <option value="">Not Exported</option>
+                    {values.map((value) => <option key={value} value={value}>{value}</option>)}
+                </select>
+            </div>)}
+        </div>
+    );
+}
+
+
+export default function ExportTab() {
+    const { statuses, types } = useSafeContext(DataContext);
+    const [service, setService] = useState<Service>("myAnimeList");
+    const [options, setOptions] = useState<MappingOptions | null>(null);
+    const [report, setReport] = useState<ExportReport | null>(null);
+
+    useEffect(() => {
+        api.getExportMapping(service).then(setOptions).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to get export mapping.", kind: "error" });
+        });
+    }, [service, statuses, types]);
+
+
+    function updateMapping(key: "statuses" | "types", mapped: Mapped[]) {
+        if (options === null) {
+            return;
+        }
+
+        const mapping = { ...options.mapping, [key]: mapped };
+        setOptions({ ...options, mapping });
+        setReport(null);
+        api.setExportMapping(service, mapping).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to save export mapping.", kind: "error" });
+        });
+    }
+
+    function exportService(manga: boolean) {
+        const [name, defaultPath] = service === "goodreads"
+            ? ["Goodreads", "goodreads_library_export.csv"]
+            : manga ? ["MyAnimeList Manga", "mangalist.xml"] : ["MyAnimeList Anime", "animelist.xml"];
+        const extension = service === "goodreads" ? "csv" : "xml";
+
+        save({ title: `Export ${name}`, defaultPath, filters: [{ name, extensions: [extension] }] }).then(async (path) => {
+            if (path === null) {
+                return;
+            }
+            const report = service === "goodreads" ? await api.exportGoodreads(path) : await api.exportMal(path, manga);
+            setReport(report);
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: `Failed to export ${name}.`, kind: "error" });
+        });
+    }
+
+
+    return (
+        <div className="px-[5px] py-[10px] grow flex flex-col gap-y-[10px] overflow-y-auto">
+            <div className="flex gap-x-[3px] items-center">
+                <Select
+                    id="export-service-select"
+                    value={service}
+                    onChange={(value) => { setService(value); setOptions(null); setReport(null); }}
+                >
+                    <Option value="myAnimeList">MyAnimeList</Option>
+                    <Option value="goodreads">Goodreads</Option>
+                </Select>
+                {service === "goodreads" ? (
+                    <Button onClick={() => { exportService(false); }}>Export...</Button>
+                ) : <>
+                    <Button onClick={() => { exportService(false); }}>Export Anime...</Button>
+                    <Button onClick={() => { exportService(true); }}>Export Manga...</Button>
+                </>}
+            </div>
+            {report !== null && <span className="whitespace-pre-line">{summary(report)}</span>}
+            {options !== null && <>
+                <MappingList
+                    label="Statuses"
+                    rows={statuses}
+                    mapped={options.mapping.statuses}
+                    values={options.statuses}
+                    onChange={(mapped) => { updateMapping("statuses", mapped); }}
+                />
+                <MappingList
+                    label="Types"
+                    rows={types}
+                    mapped={options.mapping.types}
+                    values={options.types}
+                    onChange={(mapped) => { updateMapping("types", mapped); }}
+                />
+            </>}
+        </div>
+    );
+}
@@ -0,0 +1,61 @@
+import * as api from "../../data/api";
+import { type FormEvent, useState } from "react";
+import Button from "../../components/Button";
+import Input from "../../components/Input";
+import { message } from "@tauri-apps/plugin-dialog";
+
+
+
+export default function PasswordTab() {
+    const [password, setPassword] = useState("");
+    const [confirmation, setConfirmation] = useState("");
+
+
+    function changePassword(value: string | null) {
+        api.setPassword(value).then(async () => {
+            setPassword("");
+            setConfirmation("");
+            await message(value === null ? "Password removed." : "Password set.", { title: "zero", kind: "info" });
+        }).catch(async (reason: unknown) => {
+            await message(`${reason}`, { title: "Failed to set password.", kind: "error" });
+        });
+    }
+
+    function handleSubmit(event: FormEvent<HTMLFormElement>) {
+        event.preventDefault();
+        if (password !== confirmation) {
+            message("Passwords don't match.", { title: "Failed to set password.", kind: "error" }).catch((reason: unknown) => { api.error(`${reason}`); });
+            return;
+        }
+
+        changePassword(password);
+    }
+
+
+    return (
+        <div className="px-[5px] py-[10px] grow flex flex-col gap-y-[10px]">
+            <form onSubmit={handleSubmit} className="flex gap-x-[3px]">
+                <Input
+                    name="password-input"
+                    type="password"
+                    className="grow"
+                    value={password}
+                    onChange={(event) => { setPassword(event.target.value); }}
+                    placeholder="New Password"
+                    required={true}
+                />
+                <Input
+                    name="password-confirmation-input"
+                    type="password"
+                    className="grow"
+                    value={confirmation}
+                    onChange={(event) => { setConfirmation(event.target.value); }}
+                    placeholder="Confirm Password"
+                    required={true}
+                />
+                <Button>Set</Button>
+            </form>
+            <Button className="self-start" onClick={() => { changePassword(null); }}>Remove Password</Button>
+        </div>
+    );
+}
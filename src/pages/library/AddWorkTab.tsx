@@ -112,7 +112,8 @@ export default function AddWorkTab() {
                 format: format.id,
                 updated: timestamp,
                 added: timestamp,
-                creators: addWorkFormData.creators
+                creators: addWorkFormData.creators,
+                tags: []
             };
 
             dataContext.addWork(work, (id) => {